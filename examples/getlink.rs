@@ -0,0 +1,26 @@
+extern crate neli;
+
+use neli::consts::*;
+use neli::nl::Nlmsghdr;
+use neli::rtnl::*;
+use neli::socket::*;
+use neli::{Nl,StreamWriteBuffer};
+
+fn main() {
+    // Responses are parsed as interface information messages
+    let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+
+    // The request only needs the address family as its payload
+    let nlhdr = Nlmsghdr::new(None, Rtm::Getlink, vec![NlmF::Request, NlmF::Dump], None, None,
+                              Rtgenmsg::new(AddrFamily::Packet));
+    let mut mem = StreamWriteBuffer::new_growable(Some(nlhdr.asize()));
+    nlhdr.serialize(&mut mem).unwrap();
+    sock.send(mem, 0).unwrap();
+
+    for msg in sock.iter() {
+        let ifinfomsg = msg.unwrap().nl_payload;
+        let name = ifinfomsg.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Ifname)
+            .map(|rta| rta.get_payload_with::<String>(Some(rta.rta_payload.len())).unwrap());
+        println!("{}: {}", ifinfomsg.ifi_index, name.unwrap_or_default());
+    }
+}
@@ -0,0 +1,32 @@
+extern crate neli;
+
+use neli::consts::*;
+use neli::consts::nl80211::*;
+use neli::genl::Genlmsghdr;
+use neli::nl::Nlmsghdr;
+use neli::socket::*;
+
+fn main() {
+    // The nl80211 family ID is assigned at runtime so messages use the resolved ID as their
+    // type
+    let mut sock = NlSocket::<GenlFamilyId, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>::connect(
+        NlFamily::Generic, None, Vec::new()
+    ).unwrap();
+    let family_id = resolve_genl_family(&mut sock, "nl80211").unwrap();
+
+    // Dump every wireless interface
+    let genlhdr = Genlmsghdr::new(Nl80211Cmd::GetInterface, 0, Vec::new()).unwrap();
+    let req = Nlmsghdr::new(None, family_id, vec![NlmF::Request, NlmF::Dump], None, None,
+                            genlhdr);
+    for msg in sock.dump(req).unwrap() {
+        let mut handle = msg.nl_payload.get_attr_handle();
+        let index = handle.get_payload_with::<u32>(Nl80211Attr::Ifindex, None).unwrap_or(0);
+        let name = handle.get_attribute(Nl80211Attr::Ifname)
+            .map(|attr| attr.get_payload_as_string_lossy())
+            .unwrap_or_default();
+        let iftype = handle.get_payload_with::<u32>(Nl80211Attr::Iftype, None)
+            .map(Nl80211Iftype::from)
+            .unwrap_or(Nl80211Iftype::Unspecified);
+        println!("{}: {} ({})", index, name, iftype);
+    }
+}
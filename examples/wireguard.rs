@@ -0,0 +1,58 @@
+extern crate neli;
+
+use std::env;
+
+use neli::consts::*;
+use neli::consts::wireguard::*;
+use neli::genl::Genlmsghdr;
+use neli::nl::Nlmsghdr;
+use neli::nlattr::Nlattr;
+use neli::socket::*;
+use neli::wireguard::WgDevice;
+
+fn main() {
+    let ifname = env::args().nth(1).unwrap_or_else(|| "wg0".to_string());
+
+    let mut sock = NlSocket::<GenlFamilyId, Genlmsghdr<WgCmd, WgDeviceAttr>>::connect(
+        NlFamily::Generic, None, Vec::new()
+    ).unwrap();
+    let family_id = resolve_genl_family(&mut sock, WG_GENL_NAME).unwrap();
+
+    // GET_DEVICE must be a dump naming the device by index or name
+    let genlhdr = Genlmsghdr::new(WgCmd::GetDevice, WG_GENL_VERSION, vec![
+        Nlattr::new_str_payload(None, WgDeviceAttr::Ifname, &ifname).unwrap(),
+    ]).unwrap();
+    let req = Nlmsghdr::new(None, family_id, vec![NlmF::Request, NlmF::Dump], None, None,
+                            genlhdr);
+
+    // Large devices are split across messages so merge the peers into the first one
+    let mut device: Option<WgDevice> = None;
+    for msg in sock.dump(req).unwrap() {
+        let part = WgDevice::from_attr_handle(msg.nl_payload.get_attr_handle()).unwrap();
+        match device {
+            Some(ref mut dev) => dev.peers.extend(part.peers),
+            None => device = Some(part),
+        };
+    }
+    let device = device.expect("No reply for device");
+
+    println!("interface: {} ({})", device.ifname, device.ifindex);
+    println!("  listening port: {}", device.listen_port);
+    if device.fwmark != 0 {
+        println!("  fwmark: {:#x}", device.fwmark);
+    }
+    for peer in device.peers.iter() {
+        println!("peer: {}", hex(&peer.public_key.0));
+        if let Some(endpoint) = peer.endpoint {
+            println!("  endpoint: {}", endpoint);
+        }
+        for allowed in peer.allowed_ips.iter() {
+            println!("  allowed ip: {}/{}", allowed.addr, allowed.cidr_mask);
+        }
+        println!("  transfer: {} B received, {} B sent", peer.rx_bytes, peer.tx_bytes);
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
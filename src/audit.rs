@@ -0,0 +1,287 @@
+//! Audit messages of `NlFamily::Audit` are not uniform: `AuditMsg::Get` and `AuditMsg::Set`
+//! carry `struct audit_status` while most record types carry free-form text, which can be
+//! received as `AuditText`.
+//!
+//! The kernel always acknowledges audit requests as if `NlmF::Ack` was set and may send the
+//! acknowledgement before the reply, so the helpers of this module wait for both in either
+//! order.
+//!
+//! ```no_run
+//! use neli::audit;
+//! use neli::consts::NlFamily;
+//! use neli::socket::NlSocket;
+//!
+//! let mut sock = NlSocket::<u16, ()>::connect(NlFamily::Audit, None, Vec::new()).unwrap();
+//! let status = audit::get_audit_status(&mut sock).unwrap();
+//! println!("enabled: {}, pid: {}", status.enabled, status.pid);
+//! ```
+
+use std::io::{Read,Write};
+use std::mem;
+use std::str;
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+
+use Nl;
+use consts::{AuditMsg,AuditStatusMask,NlFlags,NlmF,Nlmsg};
+use err::{SerError,DeError,NlError,Nlmsgerr};
+use nl::{Nlmsghdr,RawPayload};
+use socket::{recv_nl,send_nl,NlStream};
+
+/// Audit status (`struct audit_status`) returned by `AuditMsg::Get` and applied by
+/// `AuditMsg::Set` for the fields selected in `mask`
+///
+/// The fields after `backlog` were added in later kernel versions and are 0 when missing.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct AuditStatus {
+    /// Fields to apply with `AuditMsg::Set`
+    pub mask: NlFlags<AuditStatusMask>,
+    /// 1 if auditing is enabled, 2 if the configuration is locked
+    pub enabled: u32,
+    /// Action on critical errors - 0 silent, 1 printk, 2 panic
+    pub failure: u32,
+    /// Process ID of the registered audit daemon
+    pub pid: u32,
+    /// Message rate limit per second
+    pub rate_limit: u32,
+    /// Maximum number of outstanding audit buffers
+    pub backlog_limit: u32,
+    /// Number of lost messages
+    pub lost: u32,
+    /// Number of messages waiting in the queue
+    pub backlog: u32,
+    /// Bitmap of the features supported by the kernel (shares its storage with `version`)
+    pub feature_bitmap: u32,
+    /// Time in jiffies to wait for the backlog to drain
+    pub backlog_wait_time: u32,
+    /// Time in jiffies spent waiting for the backlog to drain
+    pub backlog_wait_time_actual: u32,
+}
+
+impl AuditStatus {
+    /// Length of the struct on the wire
+    pub const LEN: usize = mem::size_of::<u32>() * 11;
+
+    // Length of the struct before `feature_bitmap` was added
+    const MIN_LEN: usize = mem::size_of::<u32>() * 8;
+}
+
+impl Nl for AuditStatus {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.mask.serialize(mem)?;
+        for v in &[self.enabled, self.failure, self.pid, self.rate_limit, self.backlog_limit,
+                   self.lost, self.backlog, self.feature_bitmap, self.backlog_wait_time,
+                   self.backlog_wait_time_actual] {
+            v.serialize(mem)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        mem.read_to_end(&mut bytes)?;
+        if bytes.len() < Self::MIN_LEN {
+            return Err(DeError::new("Audit status is shorter than the oldest layout"));
+        }
+        bytes.resize(bytes.len().max(Self::LEN), 0);
+        let mut mem = StreamReadBuffer::new(bytes.as_slice());
+        Ok(AuditStatus {
+            mask: NlFlags::<AuditStatusMask>::deserialize(&mut mem)?,
+            enabled: u32::deserialize(&mut mem)?,
+            failure: u32::deserialize(&mut mem)?,
+            pid: u32::deserialize(&mut mem)?,
+            rate_limit: u32::deserialize(&mut mem)?,
+            backlog_limit: u32::deserialize(&mut mem)?,
+            lost: u32::deserialize(&mut mem)?,
+            backlog: u32::deserialize(&mut mem)?,
+            feature_bitmap: u32::deserialize(&mut mem)?,
+            backlog_wait_time: u32::deserialize(&mut mem)?,
+            backlog_wait_time_actual: u32::deserialize(&mut mem)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::LEN
+    }
+}
+
+/// Payload of audit records consisting of free-form text such as `AuditMsg::Syscall`
+#[derive(Clone,Debug,PartialEq)]
+pub struct AuditText(pub String);
+
+impl Nl for AuditText {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_all(self.0.as_bytes())?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        mem.read_to_end(&mut bytes)?;
+        // Records may be null terminated or padded with null bytes
+        let end = bytes.iter().rposition(|b| *b != 0).map(|i| i + 1).unwrap_or(0);
+        Ok(AuditText(str::from_utf8(&bytes[..end])?.to_string()))
+    }
+
+    fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Query the audit status over any `NlStream` connected to `NlFamily::Audit` - requires
+/// `CAP_AUDIT_CONTROL`
+pub fn get_audit_status<S>(stream: &mut S) -> Result<AuditStatus, NlError> where S: NlStream {
+    send_nl(stream, Nlmsghdr::new(None, AuditMsg::Get, vec![NlmF::Request, NlmF::Ack], None,
+                                  None, ()))?;
+    let reply = recv_ack_and_reply(stream, Some(AuditMsg::Get))?
+        .ok_or_else(|| NlError::new("No audit status received"))?;
+    Ok(AuditStatus::from_bytes(&reply.0)?)
+}
+
+/// Register the process with ID `pid` as the audit daemon over any `NlStream` connected to
+/// `NlFamily::Audit` - audit records are then sent to the socket that made the request. A
+/// `pid` of 0 unregisters the daemon.
+pub fn set_audit_pid<S>(stream: &mut S, pid: u32) -> Result<(), NlError> where S: NlStream {
+    let status = AuditStatus { mask: NlFlags::from(AuditStatusMask::Pid), pid,
+                               ..Default::default() };
+    send_nl(stream, Nlmsghdr::new(None, AuditMsg::Set, vec![NlmF::Request, NlmF::Ack], None,
+                                  None, status))?;
+    recv_ack_and_reply(stream, None)?;
+    Ok(())
+}
+
+// Receive until the acknowledgement and the reply of type `reply`, if any, have arrived in
+// either order - other messages such as records sent to the audit daemon are skipped
+fn recv_ack_and_reply<S>(stream: &mut S, reply: Option<AuditMsg>)
+        -> Result<Option<RawPayload>, NlError> where S: NlStream {
+    let reply = reply.map(u16::from);
+    let mut payload = None;
+    let mut acked = false;
+    while !acked || (reply.is_some() && payload.is_none()) {
+        let msg = recv_nl::<S, u16, RawPayload>(stream, None)?;
+        if msg.nl_type == u16::from(Nlmsg::Error) {
+            Nlmsgerr::<u16>::deserialize_with_flags(
+                &mut StreamReadBuffer::new(&msg.nl_payload.0), &msg.nl_flags
+            )?.into_ack()?;
+            acked = true;
+        } else if Some(msg.nl_type) == reply {
+            payload = Some(msg.nl_payload);
+        }
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::VecDeque;
+    use std::io;
+    use std::process;
+
+    use consts::NlFamily;
+    use socket::NlSocket;
+
+    struct MockStream {
+        sent: Vec<Vec<u8>>,
+        responses: VecDeque<Vec<u8>>,
+    }
+
+    impl NlStream for MockStream {
+        fn send(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+            self.sent.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+            match self.responses.pop_front() {
+                Some(resp) => {
+                    buf[..resp.len()].copy_from_slice(&resp);
+                    Ok(resp.len())
+                },
+                None => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            }
+        }
+    }
+
+    // Acknowledgement with the header of the request as sent with `NlmF::Capped`
+    fn ack() -> Vec<u8> {
+        let mut payload = 0i32.serialize_to_vec().unwrap();
+        payload.extend(Nlmsghdr::new(None, AuditMsg::Set, vec![NlmF::Request, NlmF::Ack], None,
+                                     None, ()).serialize_to_vec().unwrap());
+        Nlmsghdr::new(None, Nlmsg::Error, vec![NlmF::Capped], None, None, RawPayload(payload))
+            .serialize_to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_audit_status() {
+        let status = AuditStatus {
+            mask: NlFlags::from(vec![AuditStatusMask::Pid, AuditStatusMask::BacklogLimit]),
+            pid: 42,
+            backlog_limit: 64,
+            feature_bitmap: 0x7f,
+            ..Default::default()
+        };
+        let bytes = status.serialize_to_vec().unwrap();
+        assert_eq!(bytes.len(), AuditStatus::LEN);
+        assert_eq!(&bytes[..4], &0x14u32.serialize_to_vec().unwrap()[..]);
+        assert_eq!(AuditStatus::from_bytes(&bytes).unwrap(), status);
+
+        // Replies of older kernels end after `backlog`
+        let short = AuditStatus::from_bytes(&bytes[..AuditStatus::MIN_LEN]).unwrap();
+        assert_eq!(short.pid, 42);
+        assert_eq!(short.feature_bitmap, 0);
+        assert!(AuditStatus::from_bytes(&bytes[..AuditStatus::MIN_LEN - 4]).is_err());
+    }
+
+    #[test]
+    fn test_audit_text() {
+        let text = AuditText::from_bytes(b"arch=c000003e syscall=59 success=yes\0\0\0").unwrap();
+        assert_eq!(text.0, "arch=c000003e syscall=59 success=yes");
+    }
+
+    #[test]
+    fn test_audit_pid_mock() {
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        // A record for the audit daemon arriving before the acknowledgement is skipped
+        stream.responses.push_back(Nlmsghdr::new(None, AuditMsg::User, Vec::new(), None, None,
+                                                 AuditText("msg=test".to_string()))
+            .serialize_to_vec().unwrap());
+        stream.responses.push_back(ack());
+        set_audit_pid(&mut stream, 42).unwrap();
+        assert!(stream.responses.is_empty());
+
+        let req = Nlmsghdr::<AuditMsg, AuditStatus>::from_bytes(&stream.sent[0]).unwrap();
+        assert_eq!(req.nl_type, AuditMsg::Set);
+        assert_eq!(req.nl_payload.mask, NlFlags::from(AuditStatusMask::Pid));
+        assert_eq!(req.nl_payload.pid, 42);
+
+        // The acknowledgement may also arrive before the reply
+        let status = AuditStatus { enabled: 1, pid: 42, backlog_limit: 64, ..Default::default() };
+        stream.responses.push_back(ack());
+        stream.responses.push_back(Nlmsghdr::new(None, AuditMsg::Get, Vec::new(), None, None,
+                                                 status.clone()).serialize_to_vec().unwrap());
+        assert_eq!(get_audit_status(&mut stream).unwrap(), status);
+    }
+
+    // Requires CAP_AUDIT_CONTROL in the initial network namespace and replaces the running
+    // audit daemon - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_audit_pid() {
+        let mut sock = NlSocket::<u16, ()>::connect(NlFamily::Audit, None, Vec::new()).unwrap();
+        let status = get_audit_status(&mut sock).unwrap();
+        assert!(status.backlog_limit > 0);
+
+        set_audit_pid(&mut sock, process::id()).unwrap();
+        assert_eq!(get_audit_status(&mut sock).unwrap().pid, process::id());
+        set_audit_pid(&mut sock, 0).unwrap();
+        assert_eq!(get_audit_status(&mut sock).unwrap().pid, 0);
+    }
+}
@@ -0,0 +1,365 @@
+//! Messages of `NlFamily::Connector` start with `struct cn_msg` addressing a kernel service by
+//! its index and value, followed by a payload bounded by the length field of the header.
+//!
+//! The proc connector broadcasts fork, exec and exit events of all processes once a socket
+//! bound to `CN_IDX_PROC` has sent `ProcCnMcastOp::Listen`. This requires `CAP_NET_ADMIN` in
+//! the initial user and PID namespaces.
+//!
+//! ```no_run
+//! use neli::connector::{self,ProcEventData};
+//!
+//! let mut sock = connector::connect_proc_events().unwrap();
+//! loop {
+//!     match connector::recv_proc_event(&mut sock).unwrap().data {
+//!         ProcEventData::Exec { process_pid, .. } => println!("exec {}", process_pid),
+//!         ProcEventData::Exit { process_pid, exit_code, .. } => {
+//!             println!("exit {} ({})", process_pid, exit_code)
+//!         },
+//!         _ => (),
+//!     }
+//! }
+//! ```
+
+use std::io::{Read,Write};
+use std::mem;
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+
+use Nl;
+use consts::{NlFamily,Nlmsg,ProcCnMcastOp,ProcEventWhat};
+use err::{SerError,DeError,NlError};
+use nl::Nlmsghdr;
+use socket::{recv_nl,send_nl,NlSocket,NlStream};
+
+/// Index of the proc connector (`CN_IDX_PROC`) - also the multicast group of its events
+pub const CN_IDX_PROC: u32 = 1;
+
+/// Value of the proc connector (`CN_VAL_PROC`)
+pub const CN_VAL_PROC: u32 = 1;
+
+/// Length of the event data union of `struct proc_event`
+pub const PROC_EVENT_DATA_LEN: usize = 24;
+
+/// Connector message header (`struct cn_msg`) followed by its payload
+///
+/// The length field of the header is computed from the size of the payload when
+/// serializing and bounds the payload when deserializing.
+#[derive(Clone,Debug,PartialEq)]
+pub struct CnMsg<P> {
+    /// Index of the addressed connector service (`cb_id.idx`)
+    pub idx: u32,
+    /// Value of the addressed connector service (`cb_id.val`)
+    pub val: u32,
+    /// Sequence number of the message
+    pub seq: u32,
+    /// Acknowledgement number of the message
+    pub ack: u32,
+    /// Flags of the message
+    pub flags: u16,
+    /// Payload of the message
+    pub payload: P,
+}
+
+impl<P> CnMsg<P> {
+    /// Length of the header preceding the payload
+    pub const HEADER_LEN: usize = mem::size_of::<u32>() * 4 + mem::size_of::<u16>() * 2;
+
+    /// Create a message addressed to the given connector service
+    pub fn new(idx: u32, val: u32, seq: u32, payload: P) -> Self {
+        CnMsg { idx, val, seq, ack: 0, flags: 0, payload }
+    }
+}
+
+impl<P> Nl for CnMsg<P> where P: Nl {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        if self.payload.size() > u16::max_value() as usize {
+            return Err(SerError::new("Payload does not fit the length of a connector message"));
+        }
+        self.idx.serialize(mem)?;
+        self.val.serialize(mem)?;
+        self.seq.serialize(mem)?;
+        self.ack.serialize(mem)?;
+        (self.payload.size() as u16).serialize(mem)?;
+        self.flags.serialize(mem)?;
+        self.payload.serialize(mem)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let idx = u32::deserialize(mem)?;
+        let val = u32::deserialize(mem)?;
+        let seq = u32::deserialize(mem)?;
+        let ack = u32::deserialize(mem)?;
+        let len = u16::deserialize(mem)?;
+        let flags = u16::deserialize(mem)?;
+        let mut payload = vec![0; len as usize];
+        mem.read_exact(payload.as_mut_slice())?;
+        Ok(CnMsg {
+            idx,
+            val,
+            seq,
+            ack,
+            flags,
+            payload: P::deserialize(&mut StreamReadBuffer::new(payload.as_slice()))?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::HEADER_LEN + self.payload.size()
+    }
+}
+
+/// Data of an event of the proc connector - the members of the `event_data` union of
+/// `struct proc_event` that are decoded
+#[derive(Clone,Debug,PartialEq)]
+pub enum ProcEventData {
+    /// Acknowledgement of a subscription message with the resulting error code
+    Ack {
+        /// Error code of the subscription - 0 on success
+        err: u32,
+    },
+    /// A process forked
+    Fork {
+        /// Process ID of the parent
+        parent_pid: u32,
+        /// Thread group ID of the parent
+        parent_tgid: u32,
+        /// Process ID of the child
+        child_pid: u32,
+        /// Thread group ID of the child
+        child_tgid: u32,
+    },
+    /// A process executed a new program
+    Exec {
+        /// Process ID of the process
+        process_pid: u32,
+        /// Thread group ID of the process
+        process_tgid: u32,
+    },
+    /// A process exited
+    Exit {
+        /// Process ID of the process
+        process_pid: u32,
+        /// Thread group ID of the process
+        process_tgid: u32,
+        /// Exit code as returned by `wait`
+        exit_code: u32,
+        /// Signal sent to the parent on exit
+        exit_signal: u32,
+        /// Process ID of the parent
+        parent_pid: u32,
+        /// Thread group ID of the parent
+        parent_tgid: u32,
+    },
+    /// Any other event with the raw bytes of its data
+    Other {
+        /// Type of the event
+        what: ProcEventWhat,
+        /// Raw bytes of the event data
+        data: Vec<u8>,
+    },
+}
+
+impl ProcEventData {
+    /// Type of the event
+    pub fn what(&self) -> ProcEventWhat {
+        match *self {
+            ProcEventData::Ack { .. } => ProcEventWhat::None,
+            ProcEventData::Fork { .. } => ProcEventWhat::Fork,
+            ProcEventData::Exec { .. } => ProcEventWhat::Exec,
+            ProcEventData::Exit { .. } => ProcEventWhat::Exit,
+            ProcEventData::Other { ref what, .. } => *what,
+        }
+    }
+
+    fn fields(&self) -> Vec<u32> {
+        match *self {
+            ProcEventData::Ack { err } => vec![err],
+            ProcEventData::Fork { parent_pid, parent_tgid, child_pid, child_tgid } => {
+                vec![parent_pid, parent_tgid, child_pid, child_tgid]
+            },
+            ProcEventData::Exec { process_pid, process_tgid } => vec![process_pid, process_tgid],
+            ProcEventData::Exit { process_pid, process_tgid, exit_code, exit_signal, parent_pid,
+                                  parent_tgid } => {
+                vec![process_pid, process_tgid, exit_code, exit_signal, parent_pid, parent_tgid]
+            },
+            ProcEventData::Other { .. } => Vec::new(),
+        }
+    }
+}
+
+/// Event of the proc connector (`struct proc_event`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct ProcEvent {
+    /// CPU the event occurred on
+    pub cpu: u32,
+    /// Time of the event in nanoseconds since boot
+    pub timestamp_ns: u64,
+    /// Data of the event
+    pub data: ProcEventData,
+}
+
+impl Nl for ProcEvent {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        let mut data = Vec::new();
+        match self.data {
+            ProcEventData::Other { data: ref bytes, .. } => data.extend_from_slice(bytes),
+            ref event => for field in event.fields() {
+                data.write_all(&field.serialize_to_vec()?)?;
+            },
+        };
+        if data.len() < PROC_EVENT_DATA_LEN {
+            data.resize(PROC_EVENT_DATA_LEN, 0);
+        }
+        self.data.what().serialize(mem)?;
+        self.cpu.serialize(mem)?;
+        self.timestamp_ns.serialize(mem)?;
+        mem.write_all(data.as_slice())?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let what = ProcEventWhat::deserialize(mem)?;
+        let cpu = u32::deserialize(mem)?;
+        let timestamp_ns = u64::deserialize(mem)?;
+        let mut data = Vec::new();
+        mem.read_to_end(&mut data)?;
+        let event = {
+            let mut mem = StreamReadBuffer::new(data.as_slice());
+            match what {
+                ProcEventWhat::None => ProcEventData::Ack { err: u32::deserialize(&mut mem)? },
+                ProcEventWhat::Fork => ProcEventData::Fork {
+                    parent_pid: u32::deserialize(&mut mem)?,
+                    parent_tgid: u32::deserialize(&mut mem)?,
+                    child_pid: u32::deserialize(&mut mem)?,
+                    child_tgid: u32::deserialize(&mut mem)?,
+                },
+                ProcEventWhat::Exec => ProcEventData::Exec {
+                    process_pid: u32::deserialize(&mut mem)?,
+                    process_tgid: u32::deserialize(&mut mem)?,
+                },
+                ProcEventWhat::Exit => ProcEventData::Exit {
+                    process_pid: u32::deserialize(&mut mem)?,
+                    process_tgid: u32::deserialize(&mut mem)?,
+                    exit_code: u32::deserialize(&mut mem)?,
+                    exit_signal: u32::deserialize(&mut mem)?,
+                    parent_pid: u32::deserialize(&mut mem)?,
+                    parent_tgid: u32::deserialize(&mut mem)?,
+                },
+                what => ProcEventData::Other { what, data: data.clone() },
+            }
+        };
+        Ok(ProcEvent { cpu, timestamp_ns, data: event })
+    }
+
+    fn size(&self) -> usize {
+        let data_len = match self.data {
+            ProcEventData::Other { ref data, .. } => data.len(),
+            _ => 0,
+        };
+        mem::size_of::<u32>() * 2 + mem::size_of::<u64>() + data_len.max(PROC_EVENT_DATA_LEN)
+    }
+}
+
+/// Create the subscription message of the proc connector for the given operation
+pub fn proc_mcast_msg(op: ProcCnMcastOp) -> Nlmsghdr<Nlmsg, CnMsg<ProcCnMcastOp>> {
+    Nlmsghdr::new(None, Nlmsg::Done, Vec::new(), None, None,
+                  CnMsg::new(CN_IDX_PROC, CN_VAL_PROC, 0, op))
+}
+
+/// Create a socket subscribed to the events of the proc connector - receive them with
+/// `recv_proc_event`
+pub fn connect_proc_events() -> Result<NlSocket<Nlmsg, CnMsg<ProcEvent>>, NlError> {
+    let mut sock = NlSocket::connect(NlFamily::Connector, None, vec![CN_IDX_PROC])?;
+    send_nl(&mut sock, proc_mcast_msg(ProcCnMcastOp::Listen))?;
+    Ok(sock)
+}
+
+/// Receive and parse an event of the proc connector from any `NlStream` subscribed with
+/// `ProcCnMcastOp::Listen`
+pub fn recv_proc_event<S>(stream: &mut S) -> Result<ProcEvent, NlError> where S: NlStream {
+    let msg = recv_nl::<S, Nlmsg, CnMsg<ProcEvent>>(stream, None)?;
+    Ok(msg.nl_payload.payload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::process::Command;
+    use std::time::{Duration,Instant};
+
+    #[test]
+    fn test_cn_msg_len() {
+        let msg = proc_mcast_msg(ProcCnMcastOp::Listen);
+        let bytes = msg.serialize_to_vec().unwrap();
+        assert_eq!(bytes.len(), 16 + CnMsg::<()>::HEADER_LEN + 4);
+        assert_eq!(&bytes[32..34], &4u16.serialize_to_vec().unwrap()[..]);
+
+        // The payload is bounded by the length of the connector header, not the datagram
+        let mut trailing = msg.nl_payload.serialize_to_vec().unwrap();
+        trailing.extend_from_slice(&[0xff; 8]);
+        let parsed = CnMsg::<ProcCnMcastOp>::deserialize(
+            &mut StreamReadBuffer::new(trailing.as_slice())
+        ).unwrap();
+        assert_eq!(parsed, msg.nl_payload);
+    }
+
+    #[test]
+    fn test_proc_event() {
+        let event = ProcEvent {
+            cpu: 3,
+            timestamp_ns: 123_456_789,
+            data: ProcEventData::Exit {
+                process_pid: 42,
+                process_tgid: 42,
+                exit_code: 256,
+                exit_signal: 17,
+                parent_pid: 1,
+                parent_tgid: 1,
+            },
+        };
+        let msg = CnMsg::new(CN_IDX_PROC, CN_VAL_PROC, 7, event);
+        let bytes = msg.serialize_to_vec().unwrap();
+        assert_eq!(bytes.len(), CnMsg::<()>::HEADER_LEN + 40);
+        assert_eq!(CnMsg::<ProcEvent>::from_bytes(&bytes).unwrap(), msg);
+
+        let comm = ProcEvent {
+            cpu: 0,
+            timestamp_ns: 0,
+            data: ProcEventData::Other { what: ProcEventWhat::Comm, data: vec![1; 24] },
+        };
+        assert_eq!(ProcEvent::from_bytes(&comm.serialize_to_vec().unwrap()).unwrap(), comm);
+        assert!(ProcEvent::from_bytes(&bytes[CnMsg::<()>::HEADER_LEN..36]).is_err());
+    }
+
+    // Requires CAP_NET_ADMIN in the initial PID namespace - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_recv_proc_event() {
+        let mut sock = connect_proc_events().unwrap();
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        assert!(child.wait().unwrap().success());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let now = Instant::now();
+            assert!(now < deadline, "Timed out waiting for the exit event of the child");
+            assert!(sock.poll_readable(Some(deadline - now)).unwrap());
+            match recv_proc_event(&mut sock).unwrap().data {
+                ProcEventData::Ack { err } => assert_eq!(err, 0),
+                ProcEventData::Exit { process_pid, exit_code, .. } if process_pid == pid => {
+                    assert_eq!(exit_code, 0);
+                    break;
+                },
+                _ => (),
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! The items in this module are created by macros, which give them the traits necessary to be
 //! serialized into Netlink compatible types. The macros are exported - you can use them too!
-//! See `impl_var`, `impl_trait`, and `impl_var_trait`.
+//! See `impl_var`, `impl_trait`, `impl_var_trait` and `impl_nl_flag`.
 //!
 //! Note that most of these constants come from the Linux kernel headers, which can be found
 //! in `/usr/include/linux` on many distros. You can also see `man 3 netlink`, `man 7 netlink`,
@@ -33,7 +33,10 @@
 
 use buffering::copy::{StreamReadBuffer, StreamWriteBuffer};
 use libc;
+use std::fmt::{self,Debug};
 use std::mem;
+use std::ops::{BitAnd,BitOr,Not,Shl};
+use std::vec;
 
 use err::{DeError, SerError};
 use Nl;
@@ -56,6 +59,29 @@ macro_rules! impl_var_base {
             }
         }
 
+        impl $name {
+            /// Convert a value like `From` but fail with `DeError::UnknownConstant` for values
+            /// that have no variant instead of returning `UnrecognizedVariant` - the standard
+            /// `TryFrom` is already implemented through `From` and never fails
+            pub fn try_from_known(v: $ty) -> Result<Self, DeError> {
+                match $name::from(v) {
+                    $name::UnrecognizedVariant(i) => Err(DeError::UnknownConstant {
+                        type_name: stringify!($name),
+                        value: i as i64,
+                    }),
+                    var => Ok(var),
+                }
+            }
+
+            /// Returns `true` for `UnrecognizedVariant`
+            pub fn is_unrecognized(&self) -> bool {
+                match *self {
+                    $name::UnrecognizedVariant(_) => true,
+                    _ => false,
+                }
+            }
+        }
+
         impl From<$name> for $ty {
             fn from(v: $name) -> Self {
                 match v {
@@ -76,25 +102,92 @@ macro_rules! impl_var_base {
             }
         }
 
+        /// Variants are ordered by their value - variants sharing a value are ordered as
+        /// declared, followed by `UnrecognizedVariant`
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                fn position(v: &$name) -> usize {
+                    let mut pos = 0;
+                    if let $name::$var_def = *v {
+                        return pos;
+                    }
+                    $(
+                        pos += 1;
+                        if let $name::$var = *v {
+                            return pos;
+                        }
+                    )*
+                    pos + 1
+                }
+                (<$ty>::from(self), position(self)).cmp(&(<$ty>::from(other), position(other)))
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
         impl Nl for $name {
             type SerIn = ();
             type DeIn = ();
 
             fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
-                let v: $ty = self.clone().into();
+                let v: $ty = self.into();
                 v.serialize(mem)
             }
 
             fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError>
                     where T: AsRef<[u8]> {
                 let v: $ty = Nl::deserialize(mem)?;
-                Ok(v.into())
+                if $crate::is_strict_deserialization() {
+                    $name::try_from_known(v)
+                } else {
+                    Ok(v.into())
+                }
             }
 
             fn size(&self) -> usize {
                 mem::size_of::<$ty>()
             }
         }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match *self {
+                    $name::$var_def => f.write_str(stringify!($var_def)),
+                    $( $name::$var => f.write_str(stringify!($var)), )*
+                    $name::UnrecognizedVariant(ref i) => write!(f, "Unrecognized({})", i),
+                }
+            }
+        }
+
+        /// Parse a variant name ignoring case, a number or `Unrecognized(n)` as printed by
+        /// `Display` - numbers are converted like `From` so the value of a variant parses as
+        /// that variant
+        impl ::std::str::FromStr for $name {
+            type Err = DeError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if s.eq_ignore_ascii_case(stringify!($var_def)) {
+                    return Ok($name::$var_def);
+                }
+                $(
+                    if s.eq_ignore_ascii_case(stringify!($var)) {
+                        return Ok($name::$var);
+                    }
+                )*
+                let num = if s.starts_with("Unrecognized(") && s.ends_with(')') {
+                    &s["Unrecognized(".len()..s.len() - 1]
+                } else {
+                    s
+                };
+                num.parse::<$ty>().map($name::from).map_err(|_| DeError::new(&format!(
+                    "Failed to parse {} from \"{}\"", stringify!($name), s
+                )))
+            }
+        }
     };
 }
 
@@ -102,7 +195,12 @@ macro_rules! impl_var_base {
 /// For naming a new enum, passing in what type it serializes to and deserializes
 /// from, and providing a mapping from variants to expressions (such as libc consts) that
 /// will ultimately be used in the serialization/deserialization step when sending the netlink
-/// message over the wire.
+/// message over the wire. The enum also implements `Display`, printing the variant name as
+/// declared or `Unrecognized(n)` for `UnrecognizedVariant(n)`, and `FromStr`, parsing the
+/// variant name in any case or a number. It is `Copy`, `Hash` and `Ord` so that it
+/// can be used as a map key - ordering compares the values of the variants. This requires
+/// the type it serializes to to implement these traits as the integer types do.
+/// `try_from_known` converts values strictly, failing for `UnrecognizedVariant`.
 ///
 /// # Usage
 ///  Create an `enum` named "MyNetlinkProtoAttrs" that can be serialized into `u16`s to use with Netlink.
@@ -131,7 +229,7 @@ macro_rules! impl_var {
       ($name:ident, $ty:ty, $var_def:ident => $val_def:expr,
       $( $var:ident => $val:expr ),* )) => ( // with comments
         $(#[$outer])*
-        #[derive(Clone,Debug,Eq,PartialEq)]
+        #[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
         pub enum $name {
             #[allow(missing_docs)]
             $var_def,
@@ -150,7 +248,7 @@ macro_rules! impl_var {
     ($name:ident, $ty:ty, $var_def:ident => $val_def:expr,
       $( $var:ident => $val:expr ),* ) => ( // without comments
         #[allow(missing_docs)]
-        #[derive(Clone,Debug,Eq,PartialEq)]
+        #[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
         pub enum $name {
             #[allow(missing_docs)]
             $var_def,
@@ -179,11 +277,11 @@ macro_rules! impl_trait {
     ( $(#[$outer:meta])*
     ( $trait_name:ident, $to_from_ty:ty )) => { // with comments
         $(#[$outer])*
-        pub trait $trait_name: Nl + From<$to_from_ty> + Into<$to_from_ty> {}
+        pub trait $trait_name: Nl + PartialEq + From<$to_from_ty> + Into<$to_from_ty> {}
     };
     ( $trait_name:ident, $to_from_ty:ty ) => { // without comments
         #[allow(missing_docs)]
-        pub trait $trait_name: Nl + From<$to_from_ty> + Into<$to_from_ty> {}
+        pub trait $trait_name: Nl + PartialEq + From<$to_from_ty> + Into<$to_from_ty> {}
     };
 }
 
@@ -209,16 +307,229 @@ macro_rules! impl_var_trait {
     );
 }
 
-/// Reimplementation of alignto macro in C
+#[macro_export]
+/// For flagging an enum created with `impl_var` as a set of bits in a bitmask so that it can be
+/// used with `NlFlags`. It accepts the name of the enum and the integer type of the bitmask,
+/// which must be the type passed to `impl_var`.
+///
+/// # Usage
+/// ```ignore
+/// impl_nl_flag!(MyNetlinkProtoFlags, u32);
+///
+/// let flags = MyNetlinkProtoFlags::First | MyNetlinkProtoFlags::Second;
+/// ```
+macro_rules! impl_nl_flag {
+    ( $name:ident, $ty:ty ) => {
+        impl $crate::consts::NlFlag for $name {
+            type Bits = $ty;
+
+            fn to_bits(&self) -> $ty {
+                self.into()
+            }
+
+            fn from_bits(bits: $ty) -> Self {
+                bits.into()
+            }
+
+            fn is_known(&self) -> bool {
+                !self.is_unrecognized()
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = $crate::consts::NlFlags<$name>;
+
+            fn bitor(self, rhs: $name) -> $crate::consts::NlFlags<$name> {
+                let mut flags = $crate::consts::NlFlags::from(self);
+                flags.insert(rhs);
+                flags
+            }
+        }
+
+        impl ::std::ops::BitOr<$name> for $crate::consts::NlFlags<$name> {
+            type Output = $crate::consts::NlFlags<$name>;
+
+            fn bitor(mut self, rhs: $name) -> $crate::consts::NlFlags<$name> {
+                self.insert(rhs);
+                self
+            }
+        }
+    };
+}
+
+/// Trait for enums whose variants are single bits in a bitmask - implement with
+/// `impl_nl_flag`
+pub trait NlFlag: Clone + PartialEq {
+    /// Integer type of the bitmask
+    type Bits: Nl + Copy + Debug + Default + PartialEq + From<u8> + BitAnd<Output = Self::Bits>
+        + BitOr<Output = Self::Bits> + Not<Output = Self::Bits> + Shl<usize, Output = Self::Bits>;
+
+    /// Convert the flag to its bit
+    fn to_bits(&self) -> Self::Bits;
+
+    /// Convert a single bit to a flag
+    fn from_bits(bits: Self::Bits) -> Self;
+
+    /// Returns `false` for `UnrecognizedVariant`
+    fn is_known(&self) -> bool;
+}
+
+/// Set of flags stored as the raw integer bitmask
+///
+/// Sets are combined with `|` and intersected with `&`, and `Debug` prints the names of the
+/// set flags.
+#[derive(Clone,PartialEq)]
+pub struct NlFlags<T> where T: NlFlag {
+    bits: T::Bits,
+}
+
+impl<T> NlFlags<T> where T: NlFlag {
+    /// Create a set with no flags
+    pub fn empty() -> Self {
+        NlFlags { bits: T::Bits::default() }
+    }
+
+    /// Create a set with every single bit flag that has a variant
+    pub fn all() -> Self {
+        let mut set = NlFlags::empty();
+        for i in 0..mem::size_of::<T::Bits>() * 8 {
+            let flag = T::from_bits(T::Bits::from(1u8) << i);
+            if flag.is_known() {
+                set.insert(flag);
+            }
+        }
+        set
+    }
+
+    /// Create a set from the raw bitmask - bits without a matching variant are preserved and
+    /// returned as `UnrecognizedVariant` by `iter`
+    pub fn from_bits(bits: T::Bits) -> Self {
+        NlFlags { bits }
+    }
+
+    /// Get the raw bitmask
+    pub fn bits(&self) -> T::Bits {
+        self.bits
+    }
+
+    /// Returns `true` if no flags are set
+    pub fn is_empty(&self) -> bool {
+        self.bits == T::Bits::default()
+    }
+
+    /// Returns `true` if all bits of `flag` are set
+    pub fn contains(&self, flag: &T) -> bool {
+        let bits = flag.to_bits();
+        self.bits & bits == bits
+    }
+
+    /// Set `flag`
+    pub fn insert(&mut self, flag: T) {
+        self.bits = self.bits | flag.to_bits();
+    }
+
+    /// Unset `flag`
+    pub fn remove(&mut self, flag: &T) {
+        self.bits = self.bits & !flag.to_bits();
+    }
+
+    /// Iterate over the set bits as flags, from least to most significant
+    pub fn iter(&self) -> vec::IntoIter<T> {
+        let zero = T::Bits::default();
+        let mut flags = Vec::new();
+        for i in 0..mem::size_of::<T::Bits>() * 8 {
+            let bit = T::Bits::from(1u8) << i;
+            if self.bits & bit != zero {
+                flags.push(T::from_bits(bit));
+            }
+        }
+        flags.into_iter()
+    }
+}
+
+impl<T> Default for NlFlags<T> where T: NlFlag {
+    fn default() -> Self {
+        NlFlags::empty()
+    }
+}
+
+impl<T> Debug for NlFlags<T> where T: NlFlag + Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.iter().map(|flag| format!("{:?}", flag)).collect::<Vec<_>>();
+        write!(f, "NlFlags({})", names.join(" | "))
+    }
+}
+
+impl<T> BitOr for NlFlags<T> where T: NlFlag {
+    type Output = NlFlags<T>;
+
+    fn bitor(self, rhs: NlFlags<T>) -> NlFlags<T> {
+        NlFlags { bits: self.bits | rhs.bits }
+    }
+}
+
+impl<T> BitAnd for NlFlags<T> where T: NlFlag {
+    type Output = NlFlags<T>;
+
+    fn bitand(self, rhs: NlFlags<T>) -> NlFlags<T> {
+        NlFlags { bits: self.bits & rhs.bits }
+    }
+}
+
+impl<T> From<T> for NlFlags<T> where T: NlFlag {
+    fn from(flag: T) -> Self {
+        NlFlags { bits: flag.to_bits() }
+    }
+}
+
+impl<T> From<Vec<T>> for NlFlags<T> where T: NlFlag {
+    fn from(flags: Vec<T>) -> Self {
+        let mut set = NlFlags::empty();
+        for flag in flags {
+            set.insert(flag);
+        }
+        set
+    }
+}
+
+impl<T> Nl for NlFlags<T> where T: NlFlag {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.bits.serialize(mem)
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError>
+            where B: AsRef<[u8]> {
+        Ok(NlFlags { bits: T::Bits::deserialize(mem)? })
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<T::Bits>()
+    }
+}
+
+/// Reimplementation of alignto macro in C - saturates at the largest aligned length instead of
+/// overflowing so use `checked_alignto` for lengths read from the wire
 pub fn alignto(len: usize) -> usize {
-    (len + libc::NLA_ALIGNTO as usize - 1) & !(libc::NLA_ALIGNTO as usize - 1)
+    checked_alignto(len).unwrap_or(!(libc::NLA_ALIGNTO as usize - 1))
+}
+
+/// Checked version of `alignto` returning an error if the aligned length overflows `usize`
+pub fn checked_alignto(len: usize) -> Result<usize, DeError> {
+    len.checked_add(libc::NLA_ALIGNTO as usize - 1)
+        .map(|len| len & !(libc::NLA_ALIGNTO as usize - 1))
+        .ok_or_else(|| DeError::new("Aligned length overflows usize"))
 }
 
 impl_var!(
     /// Address families 
     ( Af, libc::c_uchar,
+    Unspec => libc::AF_UNSPEC as libc::c_uchar,
     Inet => libc::AF_INET as libc::c_uchar,
-    Inet6 => libc::AF_INET6 as libc::c_uchar )
+    Inet6 => libc::AF_INET6 as libc::c_uchar,
+    Bridge => libc::AF_BRIDGE as libc::c_uchar )
 );
 
 impl_var!(
@@ -275,13 +586,33 @@ impl_var!(
 
 impl_var!(
     /// `rtm_protocol`
-    /// The origins of routes that are defined in the kernel
+    /// The origins of routes that are defined in the kernel and the values reserved for
+    /// routing daemons. Values chosen by users are represented as `UnrecognizedVariant`.
     ( Rtprot, libc::c_uchar,
     Unspec => libc::RTPROT_UNSPEC,
     Redirect => libc::RTPROT_REDIRECT,
     Kernel => libc::RTPROT_KERNEL,
     Boot => libc::RTPROT_BOOT,
-    Static => libc::RTPROT_STATIC )
+    Static => libc::RTPROT_STATIC,
+    // Values from `linux/rtnetlink.h` that are not defined by `libc`
+    Gated => 8,
+    Ra => 9,
+    Mrt => 10,
+    Zebra => 11,
+    Bird => 12,
+    Dnrouted => 13,
+    Xorp => 14,
+    Ntk => 15,
+    Dhcp => 16,
+    Mrouted => 17,
+    Keepalived => 18,
+    Babel => 42,
+    Openr => 99,
+    Bgp => 186,
+    Isis => 187,
+    Ospf => 188,
+    Rip => 189,
+    Eigrp => 192 )
 );
 
 impl_var!(
@@ -318,6 +649,18 @@ impl_var!(
     FibMatch => libc::RTM_F_FIB_MATCH )
 );
 
+impl_var!(
+    /// `rtnh_flags`
+    /// Flags for next hops of multipath routes from `linux/rtnetlink.h`
+    ( RtnhF, u8,
+    Dead => 1,
+    Pervasive => 2,
+    Onlink => 4,
+    Offload => 8,
+    Linkdown => 16,
+    Unresolved => 32 )
+);
+
 impl_var!(
     /// Arp neighbor cache entry states
     ( Nud, u16,
@@ -357,7 +700,129 @@ impl_var_trait!(
     Mtu => libc::IFLA_MTU,
     Link => libc::IFLA_LINK,
     Qdisc => libc::IFLA_QDISC,
-    Stats => libc::IFLA_STATS )
+    Stats => libc::IFLA_STATS,
+    // Values from `linux/if_link.h` that are not defined by `libc`
+    Cost => 8,
+    Priority => 9,
+    Master => 10,
+    Wireless => 11,
+    Protinfo => 12,
+    Txqlen => 13,
+    Map => 14,
+    Weight => 15,
+    Operstate => 16,
+    Linkmode => 17,
+    Linkinfo => 18,
+    NetNsPid => 19,
+    Ifalias => 20,
+    NumVf => 21,
+    VfinfoList => 22,
+    Stats64 => 23,
+    VfPorts => 24,
+    PortSelf => 25,
+    AfSpec => 26,
+    Group => 27,
+    NetNsFd => 28,
+    ExtMask => 29,
+    Promiscuity => 30,
+    NumTxQueues => 31,
+    NumRxQueues => 32,
+    Carrier => 33,
+    PhysPortId => 34,
+    CarrierChanges => 35,
+    PhysSwitchId => 36,
+    LinkNetnsid => 37,
+    PhysPortName => 38,
+    ProtoDown => 39,
+    GsoMaxSegs => 40,
+    GsoMaxSize => 41,
+    Pad => 42,
+    Xdp => 43,
+    Event => 44,
+    NewNetnsid => 45,
+    IfNetnsid => 46,
+    CarrierUpCount => 47,
+    CarrierDownCount => 48,
+    NewIfindex => 49,
+    MinMtu => 50,
+    MaxMtu => 51,
+    PropList => 52,
+    AltIfname => 53,
+    PermAddress => 54,
+    ProtoDownReason => 55,
+    ParentDevName => 56,
+    ParentDevBusName => 57,
+    GroMaxSize => 58,
+    TsoMaxSize => 59,
+    TsoMaxSegs => 60,
+    Allmulti => 61,
+    DevlinkPort => 62,
+    GsoIpv4MaxSize => 63,
+    GroIpv4MaxSize => 64,
+    DpllPin => 65 )
+);
+
+impl_var!(
+    /// Operational state of an interface (RFC 2863) in the `Ifla::Operstate` attribute.
+    /// Values are from `linux/if.h`
+    ( IfOper, u8,
+    Unknown => 0,
+    Notpresent => 1,
+    Down => 2,
+    Lowerlayerdown => 3,
+    Testing => 4,
+    Dormant => 5,
+    Up => 6 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `Ifla::Linkinfo`.
+    /// Values are link info attributes from `linux/if_link.h`
+    ( IflaInfo, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Kind => 1,
+    Data => 2,
+    Xstats => 3,
+    SlaveKind => 4,
+    SlaveData => 5 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `IflaInfo::Data` for
+    /// interfaces of kind `"vlan"`. Values are VLAN attributes from `linux/if_link.h`
+    ( IflaVlan, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Id => 1,
+    Flags => 2,
+    EgressQos => 3,
+    IngressQos => 4,
+    Protocol => 5 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `IflaInfo::Data` for
+    /// interfaces of kind `"veth"`. Values are from `linux/veth.h`
+    ( VethInfo, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Peer => 1 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `IflaVlan::EgressQos`
+    /// and `IflaVlan::IngressQos`. Values are from `linux/if_link.h`
+    ( IflaVlanQos, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Mapping => 1 )
+);
+
+impl_var!(
+    /// Values for `flags` and `mask` in `IflaVlanFlags` from `linux/if_vlan.h`
+    ( VlanFlag, u32,
+    ReorderHdr => 0x1,
+    Gvrp => 0x2,
+    LooseBinding => 0x4,
+    Mvrp => 0x8,
+    BridgeBinding => 0x10 )
 );
 
 impl_var_trait!(
@@ -407,6 +872,248 @@ impl_var_trait!(
     TtlPropagate => libc::RTA_TTL_PROPAGATE)
 );
 
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type`.
+    /// Values are neighbor table message attributes
+    ( Nda, libc::c_ushort, RtaType,
+    Unspec => libc::NDA_UNSPEC,
+    Dst => libc::NDA_DST,
+    Lladdr => libc::NDA_LLADDR,
+    Cacheinfo => libc::NDA_CACHEINFO,
+    Probes => libc::NDA_PROBES,
+    Vlan => libc::NDA_VLAN,
+    Port => libc::NDA_PORT,
+    Vni => libc::NDA_VNI,
+    Ifindex => libc::NDA_IFINDEX,
+    Master => libc::NDA_MASTER,
+    LinkNetnsid => libc::NDA_LINK_NETNSID,
+    SrcVni => libc::NDA_SRC_VNI,
+    // Values from `linux/neighbour.h` that are not defined by `libc`
+    Protocol => 12,
+    NhId => 13,
+    FdbExtAttrs => 14,
+    FlagsExt => 15,
+    NdmStateMask => 16,
+    NdmFlagsMask => 17 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type`.
+    /// Values are neighbor table parameter message attributes from `linux/neighbour.h`
+    ( Ndta, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Name => 1,
+    Thresh1 => 2,
+    Thresh2 => 3,
+    Thresh3 => 4,
+    Config => 5,
+    Parms => 6,
+    Stats => 7,
+    GcInterval => 8,
+    Pad => 9 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `Ndta::Parms`.
+    /// Values are neighbor table parameters from `linux/neighbour.h`
+    ( Ndtpa, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Ifindex => 1,
+    Refcnt => 2,
+    ReachableTime => 3,
+    BaseReachableTime => 4,
+    RetransTime => 5,
+    GcStaletime => 6,
+    DelayProbeTime => 7,
+    QueueLen => 8,
+    AppProbes => 9,
+    UcastProbes => 10,
+    McastProbes => 11,
+    AnycastDelay => 12,
+    ProxyDelay => 13,
+    ProxyQlen => 14,
+    Locktime => 15,
+    QueueLenbytes => 16,
+    McastReprobes => 17,
+    Pad => 18,
+    IntervalProbeTimeMs => 19 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type`.
+    /// Values are traffic control message attributes from `linux/rtnetlink.h`
+    ( Tca, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Kind => 1,
+    Options => 2,
+    Stats => 3,
+    Xstats => 4,
+    Rate => 5,
+    Fcnt => 6,
+    Stats2 => 7,
+    Stab => 8,
+    Pad => 9,
+    DumpInvisible => 10,
+    Chain => 11,
+    HwOffload => 12,
+    IngressBlock => 13,
+    EgressBlock => 14 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in the nested attributes of `Rta::Metrics`.
+    /// Values are route metrics from `linux/rtnetlink.h`
+    ( Rtax, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Lock => 1,
+    Mtu => 2,
+    Window => 3,
+    Rtt => 4,
+    Rttvar => 5,
+    Ssthresh => 6,
+    Cwnd => 7,
+    Advmss => 8,
+    Reordering => 9,
+    Hoplimit => 10,
+    Initcwnd => 11,
+    Features => 12,
+    RtoMin => 13,
+    Initrwnd => 14,
+    Quickack => 15,
+    CcAlgo => 16,
+    FastopenNoCookie => 17 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type`.
+    /// Values are FIB rule message attributes from `linux/fib_rules.h`
+    ( Fra, libc::c_ushort, RtaType,
+    Unspec => 0,
+    Dst => 1,
+    Src => 2,
+    Iifname => 3,
+    Goto => 4,
+    Priority => 6,
+    Fwmark => 10,
+    Flow => 11,
+    TunId => 12,
+    SuppressIfgroup => 13,
+    SuppressPrefixlen => 14,
+    Table => 15,
+    Fwmask => 16,
+    Oifname => 17,
+    Pad => 18,
+    L3mdev => 19,
+    UidRange => 20,
+    Protocol => 21,
+    IpProto => 22,
+    SportRange => 23,
+    DportRange => 24 )
+);
+
+impl_var!(
+    /// Values for `action` in `FibRuleHdr` from `linux/fib_rules.h`
+    ( FrAct, u8,
+    Unspec => 0,
+    ToTbl => 1,
+    Goto => 2,
+    Nop => 3,
+    Blackhole => 6,
+    Unreachable => 7,
+    Prohibit => 8 )
+);
+
+impl_var!(
+    /// Values for `flags` in `FibRuleHdr` from `linux/fib_rules.h`
+    ( FibRuleF, u32,
+    Permanent => 0x1,
+    Invert => 0x2,
+    Unresolved => 0x4,
+    IifDetached => 0x8,
+    OifDetached => 0x10,
+    FindSaddr => 0x10000 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in `NlFamily::Xfrm` messages.
+    /// Values are xfrm attributes from `linux/xfrm.h`
+    ( XfrmAttr, libc::c_ushort, RtaType,
+    Unspec => 0,
+    AlgAuth => 1,
+    AlgCrypt => 2,
+    AlgComp => 3,
+    Encap => 4,
+    Tmpl => 5,
+    Sa => 6,
+    Policy => 7,
+    SecCtx => 8,
+    LtimeVal => 9,
+    ReplayVal => 10,
+    ReplayThresh => 11,
+    EtimerThresh => 12,
+    Srcaddr => 13,
+    Coaddr => 14,
+    Lastused => 15,
+    PolicyType => 16,
+    Migrate => 17,
+    AlgAead => 18,
+    Kmaddress => 19,
+    AlgAuthTrunc => 20,
+    Mark => 21,
+    Tfcpad => 22,
+    ReplayEsnVal => 23,
+    SaExtraFlags => 24,
+    Proto => 25,
+    AddressFilter => 26,
+    Pad => 27,
+    OffloadDev => 28,
+    SetMark => 29,
+    SetMarkMask => 30,
+    IfId => 31 )
+);
+
+impl_var_trait!(
+    /// Enum for use with `RtAttr.rta_type` in `InetDiagMsg` responses.
+    /// Values are inet socket diagnostics attributes from `linux/inet_diag.h`
+    ( InetDiag, libc::c_ushort, RtaType,
+    None => 0,
+    Meminfo => 1,
+    Info => 2,
+    Vegasinfo => 3,
+    Cong => 4,
+    Tos => 5,
+    Tclass => 6,
+    Skmeminfo => 7,
+    Shutdown => 8,
+    Dctcpinfo => 9,
+    Protocol => 10,
+    Skv6only => 11,
+    Locals => 12,
+    Peers => 13,
+    Pad => 14,
+    Mark => 15,
+    Bbrinfo => 16,
+    ClassId => 17,
+    Md5sig => 18 )
+);
+
+impl_var!(
+    /// TCP socket states for `InetDiagMsg.idiag_state` from `net/tcp_states.h`
+    ( TcpState, u8,
+    Established => 1,
+    SynSent => 2,
+    SynRecv => 3,
+    FinWait1 => 4,
+    FinWait2 => 5,
+    TimeWait => 6,
+    Close => 7,
+    CloseWait => 8,
+    LastAck => 9,
+    Listen => 10,
+    Closing => 11,
+    NewSynRecv => 12 )
+);
+
 impl_var!(
     /// Interface types
     ( Arphrd, libc::c_ushort,
@@ -420,14 +1127,62 @@ impl_var!(
     Arcnet => libc::ARPHRD_ARCNET,
     Appletlk => libc::ARPHRD_APPLETLK,
     Dlci => libc::ARPHRD_DLCI,
-    Atm => libc::ARPHRD_APPLETLK,
+    Atm => libc::ARPHRD_ATM,
     Metricom => libc::ARPHRD_METRICOM,
     Ieee1394 => libc::ARPHRD_IEEE1394,
     Eui64 => libc::ARPHRD_EUI64,
     Infiniband => libc::ARPHRD_INFINIBAND,
-
-    // Possibly more types here - need to look into ARP more
-
+    Can => libc::ARPHRD_CAN,
+    Ppp => libc::ARPHRD_PPP,
+    Tunnel => libc::ARPHRD_TUNNEL,
+    Tunnel6 => libc::ARPHRD_TUNNEL6,
+    Loopback => libc::ARPHRD_LOOPBACK,
+    Sit => libc::ARPHRD_SIT,
+    Ipgre => libc::ARPHRD_IPGRE,
+    Ieee80211 => libc::ARPHRD_IEEE80211,
+    Ieee80211Radiotap => libc::ARPHRD_IEEE80211_RADIOTAP,
+    // Values from `linux/if_arp.h` that are not defined by `libc`
+    Slip => 256,
+    Cslip => 257,
+    Slip6 => 258,
+    Cslip6 => 259,
+    Rsrvd => 260,
+    Adapt => 264,
+    Rose => 270,
+    X25 => 271,
+    Hwx25 => 272,
+    Mctp => 290,
+    Hdlc => 513,
+    Lapb => 516,
+    Ddcmp => 517,
+    Rawhdlc => 518,
+    Rawip => 519,
+    Frad => 770,
+    Skip => 771,
+    Localtlk => 773,
+    Fddi => 774,
+    Bif => 775,
+    Ipddp => 777,
+    Pimreg => 779,
+    Hippi => 780,
+    Ash => 781,
+    Econet => 782,
+    Irda => 783,
+    Fcpp => 784,
+    Fcal => 785,
+    Fcpl => 786,
+    Fcfabric => 787,
+    Ieee802Tr => 800,
+    Ieee80211Prism => 802,
+    Ieee802154 => 804,
+    Ieee802154Monitor => 805,
+    Phonet => 820,
+    PhonetPipe => 821,
+    Caif => 822,
+    Ip6gre => 823,
+    Netlink => 824,
+    Sixlowpan => 825,
+    Vsockmon => 826,
     Void => libc::ARPHRD_VOID,
     None => libc::ARPHRD_NONE
 ));
@@ -461,6 +1216,10 @@ impl_var!(
 
 impl_var!(
     /// Values for `nl_family` in `NlSocket`
+    ///
+    /// `Unused`, `Firewall`, `Ip6Fw` and `Dnrtmsg` are no longer used by current kernels and
+    /// sockets of these families cannot be created. Protocol numbers of out-of-tree families
+    /// can be passed to `NlSocket::connect` as a `libc::c_int`.
     ( NlFamily, libc::c_int,
     Route => libc::NETLINK_ROUTE,
     Unused => libc::NETLINK_UNUSED,
@@ -482,14 +1241,95 @@ impl_var!(
     Scsitransport => libc::NETLINK_SCSITRANSPORT,
     Ecryptfs => libc::NETLINK_ECRYPTFS,
     Rdma => libc::NETLINK_RDMA,
-    Crypto => libc::NETLINK_CRYPTO )
+    Crypto => libc::NETLINK_CRYPTO,
+    // Value from `linux/netlink.h` that is not defined by `libc`
+    Smc => 22 )
 );
 
+impl NlFamily {
+    /// Name of `NlFamily::SockOrInetDiag` matching `NETLINK_INET_DIAG` in C
+    #[allow(non_upper_case_globals)]
+    pub const InetDiag: NlFamily = NlFamily::SockOrInetDiag;
+}
+
 impl_trait!(
     /// Trait marking constants valid for use in `Nlmsghdr.nl_type`
     (NlType, u16)
 );
 
+/// Plain numbers can be used as `nl_type` directly - see `GenlFamilyId` for the runtime
+/// assigned IDs of generic netlink families
+impl NlType for u16 {}
+
+impl_trait!(
+    /// Trait marking multicast group IDs valid for `NlSocket::add_mcast_membership` - these
+    /// are group numbers counting from 1, not bitmasks
+    (McastGroup, u32)
+);
+
+/// Group IDs returned by `resolve_nl_mcast_group` are plain numbers
+impl McastGroup for u32 {}
+
+impl_var_trait!(
+    /// Route netlink multicast group numbers (`RTNLGRP_*`) from `linux/rtnetlink.h` for use
+    /// with `NlSocket::add_mcast_membership` - unlike the legacy `Rtmgrp` bitmask, these can
+    /// address every group including those numbered above 32
+    ( Rtnlgrp, u32, McastGroup,
+    None => 0,
+    Link => 1,
+    Notify => 2,
+    Neigh => 3,
+    Tc => 4,
+    Ipv4Ifaddr => 5,
+    Ipv4Mroute => 6,
+    Ipv4Route => 7,
+    Ipv4Rule => 8,
+    Ipv6Ifaddr => 9,
+    Ipv6Mroute => 10,
+    Ipv6Route => 11,
+    Ipv6Ifinfo => 12,
+    DecnetIfaddr => 13,
+    DecnetRoute => 15,
+    DecnetRule => 16,
+    Ipv6Prefix => 18,
+    Ipv6Rule => 19,
+    NdUseropt => 20,
+    PhonetIfaddr => 21,
+    PhonetRoute => 22,
+    Dcb => 23,
+    Ipv4Netconf => 24,
+    Ipv6Netconf => 25,
+    Mdb => 26,
+    MplsRoute => 27,
+    Nsid => 28,
+    MplsNetconf => 29,
+    Ipv4MrouteR => 30,
+    Ipv6MrouteR => 31,
+    Nexthop => 32 )
+);
+
+impl_var!(
+    /// Legacy route netlink multicast group bitmask values (`RTMGRP_*`) from
+    /// `linux/rtnetlink.h` for use with `NlSocket::bind_rtmgrp` - only the groups below 32 have
+    /// a bitmask value, use `Rtnlgrp` with `NlSocket::add_mcast_membership` for the rest
+    ( Rtmgrp, u32,
+    Link => 0x1,
+    Notify => 0x2,
+    Neigh => 0x4,
+    Tc => 0x8,
+    Ipv4Ifaddr => 0x10,
+    Ipv4Mroute => 0x20,
+    Ipv4Route => 0x40,
+    Ipv4Rule => 0x80,
+    Ipv6Ifaddr => 0x100,
+    Ipv6Mroute => 0x200,
+    Ipv6Route => 0x400,
+    Ipv6Ifinfo => 0x800,
+    DecnetIfaddr => 0x1000,
+    DecnetRoute => 0x4000,
+    Ipv6Prefix => 0x20000 )
+);
+
 impl_var_trait!(
     /// Values for `nl_type` in `Nlmsghdr`
     ( Nlmsg, u16, NlType,
@@ -500,13 +1340,413 @@ impl_var_trait!(
 );
 
 impl_var_trait!(
-    /// Values for `nl_type` in `Nlmsghdr`
+    /// Values for `nl_type` in `Nlmsghdr` for the generic netlink families with static IDs -
+    /// other families are identified by the `GenlFamilyId` returned by `resolve_genl_family`
     ( GenlId, u16, NlType,
     Ctrl => libc::GENL_ID_CTRL as u16,
     VfsDquot => libc::GENL_ID_VFS_DQUOT as u16,
     Pmcraid => libc::GENL_ID_PMCRAID as u16 )
 );
 
+/// Generic netlink family ID assigned at runtime as returned by `resolve_genl_family` - use it
+/// as the `nl_type` of messages for the family
+///
+/// IDs compare equal to the `GenlId` of the families with static IDs such as `GenlId::Ctrl`.
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub struct GenlFamilyId(pub u16);
+
+impl Nl for GenlFamilyId {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.0.serialize(mem)
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError>
+            where B: AsRef<[u8]> {
+        Ok(GenlFamilyId(u16::deserialize(mem)?))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<u16>()
+    }
+}
+
+impl From<u16> for GenlFamilyId {
+    fn from(id: u16) -> Self {
+        GenlFamilyId(id)
+    }
+}
+
+impl From<GenlFamilyId> for u16 {
+    fn from(id: GenlFamilyId) -> Self {
+        id.0
+    }
+}
+
+impl From<GenlId> for GenlFamilyId {
+    fn from(id: GenlId) -> Self {
+        GenlFamilyId(id.into())
+    }
+}
+
+impl PartialEq<GenlId> for GenlFamilyId {
+    fn eq(&self, other: &GenlId) -> bool {
+        self.0 == u16::from(*other)
+    }
+}
+
+impl fmt::Display for GenlFamilyId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl NlType for GenlFamilyId {}
+
+impl_var_trait!(
+    /// Values for `nl_type` in `Nlmsghdr` for route netlink messages
+    ( Rtm, u16, NlType,
+    Newlink => libc::RTM_NEWLINK,
+    Dellink => libc::RTM_DELLINK,
+    Getlink => libc::RTM_GETLINK,
+    Setlink => libc::RTM_SETLINK,
+    Newaddr => libc::RTM_NEWADDR,
+    Deladdr => libc::RTM_DELADDR,
+    Getaddr => libc::RTM_GETADDR,
+    Newroute => libc::RTM_NEWROUTE,
+    Delroute => libc::RTM_DELROUTE,
+    Getroute => libc::RTM_GETROUTE,
+    Newneigh => libc::RTM_NEWNEIGH,
+    Delneigh => libc::RTM_DELNEIGH,
+    Getneigh => libc::RTM_GETNEIGH,
+    Newrule => libc::RTM_NEWRULE,
+    Delrule => libc::RTM_DELRULE,
+    Getrule => libc::RTM_GETRULE,
+    Newqdisc => libc::RTM_NEWQDISC,
+    Delqdisc => libc::RTM_DELQDISC,
+    Getqdisc => libc::RTM_GETQDISC,
+    Newtclass => libc::RTM_NEWTCLASS,
+    Deltclass => libc::RTM_DELTCLASS,
+    Gettclass => libc::RTM_GETTCLASS,
+    Newtfilter => libc::RTM_NEWTFILTER,
+    Deltfilter => libc::RTM_DELTFILTER,
+    Gettfilter => libc::RTM_GETTFILTER,
+    // Remaining values from `linux/rtnetlink.h`
+    Newaction => 48,
+    Delaction => 49,
+    Getaction => 50,
+    Newprefix => 52,
+    Newmulticast => 56,
+    Delmulticast => 57,
+    Getmulticast => 58,
+    Newanycast => 60,
+    Delanycast => 61,
+    Getanycast => 62,
+    Newneightbl => 64,
+    Getneightbl => 66,
+    Setneightbl => 67,
+    Newnduseropt => 68,
+    Newaddrlabel => 72,
+    Deladdrlabel => 73,
+    Getaddrlabel => 74,
+    Getdcb => 78,
+    Setdcb => 79,
+    Newnetconf => 80,
+    Delnetconf => 81,
+    Getnetconf => 82,
+    Newmdb => 84,
+    Delmdb => 85,
+    Getmdb => 86,
+    Newnsid => 88,
+    Delnsid => 89,
+    Getnsid => 90,
+    Newstats => 92,
+    Getstats => 94,
+    Setstats => 95,
+    Newcachereport => 96,
+    Newchain => 100,
+    Delchain => 101,
+    Getchain => 102,
+    Newnexthop => 104,
+    Delnexthop => 105,
+    Getnexthop => 106,
+    Newlinkprop => 108,
+    Dellinkprop => 109,
+    Getlinkprop => 110,
+    Newvlan => 112,
+    Delvlan => 113,
+    Getvlan => 114,
+    Newnexthopbucket => 116,
+    Delnexthopbucket => 117,
+    Getnexthopbucket => 118,
+    Newtunnel => 120,
+    Deltunnel => 121,
+    Gettunnel => 122 )
+);
+
+impl_var_trait!(
+    /// Values for `nl_type` in `Nlmsghdr` for `NlFamily::Xfrm` messages from `linux/xfrm.h`
+    ( XfrmMsg, u16, NlType,
+    Newsa => 0x10,
+    Delsa => 0x11,
+    Getsa => 0x12,
+    Newpolicy => 0x13,
+    Delpolicy => 0x14,
+    Getpolicy => 0x15,
+    Allocspi => 0x16,
+    Acquire => 0x17,
+    Expire => 0x18,
+    Updpolicy => 0x19,
+    Updsa => 0x1a,
+    Polexpire => 0x1b,
+    Flushsa => 0x1c,
+    Flushpolicy => 0x1d,
+    Newae => 0x1e,
+    Getae => 0x1f,
+    Report => 0x20,
+    Migrate => 0x21,
+    Newsadinfo => 0x22,
+    Getsadinfo => 0x23,
+    Newspdinfo => 0x24,
+    Getspdinfo => 0x25,
+    Mapping => 0x26 )
+);
+
+impl_var_trait!(
+    /// Values for `nl_type` in `Nlmsghdr` for `NlFamily::SockOrInetDiag` messages from
+    /// `linux/sock_diag.h`
+    ( SockDiag, u16, NlType,
+    ByFamily => 20,
+    Destroy => 21 )
+);
+
+impl_var_trait!(
+    /// Values for `nl_type` in `Nlmsghdr` for `NlFamily::Audit` messages and the record types
+    /// carrying text from `linux/audit.h`
+    ( AuditMsg, u16, NlType,
+    Get => 1000,
+    Set => 1001,
+    List => 1002,
+    Add => 1003,
+    Del => 1004,
+    User => 1005,
+    Login => 1006,
+    WatchIns => 1007,
+    WatchRem => 1008,
+    WatchList => 1009,
+    SignalInfo => 1010,
+    AddRule => 1011,
+    DelRule => 1012,
+    ListRules => 1013,
+    Trim => 1014,
+    MakeEquiv => 1015,
+    TtyGet => 1016,
+    TtySet => 1017,
+    SetFeature => 1018,
+    GetFeature => 1019,
+    Syscall => 1300,
+    Path => 1302,
+    Ipc => 1303,
+    Socketcall => 1304,
+    ConfigChange => 1305,
+    Sockaddr => 1306,
+    Cwd => 1307,
+    Execve => 1309,
+    Eoe => 1320,
+    Proctitle => 1327,
+    Avc => 1400 )
+);
+
+impl_var!(
+    /// Netfilter subsystems selected by the upper byte of `nl_type` in `NlFamily::Netfilter`
+    /// messages from `linux/netfilter/nfnetlink.h` - see `nfnetlink::NfnlType`
+    ( NfnlSubsys, u8,
+    None => 0,
+    Ctnetlink => 1,
+    CtnetlinkExp => 2,
+    Queue => 3,
+    Ulog => 4,
+    Osf => 5,
+    Ipset => 6,
+    Acct => 7,
+    CtnetlinkTimeout => 8,
+    Cthelper => 9,
+    Nftables => 10,
+    Nftcompat => 11,
+    Hook => 12 )
+);
+
+impl_var!(
+    /// Message types of `NfnlSubsys::Ctnetlink` (`IPCTNL_MSG_CT_*`) from
+    /// `linux/netfilter/nfnetlink_conntrack.h`
+    ( CtnlMsg, u8,
+    New => 0,
+    Get => 1,
+    Delete => 2,
+    GetCtrzero => 3,
+    GetStatsCpu => 4,
+    GetStats => 5,
+    GetDying => 6,
+    GetUnconfirmed => 7 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of conntrack entries (`CTA_*`) from
+    /// `linux/netfilter/nfnetlink_conntrack.h`
+    ( Cta, u16,
+    Unspec => 0,
+    TupleOrig => 1,
+    TupleReply => 2,
+    Status => 3,
+    Protoinfo => 4,
+    Help => 5,
+    NatSrc => 6,
+    Timeout => 7,
+    Mark => 8,
+    CountersOrig => 9,
+    CountersReply => 10,
+    Use => 11,
+    Id => 12,
+    NatDst => 13,
+    TupleMaster => 14,
+    SeqAdjOrig => 15,
+    SeqAdjReply => 16,
+    Secmark => 17,
+    Zone => 18,
+    Secctx => 19,
+    Timestamp => 20,
+    MarkMask => 21,
+    Labels => 22,
+    LabelsMask => 23,
+    Synproxy => 24,
+    Filter => 25,
+    StatusMask => 26 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes nested in `Cta::TupleOrig` and
+    /// `Cta::TupleReply` (`CTA_TUPLE_*`)
+    ( CtaTuple, u16,
+    Unspec => 0,
+    Ip => 1,
+    Proto => 2,
+    Zone => 3 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes nested in `CtaTuple::Ip` (`CTA_IP_*`)
+    ( CtaIp, u16,
+    Unspec => 0,
+    V4Src => 1,
+    V4Dst => 2,
+    V6Src => 3,
+    V6Dst => 4 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes nested in `CtaTuple::Proto` (`CTA_PROTO_*`)
+    ( CtaProto, u16,
+    Unspec => 0,
+    Num => 1,
+    SrcPort => 2,
+    DstPort => 3,
+    IcmpId => 4,
+    IcmpType => 5,
+    IcmpCode => 6,
+    Icmpv6Id => 7,
+    Icmpv6Type => 8,
+    Icmpv6Code => 9 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes nested in `Cta::CountersOrig` and
+    /// `Cta::CountersReply` (`CTA_COUNTERS_*`)
+    ( CtaCounters, u16,
+    Unspec => 0,
+    Packets => 1,
+    Bytes => 2,
+    Packets32 => 3,
+    Bytes32 => 4,
+    Pad => 5 )
+);
+
+impl_var!(
+    /// Status bits of conntrack entries in `Cta::Status` (`IPS_*`) from
+    /// `linux/netfilter/nf_conntrack_common.h`
+    ( CtStatus, u32,
+    Expected => 1,
+    SeenReply => 1 << 1,
+    Assured => 1 << 2,
+    Confirmed => 1 << 3,
+    SrcNat => 1 << 4,
+    DstNat => 1 << 5,
+    SeqAdjust => 1 << 6,
+    SrcNatDone => 1 << 7,
+    DstNatDone => 1 << 8,
+    Dying => 1 << 9,
+    FixedTimeout => 1 << 10,
+    Template => 1 << 11,
+    Untracked => 1 << 12,
+    Helper => 1 << 13,
+    Offload => 1 << 14 )
+);
+
+impl_var_trait!(
+    /// Netfilter netlink multicast group numbers (`NFNLGRP_*`) from
+    /// `linux/netfilter/nfnetlink.h` for use with `NlSocket::add_mcast_membership`
+    ( Nfnlgrp, u32, McastGroup,
+    None => 0,
+    ConntrackNew => 1,
+    ConntrackUpdate => 2,
+    ConntrackDestroy => 3,
+    ConntrackExpNew => 4,
+    ConntrackExpUpdate => 5,
+    ConntrackExpDestroy => 6,
+    Nftables => 7,
+    Acct => 8,
+    Nftrace => 9 )
+);
+
+impl_var!(
+    /// Fields of `audit::AuditStatus` to apply with `AuditMsg::Set` (`AUDIT_STATUS_*`) from
+    /// `linux/audit.h`
+    ( AuditStatusMask, u32,
+    Enabled => 0x1,
+    Failure => 0x2,
+    Pid => 0x4,
+    RateLimit => 0x8,
+    BacklogLimit => 0x10,
+    BacklogWaitTime => 0x20,
+    Lost => 0x40,
+    BacklogWaitTimeActual => 0x80 )
+);
+
+impl_var!(
+    /// Operations of the subscription message of the proc connector (`PROC_CN_MCAST_*`) from
+    /// `linux/cn_proc.h` - see `connector::proc_mcast_msg`
+    ( ProcCnMcastOp, u32,
+    Listen => 1,
+    Ignore => 2 )
+);
+
+impl_var!(
+    /// Types of the events of the proc connector (`PROC_EVENT_*`) from `linux/cn_proc.h`
+    ( ProcEventWhat, u32,
+    None => 0,
+    Fork => 0x1,
+    Exec => 0x2,
+    Uid => 0x4,
+    Gid => 0x40,
+    Sid => 0x80,
+    Ptrace => 0x100,
+    Comm => 0x200,
+    NonzeroExit => 0x20000000,
+    Coredump => 0x40000000,
+    Exit => 0x80000000 )
+);
+
 impl_var!(
     /// Values for `nl_flags` in `NlHdr`
     ( NlmF, u16,
@@ -523,7 +1763,22 @@ impl_var!(
     Replace => libc::NLM_F_REPLACE as u16,
     Excl => libc::NLM_F_EXCL as u16,
     Create => libc::NLM_F_CREATE as u16,
-    Append => libc::NLM_F_APPEND as u16 )
+    Append => libc::NLM_F_APPEND as u16,
+    // Flags only used in `Nlmsg::Error` messages from `linux/netlink.h` - these share values
+    // with `Root` and `Match` so received flags will deserialize as those variants
+    Capped => 0x100,
+    AckTlvs => 0x200 )
+);
+
+impl_var!(
+    /// Extended acknowledgement attributes following the echoed request in `Nlmsg::Error`
+    /// messages with `NlmF::AckTlvs` set. Values are from `linux/netlink.h`
+    ( NlmsgerrAttr, u16,
+    Unused => 0,
+    Msg => 1,
+    Offs => 2,
+    Cookie => 3,
+    Policy => 4 )
 );
 
 impl_var!(
@@ -551,7 +1806,20 @@ impl_var!(
     Hdrsize => libc::CTRL_ATTR_HDRSIZE as u16,
     Maxattr => libc::CTRL_ATTR_MAXATTR as u16,
     Ops => libc::CTRL_ATTR_OPS as u16,
-    McastGroups => libc::CTRL_ATTR_MCAST_GROUPS as u16 )
+    McastGroups => libc::CTRL_ATTR_MCAST_GROUPS as u16,
+    // Values of `CTRL_ATTR_POLICY`, `CTRL_ATTR_OP_POLICY` and `CTRL_ATTR_OP` from
+    // `linux/genetlink.h`
+    Policy => 8,
+    OpPolicy => 9,
+    Op => 10 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the nested attributes of each operation in `CtrlAttr::Ops`
+    ( CtrlAttrOp, u16,
+    Unspec => libc::CTRL_ATTR_OP_UNSPEC as u16,
+    Id => libc::CTRL_ATTR_OP_ID as u16,
+    Flags => libc::CTRL_ATTR_OP_FLAGS as u16 )
 );
 
 impl_var!(
@@ -561,3 +1829,325 @@ impl_var!(
     Name => libc::CTRL_ATTR_MCAST_GRP_NAME as u16,
     Id => libc::CTRL_ATTR_MCAST_GRP_ID as u16 )
 );
+
+impl_var!(
+    /// Values for `nla_type` in the nested attributes of each operation in
+    /// `CtrlAttr::OpPolicy` from `linux/genetlink.h`
+    ( CtrlAttrPolicy, u16,
+    Unspec => 0,
+    Do => 1,
+    Dump => 2 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the nested attributes describing the validation policy of an
+    /// attribute in `CtrlAttr::Policy` from `linux/netlink.h`
+    ( NlPolicyTypeAttr, u16,
+    Unspec => 0,
+    Type => 1,
+    MinValueS => 2,
+    MaxValueS => 3,
+    MinValueU => 4,
+    MaxValueU => 5,
+    MinLength => 6,
+    MaxLength => 7,
+    PolicyIdx => 8,
+    PolicyMaxtype => 9,
+    Bitfield32Mask => 10,
+    Pad => 11,
+    Mask => 12 )
+);
+
+impl_var!(
+    /// Attribute types reported in `NlPolicyTypeAttr::Type` from `linux/netlink.h`
+    ( NlAttrType, u32,
+    Invalid => 0,
+    Flag => 1,
+    U8 => 2,
+    U16 => 3,
+    U32 => 4,
+    U64 => 5,
+    S8 => 6,
+    S16 => 7,
+    S32 => 8,
+    S64 => 9,
+    Binary => 10,
+    String => 11,
+    NulString => 12,
+    Nested => 13,
+    NestedArray => 14,
+    Bitfield32 => 15 )
+);
+
+impl_nl_flag!(IfaF, u32);
+impl_nl_flag!(RtmF, libc::c_uint);
+impl_nl_flag!(RtnhF, u8);
+impl_nl_flag!(Nud, u16);
+impl_nl_flag!(Ntf, u8);
+impl_nl_flag!(Iff, libc::c_uint);
+impl_nl_flag!(NlmF, u16);
+impl_nl_flag!(VlanFlag, u32);
+impl_nl_flag!(Rtmgrp, u32);
+impl_nl_flag!(FibRuleF, u32);
+impl_nl_flag!(CtStatus, u32);
+impl_nl_flag!(AuditStatusMask, u32);
+
+/// Constants of the `nl80211` wireless configuration family - requires the `nl80211` feature
+#[cfg(feature = "nl80211")]
+pub mod nl80211;
+
+/// Constants of the `wireguard` family - requires the `wireguard` feature
+#[cfg(feature = "wireguard")]
+pub mod wireguard;
+
+/// Constants of the `TASKSTATS` per-task accounting family - requires the `taskstats` feature
+#[cfg(feature = "taskstats")]
+pub mod taskstats;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::{BTreeSet,HashMap};
+    use std::hash::Hash;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(CtrlCmd::Getfamily.to_string(), "Getfamily");
+        assert_eq!(Rtm::Newlink.to_string(), "Newlink");
+        assert_eq!(CtrlCmd::UnrecognizedVariant(42).to_string(), "Unrecognized(42)");
+        assert_eq!(format!("{}", NlmF::from(0x8000u16)), "Unrecognized(32768)");
+    }
+
+    #[test]
+    fn test_from_str() {
+        for nud in vec![Nud::None, Nud::Incomplete, Nud::Reachable, Nud::Stale, Nud::Delay,
+                        Nud::Probe, Nud::Failed, Nud::Noarp, Nud::Permanent,
+                        Nud::UnrecognizedVariant(0x100)] {
+            assert_eq!(nud.to_string().parse::<Nud>().unwrap(), nud);
+        }
+        assert_eq!("broadcast".parse::<Iff>().unwrap(), Iff::Broadcast);
+        assert_eq!("UP".parse::<Iff>().unwrap(), Iff::Up);
+        assert_eq!("3".parse::<CtrlCmd>().unwrap(), CtrlCmd::Getfamily);
+        assert_eq!("99".parse::<CtrlCmd>().unwrap(), CtrlCmd::UnrecognizedVariant(99));
+        assert!("Getfam".parse::<CtrlCmd>().is_err());
+        assert!("Unrecognized(-1)".parse::<CtrlCmd>().is_err());
+    }
+
+    #[test]
+    fn test_try_from_known() {
+        assert_eq!(CtrlCmd::try_from_known(3).unwrap(), CtrlCmd::Getfamily);
+        match CtrlCmd::try_from_known(99) {
+            Err(DeError::UnknownConstant { type_name, value }) => {
+                assert_eq!(type_name, "CtrlCmd");
+                assert_eq!(value, 99);
+            },
+            r => panic!("Expected DeError::UnknownConstant, got {:?}", r),
+        }
+        assert!(CtrlCmd::UnrecognizedVariant(99).is_unrecognized());
+        assert!(!CtrlCmd::Getfamily.is_unrecognized());
+        assert!(NlFamily::try_from_known(libc::NETLINK_GENERIC).is_ok());
+    }
+
+    #[test]
+    fn test_genl_family_id() {
+        let id = GenlFamilyId(28);
+        assert_eq!(id.serialize_to_vec().unwrap(), 28u16.serialize_to_vec().unwrap());
+        assert_eq!(GenlFamilyId::from_bytes(&id.serialize_to_vec().unwrap()).unwrap(), id);
+        assert_eq!(u16::from(id), 28);
+        assert_eq!(id.to_string(), "28");
+        assert_eq!(GenlFamilyId::from(GenlId::Ctrl), GenlId::Ctrl);
+        assert!(id != GenlId::Ctrl);
+    }
+
+    #[test]
+    fn test_derived_traits() {
+        fn assert_map_key<T>() where T: Copy + Hash + Ord {}
+        assert_map_key::<CtrlAttr>();
+        assert_map_key::<Ifla>();
+        assert_map_key::<NlFamily>();
+        assert_map_key::<Rtprot>();
+        assert_map_key::<NlmF>();
+
+        let mut set = BTreeSet::new();
+        set.extend(vec![Ifla::Mtu, Ifla::UnrecognizedVariant(1000), Ifla::Unspec,
+                        Ifla::UnrecognizedVariant(5), Ifla::Address]);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![
+            Ifla::Unspec, Ifla::Address, Ifla::Mtu, Ifla::UnrecognizedVariant(5),
+            Ifla::UnrecognizedVariant(1000),
+        ]);
+
+        // Variants sharing a value are ordered as declared and still differ
+        assert!(NlmF::Root < NlmF::Replace);
+        assert!(Ifla::Mtu < Ifla::UnrecognizedVariant(4));
+
+        let mut names = HashMap::new();
+        names.insert(CtrlAttr::FamilyName, "name");
+        let attr = CtrlAttr::FamilyName;
+        assert_eq!(names.get(&attr), Some(&"name"));
+        assert_eq!(attr, CtrlAttr::FamilyName);
+    }
+
+    #[test]
+    fn test_ifla_values() {
+        for &(ref ifla, value) in &[(Ifla::Stats, 7), (Ifla::Txqlen, 13), (Ifla::Operstate, 16),
+                                (Ifla::Linkinfo, 18), (Ifla::Ifalias, 20), (Ifla::Stats64, 23),
+                                (Ifla::AfSpec, 26), (Ifla::Promiscuity, 30), (Ifla::Carrier, 33),
+                                (Ifla::Xdp, 43), (Ifla::MinMtu, 50), (Ifla::MaxMtu, 51),
+                                (Ifla::PermAddress, 54), (Ifla::DpllPin, 65)] {
+            assert_eq!(libc::c_ushort::from(*ifla), value);
+            assert_eq!(&Ifla::from(value), ifla);
+        }
+        assert_eq!(Ifla::from(66), Ifla::UnrecognizedVariant(66));
+    }
+
+    #[test]
+    fn test_rtprot_values() {
+        assert_eq!(Rtprot::from(libc::RTPROT_STATIC), Rtprot::Static);
+        assert_eq!(Rtprot::from(9), Rtprot::Ra);
+        assert_eq!(Rtprot::from(16), Rtprot::Dhcp);
+        assert_eq!(libc::c_uchar::from(Rtprot::Bgp), 186);
+        assert_eq!(libc::c_uchar::from(Rtprot::Eigrp), 192);
+        assert_eq!(Rtprot::from(250), Rtprot::UnrecognizedVariant(250));
+    }
+
+    #[test]
+    fn test_nl_flags() {
+        let mut flags = Iff::Up | Iff::Running;
+        assert!(flags.contains(&Iff::Up));
+        assert!(flags.contains(&Iff::Running));
+        assert!(!flags.contains(&Iff::Loopback));
+        assert_eq!(flags.bits(), (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_uint);
+
+        flags.insert(Iff::Loopback);
+        flags.remove(&Iff::Up);
+        assert_eq!(flags.iter().collect::<Vec<_>>(), vec![Iff::Loopback, Iff::Running]);
+        assert_eq!(flags, NlFlags::from(vec![Iff::Running, Iff::Loopback]));
+
+        flags.remove(&Iff::Loopback);
+        flags.remove(&Iff::Running);
+        assert!(flags.is_empty());
+        assert_eq!(flags, NlFlags::empty());
+    }
+
+    #[test]
+    fn test_nl_flags_serialize() {
+        let flags = NlmF::Request | NlmF::Ack;
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        flags.serialize(&mut mem).unwrap();
+        assert_eq!(flags.size(), 2);
+
+        let parsed = NlFlags::<NlmF>::deserialize(&mut StreamReadBuffer::new(mem.as_ref()))
+            .unwrap();
+        assert_eq!(parsed, flags);
+
+        let unknown = NlFlags::<Ntf>::from_bits(0x40);
+        assert_eq!(unknown.iter().collect::<Vec<_>>(), vec![Ntf::UnrecognizedVariant(0x40)]);
+    }
+
+    #[test]
+    fn test_nl_flags_ops() {
+        let up = NlFlags::from(Iff::Up);
+        let both = up.clone() | NlFlags::from(Iff::Running);
+        assert_eq!(both, Iff::Up | Iff::Running);
+        assert_eq!(both.clone() & NlFlags::from(vec![Iff::Running, Iff::Loopback]),
+                   NlFlags::from(Iff::Running));
+        assert!((both.clone() & NlFlags::from(Iff::Loopback)).is_empty());
+
+        assert_eq!(format!("{:?}", both), "NlFlags(Up | Running)");
+        assert_eq!(format!("{:?}", NlFlags::<Ntf>::from_bits(0x41)),
+                   "NlFlags(Use | UnrecognizedVariant(64))");
+        assert_eq!(format!("{:?}", NlFlags::<Ntf>::empty()), "NlFlags()");
+
+        let all = NlFlags::<Ntf>::all();
+        assert_eq!(all.bits(), !0x40);
+        assert!(all.contains(&Ntf::Router) && all.contains(&Ntf::Use));
+        assert_eq!(NlFlags::<Ntf>::from_bits(all.bits()), all);
+    }
+
+    #[test]
+    fn test_alignto_overflow() {
+        assert_eq!(checked_alignto(5).unwrap(), 8);
+        assert_eq!(checked_alignto(8).unwrap(), 8);
+        assert!(checked_alignto(usize::max_value()).is_err());
+        assert!(checked_alignto(usize::max_value() - 2).is_err());
+        assert_eq!(checked_alignto(usize::max_value() - 3).unwrap(), usize::max_value() - 3);
+        assert_eq!(alignto(usize::max_value()), usize::max_value() - 3);
+    }
+
+    #[test]
+    fn test_rtm() {
+        assert_eq!(u16::from(Rtm::Newlink), 16);
+        assert_eq!(Rtm::from(libc::RTM_GETROUTE), Rtm::Getroute);
+        assert_eq!(Rtm::from(94), Rtm::Getstats);
+        assert_eq!(Rtm::from(90), Rtm::Getnsid);
+        assert_eq!(Rtm::from(57), Rtm::Delmulticast);
+        assert_eq!(u16::from(Rtm::Setstats), 95);
+        assert_eq!(Rtm::from(106), Rtm::Getnexthop);
+        assert_eq!(u16::from(Rtm::Newlinkprop), 108);
+        assert_eq!(Rtm::from(114), Rtm::Getvlan);
+        assert_eq!(u16::from(Rtm::Gettunnel), 122);
+        assert_eq!(Rtm::from(1000), Rtm::UnrecognizedVariant(1000));
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        Rtm::Newneigh.serialize(&mut mem).unwrap();
+        let parsed = Rtm::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, Rtm::Newneigh);
+    }
+
+    #[test]
+    fn test_rtnl_groups() {
+        assert_eq!(u32::from(Rtnlgrp::Ipv4Ifaddr), 5);
+        assert_eq!(u32::from(Rtnlgrp::Nsid), 28);
+        assert_eq!(u32::from(Rtmgrp::Ipv4Ifaddr), 1 << (u32::from(Rtnlgrp::Ipv4Ifaddr) - 1));
+        assert_eq!(u32::from(Rtmgrp::Ipv6Prefix), 1 << (u32::from(Rtnlgrp::Ipv6Prefix) - 1));
+        let groups = Rtmgrp::Link | Rtmgrp::Ipv6Route;
+        assert_eq!(groups.bits(), 0x401);
+    }
+
+    #[test]
+    fn test_nl_family() {
+        assert_eq!(NlFamily::InetDiag, NlFamily::SockOrInetDiag);
+        assert_eq!(libc::c_int::from(NlFamily::InetDiag), 4);
+        assert_eq!(NlFamily::from(22), NlFamily::Smc);
+        match NlFamily::from(4) {
+            NlFamily::InetDiag => (),
+            family => panic!("Expected NlFamily::InetDiag, got {:?}", family),
+        }
+    }
+
+    #[test]
+    fn test_arphrd() {
+        assert_eq!(libc::c_ushort::from(Arphrd::Atm), 19);
+        assert_eq!(Arphrd::from(19), Arphrd::Atm);
+        assert_ne!(Arphrd::from(libc::c_ushort::from(Arphrd::Atm)), Arphrd::Appletlk);
+        assert_eq!(libc::c_ushort::from(Arphrd::Loopback), 772);
+        assert_eq!(Arphrd::from(772), Arphrd::Loopback);
+        for &(ref arphrd, value) in &[(Arphrd::Rawip, 519), (Arphrd::Hdlc, 513),
+                                      (Arphrd::Ieee802154, 804), (Arphrd::Vsockmon, 826),
+                                      (Arphrd::Ieee80211Radiotap, 803), (Arphrd::Ipgre, 778)] {
+            assert_eq!(libc::c_ushort::from(arphrd), value);
+            assert_eq!(&Arphrd::from(value), arphrd);
+        }
+        for arphrd in vec![Arphrd::Atm, Arphrd::Loopback, Arphrd::Sit, Arphrd::Can] {
+            let mut mem = StreamWriteBuffer::new_growable(None);
+            arphrd.serialize(&mut mem).unwrap();
+            let parsed = Arphrd::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+            assert_eq!(parsed, arphrd);
+        }
+    }
+
+    #[test]
+    fn test_xfrm() {
+        for (msg, val) in vec![(XfrmMsg::Newsa, 0x10u16), (XfrmMsg::Getsa, 0x12)] {
+            let mut mem = StreamWriteBuffer::new_growable(None);
+            msg.serialize(&mut mem).unwrap();
+            assert_eq!(u16::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), val);
+            let parsed = XfrmMsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+            assert_eq!(parsed, msg);
+        }
+        assert_eq!(XfrmMsg::from(0x26), XfrmMsg::Mapping);
+        assert_eq!(XfrmAttr::from(6), XfrmAttr::Sa);
+        assert_eq!(libc::c_ushort::from(XfrmAttr::IfId), 31);
+    }
+}
@@ -0,0 +1,192 @@
+//! Constants for the `nl80211` generic netlink family used to configure wireless devices
+//!
+//! These values are not defined in `libc` so they are taken from `linux/nl80211.h`. The
+//! family ID is assigned at runtime - resolve it with `resolve_genl_family("nl80211")` and use
+//! it as the `nl_type` of messages carrying a `Genlmsghdr<Nl80211Cmd, Nl80211Attr>`.
+
+use std::mem;
+
+use buffering::copy::{StreamReadBuffer, StreamWriteBuffer};
+
+use err::{DeError, SerError};
+use Nl;
+
+impl_var!(
+    /// Values for `cmd` in `Genlmsghdr` for the `nl80211` family from `enum nl80211_commands`
+    /// in `linux/nl80211.h`
+    ( Nl80211Cmd, u8,
+    Unspec => 0,
+    GetWiphy => 1,
+    SetWiphy => 2,
+    NewWiphy => 3,
+    DelWiphy => 4,
+    GetInterface => 5,
+    SetInterface => 6,
+    NewInterface => 7,
+    DelInterface => 8,
+    GetKey => 9,
+    SetKey => 10,
+    NewKey => 11,
+    DelKey => 12,
+    GetBeacon => 13,
+    SetBeacon => 14,
+    StartAp => 15,
+    StopAp => 16,
+    GetStation => 17,
+    SetStation => 18,
+    NewStation => 19,
+    DelStation => 20,
+    GetMpath => 21,
+    SetMpath => 22,
+    NewMpath => 23,
+    DelMpath => 24,
+    SetBss => 25,
+    SetReg => 26,
+    ReqSetReg => 27,
+    GetMeshConfig => 28,
+    SetMeshConfig => 29,
+    SetMgmtExtraIe => 30,
+    GetReg => 31,
+    GetScan => 32,
+    TriggerScan => 33,
+    NewScanResults => 34,
+    ScanAborted => 35,
+    RegChange => 36,
+    Authenticate => 37,
+    Associate => 38,
+    Deauthenticate => 39,
+    Disassociate => 40,
+    MichaelMicFailure => 41,
+    RegBeaconHint => 42,
+    JoinIbss => 43,
+    LeaveIbss => 44,
+    Testmode => 45,
+    Connect => 46,
+    Roam => 47,
+    Disconnect => 48 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of `nl80211` messages from
+    /// `enum nl80211_attrs` in `linux/nl80211.h`
+    ( Nl80211Attr, u16,
+    Unspec => 0,
+    Wiphy => 1,
+    WiphyName => 2,
+    Ifindex => 3,
+    Ifname => 4,
+    Iftype => 5,
+    Mac => 6,
+    KeyData => 7,
+    KeyIdx => 8,
+    KeyCipher => 9,
+    KeySeq => 10,
+    KeyDefault => 11,
+    BeaconInterval => 12,
+    DtimPeriod => 13,
+    BeaconHead => 14,
+    BeaconTail => 15,
+    StaAid => 16,
+    StaFlags => 17,
+    StaListenInterval => 18,
+    StaSupportedRates => 19,
+    StaVlan => 20,
+    StaInfo => 21,
+    WiphyBands => 22,
+    MntrFlags => 23,
+    MeshId => 24,
+    StaPlinkAction => 25,
+    MpathNextHop => 26,
+    MpathInfo => 27,
+    BssCtsProt => 28,
+    BssShortPreamble => 29,
+    BssShortSlotTime => 30,
+    HtCapability => 31,
+    SupportedIftypes => 32,
+    RegAlpha2 => 33,
+    RegRules => 34,
+    MeshConfig => 35,
+    BssBasicRates => 36,
+    WiphyTxqParams => 37,
+    WiphyFreq => 38,
+    WiphyChannelType => 39,
+    KeyDefaultMgmt => 40,
+    MgmtSubtype => 41,
+    Ie => 42,
+    MaxNumScanSsids => 43,
+    ScanFrequencies => 44,
+    ScanSsids => 45,
+    Generation => 46,
+    Bss => 47,
+    RegInitiator => 48,
+    RegType => 49,
+    SupportedCommands => 50,
+    Frame => 51,
+    Ssid => 52,
+    Wdev => 153 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes nested in `Nl80211Attr::Bss` of scan results
+    /// from `enum nl80211_bss` in `linux/nl80211.h`
+    ( Nl80211Bss, u16,
+    Invalid => 0,
+    Bssid => 1,
+    Frequency => 2,
+    Tsf => 3,
+    BeaconInterval => 4,
+    Capability => 5,
+    InformationElements => 6,
+    SignalMbm => 7,
+    SignalUnspec => 8,
+    Status => 9,
+    SeenMsAgo => 10,
+    BeaconIes => 11,
+    ChanWidth => 12,
+    BeaconTsf => 13,
+    PrespData => 14,
+    LastSeenBoottime => 15 )
+);
+
+impl_var!(
+    /// Interface types carried in `Nl80211Attr::Iftype` from `enum nl80211_iftype` in
+    /// `linux/nl80211.h`
+    ( Nl80211Iftype, u32,
+    Unspecified => 0,
+    Adhoc => 1,
+    Station => 2,
+    Ap => 3,
+    ApVlan => 4,
+    Wds => 5,
+    Monitor => 6,
+    MeshPoint => 7,
+    P2pClient => 8,
+    P2pGo => 9,
+    P2pDevice => 10,
+    Ocb => 11,
+    Nan => 12 )
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use genl::Genlmsghdr;
+    use nlattr::Nlattr;
+
+    #[test]
+    fn test_nl80211_values() {
+        assert_eq!(u8::from(Nl80211Cmd::TriggerScan), 33);
+        assert_eq!(Nl80211Cmd::from(34), Nl80211Cmd::NewScanResults);
+        assert_eq!(u16::from(Nl80211Attr::Ssid), 52);
+        assert_eq!(u16::from(Nl80211Attr::Wdev), 153);
+        assert_eq!(Nl80211Bss::from(7), Nl80211Bss::SignalMbm);
+
+        let genl = Genlmsghdr::new(Nl80211Cmd::GetInterface, 0, vec![
+            Nlattr::new_nl_payload(None, Nl80211Attr::Ifindex, 3u32).unwrap(),
+        ]).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        genl.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), &[5, 0, 0, 0, 8, 0, 3, 0, 3, 0, 0, 0][..]);
+    }
+}
@@ -0,0 +1,53 @@
+//! Constants for the `TASKSTATS` generic netlink family used to query per-task accounting
+//!
+//! These values are not defined in `libc` so they are taken from `linux/taskstats.h`. Requests
+//! carry `TaskstatsCmdAttr` attributes while replies carry `TaskstatsType` attributes - see
+//! `neli::taskstats` for decoding the replies.
+
+use std::mem;
+
+use buffering::copy::{StreamReadBuffer, StreamWriteBuffer};
+
+use err::{DeError, SerError};
+use Nl;
+
+/// Name of the family to pass to `resolve_genl_family` - value of `TASKSTATS_GENL_NAME`
+pub const TASKSTATS_GENL_NAME: &str = "TASKSTATS";
+
+/// Version of the family protocol - value of `TASKSTATS_GENL_VERSION`
+pub const TASKSTATS_GENL_VERSION: u8 = 1;
+
+/// Length of the command name in `struct taskstats` - value of `TS_COMM_LEN`
+pub const TS_COMM_LEN: usize = 32;
+
+impl_var!(
+    /// Values for `cmd` in `Genlmsghdr` for the `TASKSTATS` family
+    ( TaskstatsCmd, u8,
+    Unspec => 0,
+    Get => 1,
+    New => 2 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of `TaskstatsCmd::Get` requests
+    ( TaskstatsCmdAttr, u16,
+    Unspec => 0,
+    Pid => 1,
+    Tgid => 2,
+    RegisterCpumask => 3,
+    DeregisterCpumask => 4 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of `TaskstatsCmd::New` replies -
+    /// `TaskstatsType::AggrPid` and `TaskstatsType::AggrTgid` nest the ID and the
+    /// `TaskstatsType::Stats` struct
+    ( TaskstatsType, u16,
+    Unspec => 0,
+    Pid => 1,
+    Tgid => 2,
+    Stats => 3,
+    AggrPid => 4,
+    AggrTgid => 5,
+    Null => 6 )
+);
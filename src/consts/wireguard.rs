@@ -0,0 +1,83 @@
+//! Constants for the `wireguard` generic netlink family used to configure WireGuard devices
+//!
+//! These values are not defined in `libc` so they are taken from `linux/wireguard.h`. See
+//! `neli::wireguard` for parsing the nested device, peer and allowed IP attributes.
+
+use std::mem;
+
+use buffering::copy::{StreamReadBuffer, StreamWriteBuffer};
+
+use err::{DeError, SerError};
+use Nl;
+
+/// Name of the family to pass to `resolve_genl_family` - value of `WG_GENL_NAME`
+pub const WG_GENL_NAME: &str = "wireguard";
+
+/// Version of the family protocol - value of `WG_GENL_VERSION`
+pub const WG_GENL_VERSION: u8 = 1;
+
+/// Length of public, private and preshared keys - value of `WG_KEY_LEN`
+pub const WG_KEY_LEN: usize = 32;
+
+impl_var!(
+    /// Values for `cmd` in `Genlmsghdr` for the `wireguard` family from `enum wg_cmd`
+    ( WgCmd, u8,
+    GetDevice => 0,
+    SetDevice => 1 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the top level attributes of `wireguard` messages from
+    /// `enum wgdevice_attribute`
+    ( WgDeviceAttr, u16,
+    Unspec => 0,
+    Ifindex => 1,
+    Ifname => 2,
+    PrivateKey => 3,
+    PublicKey => 4,
+    Flags => 5,
+    ListenPort => 6,
+    Fwmark => 7,
+    Peers => 8 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of each peer nested by index in
+    /// `WgDeviceAttr::Peers` from `enum wgpeer_attribute`
+    ( WgPeerAttr, u16,
+    Unspec => 0,
+    PublicKey => 1,
+    PresharedKey => 2,
+    Flags => 3,
+    Endpoint => 4,
+    PersistentKeepaliveInterval => 5,
+    LastHandshakeTime => 6,
+    RxBytes => 7,
+    TxBytes => 8,
+    Allowedips => 9,
+    ProtocolVersion => 10 )
+);
+
+impl_var!(
+    /// Values for `nla_type` in the attributes of each allowed IP nested by index in
+    /// `WgPeerAttr::Allowedips` from `enum wgallowedip_attribute`
+    ( WgAllowedIpAttr, u16,
+    Unspec => 0,
+    Family => 1,
+    Ipaddr => 2,
+    CidrMask => 3 )
+);
+
+/// Only flag for `WgDeviceAttr::Flags` - value of `WGDEVICE_F_REPLACE_PEERS` in
+/// `enum wgdevice_flag`
+pub const WGDEVICE_F_REPLACE_PEERS: u32 = 1;
+
+impl_var!(
+    /// Flags for `WgPeerAttr::Flags` from `enum wgpeer_flag`
+    ( WgPeerF, u32,
+    RemoveMe => 1,
+    ReplaceAllowedips => 1 << 1,
+    UpdateOnly => 1 << 2 )
+);
+
+impl_nl_flag!(WgPeerF, u32);
@@ -0,0 +1,287 @@
+//! Connection tracking entries are exchanged with `NfnlSubsys::Ctnetlink` over a socket of
+//! `NlFamily::Netfilter`. Each entry is an `Nfgenmsg<Cta>` whose tuples nest the addresses
+//! and ports of each direction of the flow - ports, counters and most integers are in network
+//! byte order.
+//!
+//! Besides dumping the table with `dump_conntrack`, changes can be followed by joining the
+//! `Nfnlgrp::ConntrackNew`, `Nfnlgrp::ConntrackUpdate` and `Nfnlgrp::ConntrackDestroy`
+//! multicast groups:
+//!
+//! ```no_run
+//! use neli::consts::{Cta,Nfnlgrp,NlFamily};
+//! use neli::ctnl::CtEntry;
+//! use neli::nfnetlink::{Nfgenmsg,NfnlType};
+//! use neli::socket::NlSocket;
+//!
+//! let mut sock = NlSocket::<NfnlType, Nfgenmsg<Cta>>::connect(NlFamily::Netfilter, None,
+//!                                                             Vec::new()).unwrap();
+//! sock.add_mcast_membership(Nfnlgrp::ConntrackNew).unwrap();
+//! sock.add_mcast_membership(Nfnlgrp::ConntrackDestroy).unwrap();
+//! loop {
+//!     let msg = sock.recv_nl(None).unwrap();
+//!     // `CtnlMsg::New` for new entries and `CtnlMsg::Delete` for destroyed ones
+//!     let entry = CtEntry::from_nfgenmsg(&msg.nl_payload).unwrap();
+//!     println!("{:?} {:?}", msg.nl_type.msg_type, entry.orig);
+//! }
+//! ```
+
+use std::net::IpAddr;
+
+use buffering::copy::StreamReadBuffer;
+
+use Nl;
+use consts::{Af,CtStatus,Cta,CtaCounters,CtaIp,CtaProto,CtaTuple,CtnlMsg,NfnlSubsys,NlFlags,
+             NlmF};
+use err::{DeError,NlError};
+use nfnetlink::{Nfgenmsg,NfnlType};
+use nl::Nlmsghdr;
+use nlattr::{Nlattr,NLA_TYPE_MASK};
+use socket::{dump,NlStream};
+
+/// Addresses, protocol and ports of one direction of a tracked flow from `Cta::TupleOrig` or
+/// `Cta::TupleReply`
+#[derive(Clone,Debug,PartialEq)]
+pub struct CtTuple {
+    /// Source address
+    pub src: IpAddr,
+    /// Destination address
+    pub dst: IpAddr,
+    /// IP protocol such as `libc::IPPROTO_TCP`
+    pub proto: u8,
+    /// Source port - `None` for protocols without ports
+    pub src_port: Option<u16>,
+    /// Destination port - `None` for protocols without ports
+    pub dst_port: Option<u16>,
+}
+
+impl CtTuple {
+    /// Parse the attributes nested in a `Cta::TupleOrig` or `Cta::TupleReply` attribute
+    pub fn from_attr(attr: &Nlattr<Cta>) -> Result<Self, DeError> {
+        let mut src = None;
+        let mut dst = None;
+        let mut proto = None;
+        let mut src_port = None;
+        let mut dst_port = None;
+        attr.get_attr_handle::<CtaTuple>().parse_with(|tuple_attr| {
+            match CtaTuple::from(u16::from(&tuple_attr.nla_type) & NLA_TYPE_MASK) {
+                CtaTuple::Ip => tuple_attr.get_attr_handle::<CtaIp>().parse_with(|ip| {
+                    match CtaIp::from(u16::from(&ip.nla_type) & NLA_TYPE_MASK) {
+                        CtaIp::V4Src => { src = Some(ip.get_payload_as_ipaddr(Af::Inet)?); },
+                        CtaIp::V4Dst => { dst = Some(ip.get_payload_as_ipaddr(Af::Inet)?); },
+                        CtaIp::V6Src => { src = Some(ip.get_payload_as_ipaddr(Af::Inet6)?); },
+                        CtaIp::V6Dst => { dst = Some(ip.get_payload_as_ipaddr(Af::Inet6)?); },
+                        _ => (),
+                    };
+                    Ok(())
+                }),
+                CtaTuple::Proto => tuple_attr.get_attr_handle::<CtaProto>().parse_with(|p| {
+                    match CtaProto::from(u16::from(&p.nla_type) & NLA_TYPE_MASK) {
+                        CtaProto::Num => {
+                            proto = Some(u8::deserialize(
+                                &mut StreamReadBuffer::new(p.payload_bytes())
+                            )?);
+                        },
+                        CtaProto::SrcPort => { src_port = Some(p.get_payload_as_be_u16()?); },
+                        CtaProto::DstPort => { dst_port = Some(p.get_payload_as_be_u16()?); },
+                        _ => (),
+                    };
+                    Ok(())
+                }),
+                _ => Ok(()),
+            }
+        })?;
+        Ok(CtTuple {
+            src: src.ok_or_else(|| DeError::new("Conntrack tuple is missing its source"))?,
+            dst: dst.ok_or_else(|| DeError::new("Conntrack tuple is missing its destination"))?,
+            proto: proto.ok_or_else(|| DeError::new("Conntrack tuple is missing its protocol"))?,
+            src_port,
+            dst_port,
+        })
+    }
+}
+
+/// Packet and byte counters of one direction of a flow from `Cta::CountersOrig` or
+/// `Cta::CountersReply` - only reported if `net.netfilter.nf_conntrack_acct` is enabled
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct CtCounters {
+    /// Number of packets
+    pub packets: u64,
+    /// Number of bytes
+    pub bytes: u64,
+}
+
+impl CtCounters {
+    /// Parse the attributes nested in a `Cta::CountersOrig` or `Cta::CountersReply` attribute
+    pub fn from_attr(attr: &Nlattr<Cta>) -> Result<Self, DeError> {
+        let mut counters = CtCounters::default();
+        attr.get_attr_handle::<CtaCounters>().parse_with(|counter| {
+            match CtaCounters::from(u16::from(&counter.nla_type) & NLA_TYPE_MASK) {
+                CtaCounters::Packets => { counters.packets = counter.get_payload_as_be_u64()?; },
+                CtaCounters::Bytes => { counters.bytes = counter.get_payload_as_be_u64()?; },
+                CtaCounters::Packets32 => {
+                    counters.packets = u64::from(counter.get_payload_as_be_u32()?);
+                },
+                CtaCounters::Bytes32 => {
+                    counters.bytes = u64::from(counter.get_payload_as_be_u32()?);
+                },
+                _ => (),
+            };
+            Ok(())
+        })?;
+        Ok(counters)
+    }
+}
+
+/// Connection tracking entry
+#[derive(Clone,Debug,PartialEq)]
+pub struct CtEntry {
+    /// Layer 3 protocol of the flow
+    pub family: Af,
+    /// Tuple of the original direction
+    pub orig: CtTuple,
+    /// Tuple of the reply direction
+    pub reply: CtTuple,
+    /// Status bits
+    pub status: NlFlags<CtStatus>,
+    /// Seconds until the entry expires
+    pub timeout: Option<u32>,
+    /// Connection mark
+    pub mark: Option<u32>,
+    /// Kernel ID of the entry
+    pub id: Option<u32>,
+    /// Counters of the original direction
+    pub counters_orig: Option<CtCounters>,
+    /// Counters of the reply direction
+    pub counters_reply: Option<CtCounters>,
+}
+
+impl CtEntry {
+    /// Parse the entry carried by a `CtnlMsg::New` or `CtnlMsg::Delete` message
+    pub fn from_nfgenmsg(msg: &Nfgenmsg<Cta>) -> Result<Self, DeError> {
+        let mut orig = None;
+        let mut reply = None;
+        let mut status = NlFlags::empty();
+        let mut timeout = None;
+        let mut mark = None;
+        let mut id = None;
+        let mut counters_orig = None;
+        let mut counters_reply = None;
+        for attr in msg.attrs.iter() {
+            match Cta::from(u16::from(&attr.nla_type) & NLA_TYPE_MASK) {
+                Cta::TupleOrig => { orig = Some(CtTuple::from_attr(attr)?); },
+                Cta::TupleReply => { reply = Some(CtTuple::from_attr(attr)?); },
+                Cta::Status => { status = NlFlags::from_bits(attr.get_payload_as_be_u32()?); },
+                Cta::Timeout => { timeout = Some(attr.get_payload_as_be_u32()?); },
+                Cta::Mark => { mark = Some(attr.get_payload_as_be_u32()?); },
+                Cta::Id => { id = Some(attr.get_payload_as_be_u32()?); },
+                Cta::CountersOrig => { counters_orig = Some(CtCounters::from_attr(attr)?); },
+                Cta::CountersReply => { counters_reply = Some(CtCounters::from_attr(attr)?); },
+                _ => (),
+            };
+        }
+        Ok(CtEntry {
+            family: msg.nfgen_family,
+            orig: orig.ok_or_else(|| DeError::new("Conntrack entry is missing its tuple"))?,
+            reply: reply
+                .ok_or_else(|| DeError::new("Conntrack entry is missing its reply tuple"))?,
+            status,
+            timeout,
+            mark,
+            id,
+            counters_orig,
+            counters_reply,
+        })
+    }
+}
+
+/// Dump the connection tracking table of every address family over any `NlStream`
+/// connected to `NlFamily::Netfilter`
+pub fn dump_conntrack<S>(stream: &mut S) -> Result<Vec<CtEntry>, NlError> where S: NlStream {
+    let req = Nlmsghdr::new(None, NfnlType::new(NfnlSubsys::Ctnetlink, CtnlMsg::Get.into()),
+                            vec![NlmF::Request, NlmF::Dump], None, None,
+                            Nfgenmsg::<Cta>::new(Af::Unspec, 0, Vec::new()));
+    let mut entries = Vec::new();
+    for msg in dump(stream, req)? {
+        entries.push(CtEntry::from_nfgenmsg(&msg.nl_payload)?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    use nlattr::Nlattr;
+
+    // Nested attributes with NLA_F_NESTED set as the kernel sends them
+    fn nested<T, P>(nla_type: T, attrs: Vec<Nlattr<P>>) -> Nlattr<T>
+            where T: Nl + Into<u16> + From<u16>, P: Nl + Into<u16> + From<u16> {
+        let mut attr = Nlattr::new_nested(None, nla_type, attrs).unwrap();
+        attr.nla_type = T::from(attr.nla_type.into() | 1 << 15);
+        attr
+    }
+
+    fn tuple(nla_type: Cta, src: [u8; 4], dst: [u8; 4], sport: u16, dport: u16) -> Nlattr<Cta> {
+        nested(nla_type, vec![
+            nested(CtaTuple::Ip, vec![
+                Nlattr::new_binary_payload(None, CtaIp::V4Src, src.to_vec()),
+                Nlattr::new_binary_payload(None, CtaIp::V4Dst, dst.to_vec()),
+            ]),
+            nested(CtaTuple::Proto, vec![
+                Nlattr::new_nl_payload(None, CtaProto::Num, 6u8).unwrap(),
+                Nlattr::new_nl_payload(None, CtaProto::SrcPort, sport.to_be()).unwrap(),
+                Nlattr::new_nl_payload(None, CtaProto::DstPort, dport.to_be()).unwrap(),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let nfgen = Nfgenmsg::new(Af::Inet, 0, vec![
+            tuple(Cta::TupleOrig, [192, 0, 2, 1], [198, 51, 100, 7], 40000, 443),
+            tuple(Cta::TupleReply, [198, 51, 100, 7], [192, 0, 2, 1], 443, 40000),
+            Nlattr::new_binary_payload(None, Cta::Status, vec![0, 0, 0x01, 0x8e]),
+            Nlattr::new_binary_payload(None, Cta::Timeout, vec![0, 0, 0x01, 0x2c]),
+            nested(Cta::CountersOrig, vec![
+                Nlattr::new_binary_payload(None, CtaCounters::Packets,
+                                           vec![0, 0, 0, 0, 0, 0, 0, 3]),
+                Nlattr::new_binary_payload(None, CtaCounters::Bytes,
+                                           vec![0, 0, 0, 0, 0, 0, 0x01, 0x02]),
+            ]),
+        ]);
+        let bytes = nfgen.serialize_to_vec().unwrap();
+        let entry = CtEntry::from_nfgenmsg(&Nfgenmsg::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(entry.family, Af::Inet);
+        assert_eq!(entry.orig.src, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(entry.orig.dst, IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7)));
+        assert_eq!(entry.orig.proto, 6);
+        assert_eq!(entry.orig.src_port, Some(40000));
+        assert_eq!(entry.orig.dst_port, Some(443));
+        assert_eq!(entry.reply.src_port, Some(443));
+        assert!(entry.status.contains(&CtStatus::Assured));
+        assert!(entry.status.contains(&CtStatus::Confirmed));
+        assert!(!entry.status.contains(&CtStatus::Dying));
+        assert_eq!(entry.timeout, Some(300));
+        assert_eq!(entry.counters_orig, Some(CtCounters { packets: 3, bytes: 258 }));
+        assert_eq!(entry.counters_reply, None);
+
+        let no_reply = Nfgenmsg::new(Af::Inet, 0, vec![
+            tuple(Cta::TupleOrig, [192, 0, 2, 1], [198, 51, 100, 7], 40000, 443),
+        ]);
+        assert!(CtEntry::from_nfgenmsg(&no_reply).is_err());
+    }
+
+    // Requires CAP_NET_ADMIN and nf_conntrack_netlink - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_dump_conntrack() {
+        let mut sock = ::socket::NlSocket::<NfnlType, Nfgenmsg<Cta>>::connect(
+            ::consts::NlFamily::Netfilter, None, Vec::new()
+        ).unwrap();
+        for entry in dump_conntrack(&mut sock).unwrap() {
+            assert_eq!(entry.orig.proto, entry.reply.proto);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std;
 use std::error::Error;
 use std::fmt::{self,Display};
-use std::io;
+use std::io::{self,Read};
 use std::mem;
 use std::str;
 use std::string;
@@ -10,15 +10,16 @@ use libc;
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 
 use Nl;
-use nl::{Nlmsghdr,NlEmpty};
-use consts::NlType;
+use nl::Nlmsghdr;
+use nlattr::AttrHandle;
+use consts::{NlFlags,NlmF,NlmsgerrAttr,NlType};
 
 macro_rules! try_err_compat {
-    ( $err_name:ident, $( $from_err_name:path ),* ) => {
+    ( $err_name:ident, $( $from_err_name:path => $var:ident ),* ) => {
         $(
             impl From<$from_err_name> for $err_name {
                 fn from(v: $from_err_name) -> Self {
-                    $err_name::new(v.description())
+                    $err_name::$var(v)
                 }
             }
         )*
@@ -30,12 +31,68 @@ pub struct Nlmsgerr<T> {
     /// Error code
     pub error: libc::c_int,
     /// Packet header for request that failed
-    pub nlmsg: Nlmsghdr<T, NlEmpty>,
+    pub nlmsg: Nlmsghdr<T, ()>,
+    /// Human readable reason for the error from the `NlmsgerrAttr::Msg` extended
+    /// acknowledgement attribute
+    pub ext_msg: Option<String>,
+    /// Offset of the attribute that caused the error in the original request from the
+    /// `NlmsgerrAttr::Offs` extended acknowledgement attribute
+    pub ext_offs: Option<u32>,
+}
+
+impl<T> Nlmsgerr<T> {
+    /// Returns `true` if this message is an acknowledgement (error code of 0)
+    pub fn is_ack(&self) -> bool {
+        self.error == 0
+    }
+
+    /// Convert to `NlAck` if this message is an acknowledgement, to `NlError::ExtAck` if the
+    /// kernel attached extended acknowledgement attributes or to `NlError::Errno` otherwise
+    pub fn into_ack(self) -> Result<NlAck<T>, NlError> {
+        if self.is_ack() {
+            Ok(NlAck { nlmsg: self.nlmsg })
+        } else if self.ext_msg.is_some() || self.ext_offs.is_some() {
+            Err(NlError::ExtAck { errno: -self.error, msg: self.ext_msg, offset: self.ext_offs })
+        } else {
+            Err(NlError::Errno(-self.error))
+        }
+    }
+}
+
+impl<T> Nlmsgerr<T> where T: NlType {
+    /// Deserialize an error payload according to the flags of the enclosing header - the
+    /// echoed request is skipped unless `NlmF::Capped` is set and the extended
+    /// acknowledgement attributes following it are parsed if `NlmF::AckTlvs` is set
+    ///
+    /// Malformed extended acknowledgement attributes are ignored so that the error code is
+    /// still reported.
+    pub fn deserialize_with_flags<B>(mem: &mut StreamReadBuffer<B>, nl_flags: &NlFlags<NlmF>)
+            -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut err = Self::deserialize_with(mem, nl_flags.contains(&NlmF::Capped))?;
+        if nl_flags.contains(&NlmF::AckTlvs) {
+            let mut tlvs = Vec::new();
+            mem.read_to_end(&mut tlvs)?;
+            let mut handle = AttrHandle::<NlmsgerrAttr>::Bin(tlvs.as_slice());
+            if handle.parse_nested_attributes().is_ok() {
+                err.ext_msg = handle.get_attribute(NlmsgerrAttr::Msg)
+                    .map(|attr| attr.get_payload_as_string_lossy());
+                err.ext_offs = match handle.get_attr_payload(NlmsgerrAttr::Offs) {
+                    Ok(Some(payload)) => {
+                        u32::deserialize(&mut StreamReadBuffer::new(payload)).ok()
+                    },
+                    _ => None,
+                };
+            }
+        }
+        Ok(err)
+    }
 }
 
 impl<T> Nl for Nlmsgerr<T> where T: NlType {
     type SerIn = ();
-    type DeIn = ();
+    /// Whether `NlmF::Capped` was set on the enclosing header, meaning that the payload of
+    /// the original request was omitted by the kernel
+    type DeIn = bool;
 
     fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.error.serialize(mem)?;
@@ -44,10 +101,20 @@ impl<T> Nl for Nlmsgerr<T> where T: NlType {
     }
 
     fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
-        Ok(Nlmsgerr {
-            error: libc::c_int::deserialize(mem)?,
-            nlmsg: Nlmsghdr::<T, NlEmpty>::deserialize(mem)?,
-        })
+        Self::deserialize_with(mem, false)
+    }
+
+    fn deserialize_with<B>(mem: &mut StreamReadBuffer<B>, capped: bool) -> Result<Self, DeError>
+            where B: AsRef<[u8]> {
+        let error = libc::c_int::deserialize(mem)?;
+        let nlmsg = Nlmsghdr::<T, ()>::deserialize_header(mem)?;
+        if !capped {
+            // Skip the payload of the echoed request - older kernels omit it for
+            // acknowledgements without setting `NlmF::Capped` so a short read is tolerated
+            let payload_len = (nlmsg.nl_len as usize).saturating_sub(nlmsg.size());
+            io::copy(&mut mem.by_ref().take(payload_len as u64), &mut io::sink())?;
+        }
+        Ok(Nlmsgerr { error, nlmsg, ext_msg: None, ext_offs: None })
     }
 
     fn size(&self) -> usize {
@@ -55,6 +122,14 @@ impl<T> Nl for Nlmsgerr<T> where T: NlType {
     }
 }
 
+/// Struct representing a successful acknowledgement - an `Nlmsg::Error` message with an error
+/// code of 0
+#[derive(Debug,PartialEq)]
+pub struct NlAck<T> {
+    /// Packet header for the request that was acknowledged
+    pub nlmsg: Nlmsghdr<T, ()>,
+}
+
 /// Netlink protocol error
 #[derive(Debug)]
 pub enum NlError {
@@ -62,25 +137,91 @@ pub enum NlError {
     Msg(String),
     /// No ack was received when `NlmF::Ack` was specified in the request
     NoAck,
+    /// The kernel set `NlmF::DumpIntr` on a dump response, meaning the dump is inconsistent
+    /// and should be restarted
+    DumpInterrupted,
+    /// The kernel responded with an `Nlmsg::Error` message containing this (positive) errno
+    Errno(libc::c_int),
+    /// The kernel responded with an `Nlmsg::Error` message containing this (positive) errno
+    /// along with extended acknowledgement attributes - see `NlSocket::set_ext_ack`
+    ///
+    /// Use `NlError::errno` to match on the errno of both `Errno` and `ExtAck`.
+    ExtAck {
+        /// Error code
+        errno: libc::c_int,
+        /// Human readable reason for the error
+        msg: Option<String>,
+        /// Offset of the attribute that caused the error in the original request
+        offset: Option<u32>,
+    },
+    /// The socket receive buffer overflowed (`ENOBUFS`) and messages were dropped
+    Overrun,
+    /// A datagram was larger than the buffer it was received into and was dropped - retry
+    /// with a buffer of at least `needed` bytes, see `NlSocket::set_recv_buffer_size`
+    Truncated {
+        /// Length of the dropped datagram
+        needed: usize,
+    },
+    /// A message of a different `nl_type` than expected was received
+    UnexpectedType {
+        /// The expected value of `nl_type`
+        expected: u16,
+        /// The value of `nl_type` that was received
+        got: u16,
+    },
+    /// An `Nlmsg::Done` message was received in place of the expected message
+    UnexpectedDone,
+    /// IO error from the socket
+    Io(io::Error),
+    /// Serialization error
+    Ser(SerError),
+    /// Deserialization error
+    De(DeError),
 }
 
-try_err_compat!(NlError, io::Error, SerError, DeError);
+try_err_compat!(NlError, io::Error => Io, SerError => Ser, DeError => De);
 
 impl NlError {
     /// Create new error from `&str`
     pub fn new(s: &str) -> Self {
         NlError::Msg(s.to_string())
     }
+
+    /// Get the errno of an error response from the kernel whether or not it carried extended
+    /// acknowledgement attributes
+    pub fn errno(&self) -> Option<libc::c_int> {
+        match *self {
+            NlError::Errno(errno) | NlError::ExtAck { errno, .. } => Some(errno),
+            _ => None,
+        }
+    }
 }
 
 /// Netlink protocol error
 impl Display for NlError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let msg = match *self {
-            NlError::Msg(ref msg) => msg,
-            NlError::NoAck => "No ack received",
-        };
-        write!(f, "{}", msg)
+        match *self {
+            NlError::Errno(errno) => write!(f, "Error response received from netlink: {}",
+                                            io::Error::from_raw_os_error(errno)),
+            NlError::ExtAck { errno, msg: Some(ref msg), .. } => {
+                write!(f, "Error response received from netlink: {}: {}",
+                       io::Error::from_raw_os_error(errno), msg)
+            },
+            NlError::ExtAck { errno, msg: None, .. } => {
+                write!(f, "Error response received from netlink: {}",
+                       io::Error::from_raw_os_error(errno))
+            },
+            NlError::UnexpectedType { expected, got } => {
+                write!(f, "Expected message of type {} but received type {}", expected, got)
+            },
+            NlError::Truncated { needed } => {
+                write!(f, "Received datagram was truncated - {} bytes are needed", needed)
+            },
+            NlError::Io(ref e) => write!(f, "{}", e),
+            NlError::Ser(ref e) => write!(f, "{}", e),
+            NlError::De(ref e) => write!(f, "{}", e),
+            _ => write!(f, "{}", self.description()),
+        }
     }
 }
 
@@ -89,57 +230,283 @@ impl Error for NlError {
         match *self {
             NlError::Msg(ref msg) => msg.as_str(),
             NlError::NoAck => "No ack received",
+            NlError::DumpInterrupted => "Dump was interrupted and is inconsistent",
+            NlError::Errno(_) | NlError::ExtAck { .. } => "Error response received from netlink",
+            NlError::Overrun => "Socket receive buffer overrun - messages were dropped",
+            NlError::Truncated { .. } => "Received datagram was truncated",
+            NlError::UnexpectedType { .. } => "Unexpected message type received",
+            NlError::UnexpectedDone => "Done message received in place of the expected message",
+            NlError::Io(ref e) => e.description(),
+            NlError::Ser(ref e) => e.description(),
+            NlError::De(ref e) => e.description(),
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            NlError::Io(ref e) => Some(e),
+            NlError::Ser(ref e) => Some(e),
+            NlError::De(ref e) => Some(e),
+            _ => None,
         }
     }
 }
 
 /// Serialization error
 #[derive(Debug)]
-pub struct SerError(String);
+pub enum SerError {
+    /// Error with a message describing the failure
+    Msg(String),
+    /// IO error while writing to the buffer
+    Io(io::Error),
+}
 
 impl SerError {
     /// Create a new error with the given message as description
     pub fn new<T: ToString>(msg: T) -> Self {
-        SerError(msg.to_string())
+        SerError::Msg(msg.to_string())
     }
 }
 
-try_err_compat!(SerError, io::Error);
+try_err_compat!(SerError, io::Error => Io);
 
 impl Display for SerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match *self {
+            SerError::Msg(ref msg) => write!(f, "{}", msg),
+            SerError::Io(ref e) => write!(f, "{}", e),
+        }
     }
 }
 
 impl Error for SerError {
     fn description(&self) -> &str {
-        self.0.as_str()
+        match *self {
+            SerError::Msg(ref msg) => msg.as_str(),
+            SerError::Io(ref e) => e.description(),
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SerError::Io(ref e) => Some(e),
+            _ => None,
+        }
     }
 }
 
 /// Deserialization error
 #[derive(Debug)]
-pub struct DeError(String);
+pub enum DeError {
+    /// Error with a message describing the failure
+    Msg(String),
+    /// IO error while reading from the buffer
+    Io(io::Error),
+    /// Invalid UTF-8 in a string slice
+    Utf8(str::Utf8Error),
+    /// Invalid UTF-8 in an owned string
+    FromUtf8(string::FromUtf8Error),
+    /// Invalid null byte placement in a C string
+    FromBytesWithNul(std::ffi::FromBytesWithNulError),
+    /// Value without a variant in an enum created with `impl_var` - returned by
+    /// `try_from_known` and `Nl::deserialize_strict`
+    UnknownConstant {
+        /// Name of the enum
+        type_name: &'static str,
+        /// Value that has no variant
+        value: i64,
+    },
+    /// Buffer ending before the length declared by the message - receiving more data and
+    /// retrying may succeed
+    Incomplete {
+        /// Number of bytes declared by the message
+        needed: usize,
+        /// Number of bytes available in the buffer
+        got: usize,
+    },
+}
 
 impl DeError {
     /// Create new error from `&str`
     pub fn new(s: &str) -> Self {
-        DeError(s.to_string())
+        DeError::Msg(s.to_string())
     }
 }
 
-try_err_compat!(DeError, io::Error, str::Utf8Error, string::FromUtf8Error,
-                std::ffi::FromBytesWithNulError);
+try_err_compat!(DeError, io::Error => Io, str::Utf8Error => Utf8,
+                string::FromUtf8Error => FromUtf8,
+                std::ffi::FromBytesWithNulError => FromBytesWithNul);
 
 impl Display for DeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match *self {
+            DeError::Msg(ref msg) => write!(f, "{}", msg),
+            DeError::Io(ref e) => write!(f, "{}", e),
+            DeError::Utf8(ref e) => write!(f, "{}", e),
+            DeError::FromUtf8(ref e) => write!(f, "{}", e),
+            DeError::FromBytesWithNul(ref e) => write!(f, "{}", e),
+            DeError::UnknownConstant { type_name, value } => {
+                write!(f, "Unrecognized value {} for {}", value, type_name)
+            },
+            DeError::Incomplete { needed, got } => {
+                write!(f, "Message of {} bytes is incomplete - only {} bytes available", needed,
+                       got)
+            },
+        }
     }
 }
 
 impl Error for DeError {
     fn description(&self) -> &str {
-        self.0.as_str()
+        match *self {
+            DeError::Msg(ref msg) => msg.as_str(),
+            DeError::Io(ref e) => e.description(),
+            DeError::Utf8(ref e) => e.description(),
+            DeError::FromUtf8(ref e) => e.description(),
+            DeError::FromBytesWithNul(ref e) => e.description(),
+            DeError::UnknownConstant { .. } => "Unrecognized value for a constant enum",
+            DeError::Incomplete { .. } => "Message is incomplete",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DeError::Io(ref e) => Some(e),
+            DeError::Utf8(ref e) => Some(e),
+            DeError::FromUtf8(ref e) => Some(e),
+            DeError::FromBytesWithNul(ref e) => Some(e),
+            DeError::Msg(_) | DeError::UnknownConstant { .. } | DeError::Incomplete { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use byteorder::{NativeEndian,WriteBytesExt};
+    use libc;
+
+    use consts::Nlmsg;
+
+    fn write_ack(c: &mut Cursor<Vec<u8>>, error: i32, include_payload: bool) {
+        c.write_i32::<NativeEndian>(error).unwrap();
+        c.write_u32::<NativeEndian>(20).unwrap();
+        c.write_u16::<NativeEndian>(Nlmsg::Noop.into()).unwrap();
+        c.write_u16::<NativeEndian>(0).unwrap();
+        c.write_u32::<NativeEndian>(1).unwrap();
+        c.write_u32::<NativeEndian>(0).unwrap();
+        if include_payload {
+            c.write_u32::<NativeEndian>(0xdeadbeef).unwrap();
+        }
+        c.write_u32::<NativeEndian>(0xfeedface).unwrap();
+    }
+
+    #[test]
+    fn test_error_source() {
+        let err = DeError::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert!(err.source().is_some());
+        let err = NlError::from(err);
+        assert!(err.source().unwrap().source().is_some());
+        assert!(SerError::from(io::Error::from(io::ErrorKind::WriteZero)).source().is_some());
+        assert!(DeError::new("Manual error").source().is_none());
+        assert_eq!(DeError::new("Manual error").to_string(), "Manual error");
+    }
+
+    #[test]
+    fn test_uncapped_ack() {
+        let mut c = Cursor::new(Vec::new());
+        write_ack(&mut c, 0, true);
+        let mut mem = StreamReadBuffer::new(c.into_inner());
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with(&mut mem, false).unwrap();
+        assert_eq!(u32::deserialize(&mut mem).unwrap(), 0xfeedface);
+        let ack = err.into_ack().unwrap();
+        assert_eq!(ack.nlmsg.nl_len, 20);
+        assert_eq!(ack.nlmsg.nl_seq, 1);
+    }
+
+    #[test]
+    fn test_uncapped_ack_huge_len() {
+        let mut c = Cursor::new(Vec::new());
+        c.write_i32::<NativeEndian>(0).unwrap();
+        c.write_u32::<NativeEndian>(u32::max_value()).unwrap();
+        c.write_u16::<NativeEndian>(Nlmsg::Noop.into()).unwrap();
+        c.write_u16::<NativeEndian>(0).unwrap();
+        c.write_u32::<NativeEndian>(1).unwrap();
+        c.write_u32::<NativeEndian>(0).unwrap();
+        c.write_u32::<NativeEndian>(0xdeadbeef).unwrap();
+        let mut mem = StreamReadBuffer::new(c.into_inner());
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with(&mut mem, false).unwrap();
+        assert_eq!(err.nlmsg.nl_len, u32::max_value());
+        assert!(err.is_ack());
+        assert!(u32::deserialize(&mut mem).is_err());
+    }
+
+    #[test]
+    fn test_capped_ack() {
+        let mut c = Cursor::new(Vec::new());
+        write_ack(&mut c, 0, false);
+        let mut mem = StreamReadBuffer::new(c.into_inner());
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with(&mut mem, true).unwrap();
+        assert_eq!(u32::deserialize(&mut mem).unwrap(), 0xfeedface);
+        assert!(err.is_ack());
+    }
+
+    #[test]
+    fn test_ext_ack() {
+        let mut c = Cursor::new(Vec::new());
+        write_ack(&mut c, -libc::EOPNOTSUPP, false);
+        // Replace the trailing marker with the extended ack attributes
+        let mut bytes = c.into_inner();
+        let len = bytes.len();
+        bytes.truncate(len - 4);
+        bytes.write_u16::<NativeEndian>(4 + 20).unwrap();
+        bytes.write_u16::<NativeEndian>(NlmsgerrAttr::Msg.into()).unwrap();
+        bytes.extend_from_slice(b"Unknown device type\0");
+        bytes.write_u16::<NativeEndian>(8).unwrap();
+        bytes.write_u16::<NativeEndian>(NlmsgerrAttr::Offs.into()).unwrap();
+        bytes.write_u32::<NativeEndian>(36).unwrap();
+
+        let mut mem = StreamReadBuffer::new(bytes);
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with_flags(&mut mem,
+                                                            &(NlmF::Capped | NlmF::AckTlvs))
+            .unwrap();
+        assert_eq!(err.ext_msg, Some("Unknown device type".to_string()));
+        assert_eq!(err.ext_offs, Some(36));
+        let err = err.into_ack().unwrap_err();
+        assert_eq!(err.errno(), Some(libc::EOPNOTSUPP));
+        match err {
+            NlError::ExtAck { errno, msg, offset } => {
+                assert_eq!(errno, libc::EOPNOTSUPP);
+                assert_eq!(msg, Some("Unknown device type".to_string()));
+                assert_eq!(offset, Some(36));
+            },
+            _ => panic!("Expected NlError::ExtAck"),
+        }
+
+        // Without `NlmF::AckTlvs` the trailing data is left unread
+        let mut c = Cursor::new(Vec::new());
+        write_ack(&mut c, -libc::EINVAL, false);
+        let mut mem = StreamReadBuffer::new(c.into_inner());
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with_flags(&mut mem,
+                                                            &NlFlags::from(NlmF::Capped))
+            .unwrap();
+        assert_eq!(err.ext_msg, None);
+        assert_eq!(u32::deserialize(&mut mem).unwrap(), 0xfeedface);
+    }
+
+    #[test]
+    fn test_error_not_ack() {
+        let mut c = Cursor::new(Vec::new());
+        write_ack(&mut c, -libc::EINVAL, false);
+        let mut mem = StreamReadBuffer::new(c.into_inner());
+        let err = Nlmsgerr::<Nlmsg>::deserialize_with(&mut mem, true).unwrap();
+        assert!(!err.is_ack());
+        match err.into_ack() {
+            Err(NlError::Errno(i)) => assert_eq!(i, libc::EINVAL),
+            _ => panic!("Expected NlError::Errno"),
+        }
     }
 }
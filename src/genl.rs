@@ -1,68 +1,415 @@
+use std::io::Read;
+use std::mem;
+
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 
-use {Nl,SerError,DeError};
+use {is_strict_deserialization,Nl,SerError,DeError};
+use consts::{alignto,CtrlAttr,CtrlAttrMcastGrp,CtrlAttrOp,NlAttrType,NlPolicyTypeAttr};
 use nlattr::{Nlattr,AttrHandle};
 
 /// Struct representing generic netlink header and payload
+///
+/// Some families place a fixed size header of their own between the generic netlink header
+/// and the attributes - its length is advertised in `CtrlAttr::Hdrsize` when resolving the
+/// family. `H` is the type of this header and is padded to `NLA_ALIGNTO` on the wire. Families
+/// without one use the default of `()`. A header that is only known by its length is parsed
+/// with `deserialize_with_hdrsize`.
+///
+/// The reserved field of the header is always zero in packets created by `neli`. Packets
+/// received with a nonzero reserved field are rejected by `deserialize_strict` and
+/// `deserialize_with(mem, false)` and accepted otherwise.
 #[derive(Debug,PartialEq)]
-pub struct Genlmsghdr<C> {
+pub struct Genlmsghdr<C, T, H = ()> {
     /// Generic netlink message command
     pub cmd: C,
     /// Version of generic netlink family protocol
     pub version: u8,
     reserved: u16,
+    /// Family specific header preceding the attributes
+    pub family_header: H,
     /// Attributes included in generic netlink message
-    attrs: Vec<u8>,
+    attrs: Vec<Nlattr<T>>,
 }
 
-impl<C> Genlmsghdr<C> where C: From<u8> + Into<u8> {
+impl<C, T, H> Genlmsghdr<C, T, H> {
+    /// Length of the generic netlink header fields preceding the family header and attributes
+    pub const HEADER_LEN: usize = mem::size_of::<u8>() + mem::size_of::<u8>()
+        + mem::size_of::<u16>();
+
+    /// Get the reserved field of the header
+    pub fn reserved(&self) -> u16 {
+        self.reserved
+    }
+
+    /// Get the attributes of the packet
+    pub fn attrs(&self) -> &[Nlattr<T>] {
+        self.attrs.as_slice()
+    }
+
+    /// Get the attributes of the packet for modification
+    pub fn attrs_mut(&mut self) -> &mut Vec<Nlattr<T>> {
+        &mut self.attrs
+    }
+
+    /// Append an attribute to the packet
+    pub fn add_attr(&mut self, attr: Nlattr<T>) {
+        self.attrs.push(attr);
+    }
+}
+
+impl<C, T> Genlmsghdr<C, T> where C: From<u8> + Into<u8>, T: Nl + Into<u16> + From<u16> {
     /// Create new generic netlink packet
-    pub fn new<T>(cmd: C, version: u8, mut attrs: Vec<Nlattr<T>>)
-            -> Result<Self, SerError> where T: Nl + Into<u16> + From<u16> {
-        let mut mem = StreamWriteBuffer::new_growable(Some(attrs.iter().fold(0, |acc, item| {
-            acc + item.asize()
-        })));
-        for item in attrs.iter_mut() {
-            item.serialize(&mut mem)?;
-        }
+    pub fn new(cmd: C, version: u8, attrs: Vec<Nlattr<T>>) -> Result<Self, SerError> {
+        Self::new_with_header(cmd, version, (), attrs)
+    }
+
+    /// Start building a generic netlink packet with attributes added one at a time - see
+    /// `GenlmsghdrBuilder`
+    pub fn builder(cmd: C, version: u8) -> GenlmsghdrBuilder<C, T> {
+        GenlmsghdrBuilder::new(cmd, version)
+    }
+
+}
+
+impl<C, T, H> Genlmsghdr<C, T, H> where C: From<u8> + Into<u8>, T: Nl + Into<u16> + From<u16> {
+    /// Create new generic netlink packet with a family specific header
+    pub fn new_with_header(cmd: C, version: u8, family_header: H, attrs: Vec<Nlattr<T>>)
+            -> Result<Self, SerError> {
         Ok(Genlmsghdr {
             cmd,
             version,
             reserved: 0,
-            attrs: mem.as_ref().to_vec(),
+            family_header,
+            attrs,
         })
     }
 
     /// Get handle for attribute parsing and traversal
-    pub fn get_attr_handle<T>(&self) -> AttrHandle<T> where T: Nl + Into<u16> + From<u16> {
-        AttrHandle::Bin(self.attrs.as_slice())
+    pub fn get_attr_handle(&self) -> AttrHandle<T> {
+        AttrHandle::Borrowed(self.attrs.as_slice())
+    }
+
+    /// Convert the attributes to the attribute type `A` through their numeric types - this
+    /// replaces `get_attr_handle::<A>()` for packets received with numeric attribute types
+    pub fn into_attr_type<A>(self) -> Genlmsghdr<C, A, H> where A: Nl + Into<u16> + From<u16> {
+        Genlmsghdr {
+            cmd: self.cmd,
+            version: self.version,
+            reserved: self.reserved,
+            family_header: self.family_header,
+            attrs: self.attrs.into_iter().map(|attr| Nlattr {
+                nla_len: attr.nla_len,
+                nla_type: A::from(attr.nla_type.into()),
+                payload: attr.payload,
+            }).collect(),
+        }
+    }
+}
+
+impl<C, T> Genlmsghdr<C, T, Vec<u8>> where C: Nl + From<u8> + Into<u8>,
+        T: Nl + Into<u16> + From<u16> {
+    /// Deserialize a packet of a family whose header is not modeled by a type, keeping the
+    /// `hdrsize` bytes of the family header as raw bytes - `hdrsize` is the value of
+    /// `CtrlAttr::Hdrsize` reported for the family
+    pub fn deserialize_with_hdrsize<B>(mem: &mut StreamReadBuffer<B>, hdrsize: usize)
+            -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let cmd = C::deserialize(mem)?;
+        let version = u8::deserialize(mem)?;
+        let reserved = deserialize_reserved(mem, !is_strict_deserialization())?;
+        let mut family_header = vec![0; hdrsize];
+        mem.read_exact(family_header.as_mut_slice())?;
+        skip_header_padding(mem, hdrsize);
+        Ok(Genlmsghdr {
+            cmd,
+            version,
+            reserved,
+            family_header,
+            attrs: Vec::<Nlattr<T>>::deserialize(mem)?,
+        })
+    }
+}
+
+// Deserialize the reserved field of the header, failing if it is nonzero unless `lenient`
+fn deserialize_reserved<B>(mem: &mut StreamReadBuffer<B>, lenient: bool) -> Result<u16, DeError>
+        where B: AsRef<[u8]> {
+    let reserved = u16::deserialize(mem)?;
+    if reserved != 0 && !lenient {
+        return Err(DeError::new("Reserved field of generic netlink header is nonzero"));
+    }
+    Ok(reserved)
+}
+
+// Skip the padding following a family header of `len` bytes - a missing padding is tolerated
+// for packets without attributes
+fn skip_header_padding<B>(mem: &mut StreamReadBuffer<B>, len: usize) where B: AsRef<[u8]> {
+    let padding = &mut [0u8; 4][0..alignto(len) - len];
+    let _ = mem.read_exact(padding);
+}
+
+/// Builder accumulating attributes for a `Genlmsghdr`
+///
+/// Payloads are serialized as they are added and the first serialization error is returned
+/// from `build`.
+///
+/// ```
+/// use neli::consts::{CtrlAttr,CtrlCmd};
+/// use neli::genl::Genlmsghdr;
+///
+/// let genl = Genlmsghdr::builder(CtrlCmd::Getfamily, 2)
+///     .attr(CtrlAttr::FamilyName, "nl80211")
+///     .build()
+///     .unwrap();
+/// ```
+pub struct GenlmsghdrBuilder<C, T> {
+    cmd: C,
+    version: u8,
+    attrs: Vec<Nlattr<T>>,
+    err: Option<SerError>,
+}
+
+impl<C, T> GenlmsghdrBuilder<C, T> where C: From<u8> + Into<u8>, T: Nl + Into<u16> + From<u16> {
+    /// Create a builder for a packet with the given command and version and no attributes
+    pub fn new(cmd: C, version: u8) -> Self {
+        GenlmsghdrBuilder { cmd, version, attrs: Vec::new(), err: None }
+    }
+
+    /// Add an attribute with a payload from an object implementing `Nl` - string slices are
+    /// null terminated as in `Nlattr::new_str_payload`
+    pub fn attr<P>(mut self, nla_type: T, payload: P) -> Self where P: Nl {
+        if self.err.is_none() {
+            match Nlattr::new_nl_payload(None, nla_type, payload) {
+                Ok(attr) => self.attrs.push(attr),
+                Err(e) => self.err = Some(e),
+            }
+        }
+        self
+    }
+
+    /// Add an already constructed attribute such as one created with `Nlattr::new_nested`
+    pub fn nlattr(mut self, attr: Nlattr<T>) -> Self {
+        self.attrs.push(attr);
+        self
+    }
+
+    /// Create a `Genlmsghdr` from the accumulated attributes
+    pub fn build(self) -> Result<Genlmsghdr<C, T>, SerError> {
+        if let Some(e) = self.err {
+            return Err(e);
+        }
+        Genlmsghdr::new(self.cmd, self.version, self.attrs)
     }
 }
 
-impl<C> Nl for Genlmsghdr<C> where C: Nl + From<u8> + Into<u8> {
+impl<C, T, H> Nl for Genlmsghdr<C, T, H> where C: Nl + From<u8> + Into<u8>,
+        T: Nl + Into<u16> + From<u16>, H: Nl {
     type SerIn = ();
-    type DeIn = ();
+    /// Whether to accept a nonzero reserved field instead of failing
+    type DeIn = bool;
 
     fn serialize(&self, cur: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.cmd.serialize(cur)?;
         self.version.serialize(cur)?;
         self.reserved.serialize(cur)?;
+        self.family_header.serialize(cur)?;
+        let padding_len = self.family_header.asize() - self.family_header.size();
+        if padding_len > 0 {
+            [0u8; 4][0..padding_len].as_ref().serialize(cur)?;
+        }
         self.attrs.serialize(cur)?;
         Ok(())
     }
 
-    fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Self::deserialize_with(mem, !is_strict_deserialization())
+    }
+
+    fn deserialize_with<B>(mem: &mut StreamReadBuffer<B>, lenient: bool) -> Result<Self, DeError>
+            where B: AsRef<[u8]> {
+        let cmd = C::deserialize(mem)?;
+        let version = u8::deserialize(mem)?;
+        let reserved = deserialize_reserved(mem, lenient)?;
+        let family_header = H::deserialize(mem)?;
+        skip_header_padding(mem, family_header.size());
         Ok(Genlmsghdr {
-            cmd: C::deserialize(mem)?,
-            version: u8::deserialize(mem)?,
-            reserved: u16::deserialize(mem)?,
-            attrs: Vec::<u8>::deserialize(mem)?,
+            cmd,
+            version,
+            reserved,
+            family_header,
+            attrs: Vec::<Nlattr<T>>::deserialize(mem)?,
         })
     }
 
     fn size(&self) -> usize {
         self.cmd.size() + self.version.size() + self.reserved.size()
-            + self.attrs.size()
+            + self.family_header.asize() + self.attrs.size()
+    }
+}
+
+/// Operation supported by a generic netlink family as reported in `CtrlAttr::Ops`
+#[derive(Clone,Debug,PartialEq)]
+pub struct GenlOp {
+    /// Command of the operation
+    pub id: u32,
+    /// Flags of the operation such as `GENL_ADMIN_PERM`
+    pub flags: u32,
+}
+
+/// Multicast group of a generic netlink family as reported in `CtrlAttr::McastGroups`
+#[derive(Clone,Debug,PartialEq)]
+pub struct GenlMcastGroup {
+    /// Name of the group
+    pub name: String,
+    /// Numeric ID of the group used for subscribing
+    pub id: u32,
+}
+
+/// Description of a generic netlink family from a `CtrlCmd::Newfamily` response
+#[derive(Clone,Debug,PartialEq)]
+pub struct GenlFamilyInfo {
+    /// Numeric netlink ID of the family
+    pub id: u16,
+    /// Name of the family
+    pub name: String,
+    /// Version of the family protocol
+    pub version: u32,
+    /// Length of the family specific header following the generic netlink header
+    pub hdrsize: u32,
+    /// Highest attribute type of the family
+    pub maxattr: u32,
+    /// Operations supported by the family
+    pub ops: Vec<GenlOp>,
+    /// Multicast groups of the family
+    pub mcast_groups: Vec<GenlMcastGroup>,
+}
+
+impl GenlFamilyInfo {
+    /// Decode the attributes of a `CtrlCmd::Newfamily` response - operations and multicast
+    /// groups are nested by index and are empty if the family has none
+    pub fn from_attr_handle(mut handle: AttrHandle<CtrlAttr>) -> Result<Self, DeError> {
+        handle.parse_nested_attributes()?;
+        let name = handle.get_attribute(CtrlAttr::FamilyName)
+            .ok_or_else(|| DeError::new("Family is missing its name"))?
+            .get_payload_as_string_lossy();
+        let mut info = GenlFamilyInfo {
+            id: handle.get_payload_with::<u16>(CtrlAttr::FamilyId, None)?,
+            name,
+            version: attr_value(&handle, CtrlAttr::Version)?.unwrap_or(0),
+            hdrsize: attr_value(&handle, CtrlAttr::Hdrsize)?.unwrap_or(0),
+            maxattr: attr_value(&handle, CtrlAttr::Maxattr)?.unwrap_or(0),
+            ops: Vec::new(),
+            mcast_groups: Vec::new(),
+        };
+        if let Some(ops) = handle.get_attribute(CtrlAttr::Ops) {
+            let ops = ops.get_nested_attributes::<u16>()?;
+            for op in ops.iter().into_iter().flatten() {
+                let mut op_handle = op.get_nested_attributes::<CtrlAttrOp>()?;
+                info.ops.push(GenlOp {
+                    id: op_handle.get_payload_with::<u32>(CtrlAttrOp::Id, None)?,
+                    flags: attr_value(&op_handle, CtrlAttrOp::Flags)?.unwrap_or(0),
+                });
+            }
+        }
+        info.mcast_groups = get_mcast_groups(&handle)?.into_iter()
+            .map(|(name, id)| GenlMcastGroup { name, id })
+            .collect();
+        Ok(info)
+    }
+}
+
+/// Collect the name and ID of every multicast group nested by index in `CtrlAttr::McastGroups`
+/// of a `CtrlCmd::Newfamily` response - empty if the family has no multicast groups
+pub fn get_mcast_groups(handle: &AttrHandle<CtrlAttr>) -> Result<Vec<(String, u32)>, DeError> {
+    let mut mcast_groups = Vec::new();
+    handle.parse_with(|attr| {
+        if attr.nla_type != CtrlAttr::McastGroups {
+            return Ok(());
+        }
+        attr.get_attr_handle::<u16>().parse_with(|group| {
+            let mut group_handle = group.get_nested_attributes::<CtrlAttrMcastGrp>()?;
+            let name = group_handle.get_attribute(CtrlAttrMcastGrp::Name)
+                .ok_or_else(|| DeError::new("Multicast group is missing its name"))?
+                .get_payload_as_string_lossy();
+            let id = group_handle.get_payload_with::<u32>(CtrlAttrMcastGrp::Id, None)?;
+            mcast_groups.push((name, id));
+            Ok(())
+        })
+    })?;
+    Ok(mcast_groups)
+}
+
+/// Validation policy of a single attribute of a generic netlink family as reported in
+/// `CtrlAttr::Policy` - bounds that do not apply to the attribute type are `None`
+#[derive(Clone,Debug,PartialEq)]
+pub struct NlPolicy {
+    /// Index of the policy this attribute belongs to
+    pub policy_idx: u16,
+    /// Attribute type this policy applies to
+    pub attr: u16,
+    /// Expected type of the attribute payload
+    pub attr_type: NlAttrType,
+    /// Minimum value of a signed integer attribute
+    pub min_value_s: Option<i64>,
+    /// Maximum value of a signed integer attribute
+    pub max_value_s: Option<i64>,
+    /// Minimum value of an unsigned integer attribute
+    pub min_value_u: Option<u64>,
+    /// Maximum value of an unsigned integer attribute
+    pub max_value_u: Option<u64>,
+    /// Minimum length of a binary or string attribute
+    pub min_length: Option<u32>,
+    /// Maximum length of a binary or string attribute
+    pub max_length: Option<u32>,
+    /// Index of the policy of the attributes nested in a nested attribute
+    pub nested_policy_idx: Option<u32>,
+    /// Maximum attribute type in the policy of a nested attribute
+    pub nested_maxtype: Option<u32>,
+    /// Valid bits of a bitfield or masked integer attribute
+    pub mask: Option<u64>,
+}
+
+impl NlPolicy {
+    /// Decode the policies of every attribute from a `CtrlAttr::Policy` attribute - the
+    /// payload is nested by policy index and then by attribute type
+    pub fn parse_policies<T>(attr: &Nlattr<T>) -> Result<Vec<NlPolicy>, DeError>
+            where T: Nl + Into<u16> + From<u16> {
+        let mut policies = Vec::new();
+        let idx_handle = attr.get_nested_attributes::<u16>()?;
+        for idx_attr in idx_handle.iter().into_iter().flatten() {
+            let attr_handle = idx_attr.get_nested_attributes::<u16>()?;
+            for policy_attr in attr_handle.iter().into_iter().flatten() {
+                let handle = policy_attr.get_nested_attributes::<NlPolicyTypeAttr>()?;
+                policies.push(NlPolicy {
+                    policy_idx: idx_attr.nla_type,
+                    attr: policy_attr.nla_type,
+                    attr_type: attr_value::<u32, _>(&handle, NlPolicyTypeAttr::Type)?
+                        .ok_or_else(|| DeError::new("Policy is missing the attribute type"))?
+                        .into(),
+                    min_value_s: attr_value(&handle, NlPolicyTypeAttr::MinValueS)?,
+                    max_value_s: attr_value(&handle, NlPolicyTypeAttr::MaxValueS)?,
+                    min_value_u: attr_value(&handle, NlPolicyTypeAttr::MinValueU)?,
+                    max_value_u: attr_value(&handle, NlPolicyTypeAttr::MaxValueU)?,
+                    min_length: attr_value(&handle, NlPolicyTypeAttr::MinLength)?,
+                    max_length: attr_value(&handle, NlPolicyTypeAttr::MaxLength)?,
+                    nested_policy_idx: attr_value(&handle, NlPolicyTypeAttr::PolicyIdx)?,
+                    nested_maxtype: attr_value(&handle, NlPolicyTypeAttr::PolicyMaxtype)?,
+                    mask: match attr_value::<u64, _>(&handle, NlPolicyTypeAttr::Mask)? {
+                        Some(mask) => Some(mask),
+                        None => attr_value::<u32, _>(&handle, NlPolicyTypeAttr::Bitfield32Mask)?
+                            .map(u64::from),
+                    },
+                });
+            }
+        }
+        Ok(policies)
+    }
+}
+
+// Parse the payload of an optional attribute of a parsed handle
+fn attr_value<R, P>(handle: &AttrHandle<P>, attr: P) -> Result<Option<R>, DeError>
+        where P: PartialEq + Nl + Into<u16> + From<u16>, R: Nl {
+    match handle.get_attr_payload(attr)? {
+        Some(payload) => Ok(Some(R::deserialize(&mut StreamReadBuffer::new(payload))?)),
+        None => Ok(None),
     }
 }
 
@@ -71,15 +418,74 @@ mod test {
     use super::*;
     use byteorder::{NativeEndian,WriteBytesExt};
     use std::io::{Cursor,Write};
-    use consts::{CtrlAttr,CtrlCmd};
+    use consts::{CtrlAttr,CtrlCmd,GenlId,NlmF};
+    use nl::Nlmsghdr;
+
+    #[test]
+    fn test_reserved() {
+        let genl = Genlmsghdr::<CtrlCmd, CtrlAttr>::builder(CtrlCmd::Getfamily, 2)
+            .attr(CtrlAttr::FamilyId, 16u16)
+            .build()
+            .unwrap();
+        assert_eq!(genl.reserved(), 0);
+        assert_eq!(&genl.serialize_to_vec().unwrap()[2..4], &[0, 0]);
+
+        let bytes = [3, 2, 1, 0];
+        let genl = Genlmsghdr::<CtrlCmd, CtrlAttr>::from_bytes(&bytes).unwrap();
+        assert_eq!(genl.reserved(), 1);
+        assert!(Genlmsghdr::<CtrlCmd, CtrlAttr>::deserialize_strict(
+            &mut StreamReadBuffer::new(&bytes[..])
+        ).is_err());
+        assert!(Genlmsghdr::<CtrlCmd, CtrlAttr>::deserialize_with(
+            &mut StreamReadBuffer::new(&bytes[..]), false
+        ).is_err());
+        assert!(Genlmsghdr::<CtrlCmd, CtrlAttr, Vec<u8>>::deserialize_with_hdrsize(
+            &mut StreamReadBuffer::new(&bytes[..]), 0
+        ).is_ok());
+    }
+
+    #[test]
+    fn test_get_mcast_groups() {
+        let mut bytes = vec![52, 0, 7, 0];
+        for &(index, name, id) in &[(1u8, b"notify\0", 2u8), (2, b"config\0", 3)] {
+            bytes.extend_from_slice(&[24, 0, index, 0, 11, 0, 1, 0]);
+            bytes.extend_from_slice(name);
+            bytes.extend_from_slice(&[0, 8, 0, 2, 0, id, 0, 0, 0]);
+        }
+        let handle = AttrHandle::Bin(bytes.as_slice());
+        assert_eq!(get_mcast_groups(&handle).unwrap(),
+                   vec![("notify".to_string(), 2), ("config".to_string(), 3)]);
+
+        let no_groups = [Nlattr::new_nl_payload(None, CtrlAttr::FamilyId, 16u16).unwrap()];
+        assert!(get_mcast_groups(&AttrHandle::Borrowed(&no_groups)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_family_info_malformed() {
+        let mut attrs = vec![
+            Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "acme").unwrap(),
+            Nlattr::new_nl_payload(None, CtrlAttr::FamilyId, 31u16).unwrap(),
+        ];
+        let info = GenlFamilyInfo::from_attr_handle(AttrHandle::Borrowed(&attrs)).unwrap();
+        assert_eq!((info.version, info.hdrsize, info.maxattr), (0, 0, 0));
+
+        attrs.push(Nlattr::new_nl_payload(None, CtrlAttr::Version, 3u16).unwrap());
+        assert!(GenlFamilyInfo::from_attr_handle(AttrHandle::Borrowed(&attrs)).is_err());
+    }
 
     #[test]
     pub fn test_serialize() {
         let attr = vec![Nlattr::new_binary_payload(None, CtrlAttr::FamilyId,
-                                                        vec![0, 1, 2, 3, 4, 5, 0, 0]
+                                                        vec![0, 1, 2, 3, 4, 5]
                                                       )];
+        // `size` excludes the padding that aligns the next attribute while `asize` includes it
+        assert_eq!(attr[0].nla_len, 10);
+        assert_eq!(attr[0].size(), 10);
+        assert_eq!(attr[0].asize(), 12);
         let genl = Genlmsghdr::new(CtrlCmd::Getops, 2,
                                     attr).unwrap();
+        assert_eq!(genl.size(), 16);
+        assert_eq!(genl.asize(), 16);
         let mut mem = StreamWriteBuffer::new_growable(None);
         genl.serialize(&mut mem).unwrap();
         let v = Vec::with_capacity(genl.asize());
@@ -88,12 +494,126 @@ mod test {
             c.write_u8(CtrlCmd::Getops.into()).unwrap();
             c.write_u8(2).unwrap();
             c.write_u16::<NativeEndian>(0).unwrap();
-            c.write_u16::<NativeEndian>(12).unwrap();
+            c.write_u16::<NativeEndian>(10).unwrap();
             c.write_u16::<NativeEndian>(CtrlAttr::FamilyId.into()).unwrap();
             c.write_all(&vec![0, 1, 2, 3, 4, 5, 0, 0]).unwrap();
             c.into_inner()
         };
-        assert_eq!(mem.as_ref(), v_final.as_slice())
+        assert_eq!(mem.as_ref(), v_final.as_slice());
+        let parsed = Genlmsghdr::<CtrlCmd, CtrlAttr>::from_bytes(mem.as_ref()).unwrap();
+        assert_eq!(parsed.attrs()[0].payload, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    pub fn test_builder() {
+        let attrs = vec![
+            Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "nl80211").unwrap(),
+            Nlattr::new_binary_payload(None, CtrlAttr::FamilyId, vec![0, 1, 2, 3, 4, 5, 0, 0]),
+        ];
+        let manual = Genlmsghdr::new(CtrlCmd::Getops, 2, attrs).unwrap();
+        let built = Genlmsghdr::builder(CtrlCmd::Getops, 2)
+            .attr(CtrlAttr::FamilyName, "nl80211")
+            .attr(CtrlAttr::FamilyId, vec![0u8, 1, 2, 3, 4, 5, 0, 0])
+            .build()
+            .unwrap();
+        let mut manual_mem = StreamWriteBuffer::new_growable(None);
+        manual.serialize(&mut manual_mem).unwrap();
+        let mut built_mem = StreamWriteBuffer::new_growable(None);
+        built.serialize(&mut built_mem).unwrap();
+        assert_eq!(manual_mem.as_ref(), built_mem.as_ref());
+
+        let err = Genlmsghdr::builder(CtrlCmd::Getops, 2)
+            .attr(CtrlAttr::FamilyName, "nl\080211")
+            .attr(CtrlAttr::FamilyId, 1u16)
+            .build();
+        assert!(err.is_err());
+    }
+
+    #[test]
+    pub fn test_parse_policies() {
+        let policy = |attrs: Vec<Nlattr<NlPolicyTypeAttr>>| {
+            Nlattr::new_nested(None, 0u16, attrs).unwrap()
+        };
+        let u32_policy = Nlattr::new_nested(None, 1u16, vec![
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::Type, u32::from(NlAttrType::U32))
+                .unwrap(),
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::MinValueU, 1u64).unwrap(),
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::MaxValueU, 4096u64).unwrap(),
+        ]).unwrap();
+        let string_policy = Nlattr::new_nested(None, 2u16, vec![
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::Type, u32::from(NlAttrType::NulString))
+                .unwrap(),
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::MaxLength, 15u32).unwrap(),
+        ]).unwrap();
+        let nested_policy = Nlattr::new_nested(None, 3u16, vec![
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::Type, u32::from(NlAttrType::Nested))
+                .unwrap(),
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::PolicyIdx, 1u32).unwrap(),
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::PolicyMaxtype, 2u32).unwrap(),
+        ]).unwrap();
+        let flag_policy = Nlattr::new_nested(None, 1u16, vec![
+            Nlattr::new_nl_payload(None, NlPolicyTypeAttr::Type, u32::from(NlAttrType::Flag))
+                .unwrap(),
+        ]).unwrap();
+        let attr = Nlattr::new_nested(None, CtrlAttr::Policy, vec![
+            Nlattr::new_nested(None, 0u16, vec![u32_policy, string_policy, nested_policy])
+                .unwrap(),
+            Nlattr::new_nested(None, 1u16, vec![flag_policy]).unwrap(),
+        ]).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        let attr = Nlattr::<CtrlAttr>::deserialize(&mut StreamReadBuffer::new(mem.as_ref()))
+            .unwrap();
+
+        let policies = NlPolicy::parse_policies(&attr).unwrap();
+        assert_eq!(policies.len(), 4);
+        assert_eq!((policies[0].policy_idx, policies[0].attr), (0, 1));
+        assert_eq!(policies[0].attr_type, NlAttrType::U32);
+        assert_eq!(policies[0].min_value_u, Some(1));
+        assert_eq!(policies[0].max_value_u, Some(4096));
+        assert_eq!(policies[0].min_value_s, None);
+        assert_eq!(policies[1].attr_type, NlAttrType::NulString);
+        assert_eq!(policies[1].max_length, Some(15));
+        assert_eq!(policies[1].min_length, None);
+        assert_eq!(policies[2].attr_type, NlAttrType::Nested);
+        assert_eq!(policies[2].nested_policy_idx, Some(1));
+        assert_eq!(policies[2].nested_maxtype, Some(2));
+        assert_eq!((policies[3].policy_idx, policies[3].attr), (1, 1));
+        assert_eq!(policies[3].attr_type, NlAttrType::Flag);
+
+        let missing_type = Nlattr::new_nested(None, CtrlAttr::Policy, vec![
+            Nlattr::new_nested(None, 0u16, vec![policy(Vec::new())]).unwrap(),
+        ]).unwrap();
+        assert!(NlPolicy::parse_policies(&missing_type).is_err());
+    }
+
+    // `CTRL_ATTR_POLICY` of a `CTRL_CMD_GETPOLICY` reply with a single `NLA_U32` policy for
+    // attribute 1 of policy 0
+    #[cfg(target_endian = "little")]
+    #[test]
+    pub fn test_parse_policies_wire() {
+        let bytes: &[u8] = &[
+            0x14, 0x00, 0x08, 0x00,
+            0x10, 0x00, 0x00, 0x00,
+            0x0c, 0x00, 0x01, 0x00,
+            0x08, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00,
+        ];
+        let attr = Nlattr::<CtrlAttr>::from_bytes(bytes).unwrap();
+        assert_eq!(attr.nla_type, CtrlAttr::Policy);
+        let policies = NlPolicy::parse_policies(&attr).unwrap();
+        assert_eq!(policies.len(), 1);
+        assert_eq!((policies[0].policy_idx, policies[0].attr), (0, 1));
+        assert_eq!(policies[0].attr_type, NlAttrType::U32);
+        assert_eq!(u16::from(CtrlAttr::OpPolicy), 9);
+        assert_eq!(u16::from(CtrlAttr::Op), 10);
+    }
+
+    #[test]
+    pub fn test_header_len() {
+        let genl = Genlmsghdr::<_, u16>::new(CtrlCmd::Getfamily, 2, Vec::new()).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        genl.serialize(&mut mem).unwrap();
+        assert_eq!(Genlmsghdr::<CtrlCmd, u16>::HEADER_LEN, mem.as_ref().len());
     }
 
     #[test]
@@ -118,4 +638,85 @@ mod test {
         let genl = Genlmsghdr::deserialize(&mut mem).unwrap();
         assert_eq!(genl, genl_mock)
     }
+
+    #[test]
+    pub fn test_attrs() {
+        let mut genl = Genlmsghdr::new(CtrlCmd::Getfamily, 2, vec![
+            Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "nlctrl").unwrap(),
+        ]).unwrap();
+        genl.add_attr(Nlattr::new_nl_payload(None, CtrlAttr::FamilyId, 16u16).unwrap());
+        assert_eq!(genl.attrs().len(), 2);
+        assert_eq!(genl.attrs()[1].nla_type, CtrlAttr::FamilyId);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        genl.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), genl.size());
+        let mut parsed = Genlmsghdr::<CtrlCmd, CtrlAttr>::deserialize(
+            &mut StreamReadBuffer::new(mem.as_ref())
+        ).unwrap();
+        assert_eq!(parsed, genl);
+        assert_eq!(parsed.get_attr_handle().get_payload_with::<u16>(CtrlAttr::FamilyId, None)
+                   .unwrap(), 16);
+
+        parsed.attrs_mut().retain(|attr| attr.nla_type != CtrlAttr::FamilyName);
+        assert_eq!(parsed.get_attr_handle().len(), Some(1));
+        assert!(parsed.get_attr_handle().get_attribute(CtrlAttr::FamilyName).is_none());
+    }
+
+    #[test]
+    pub fn test_deserialize_packed() {
+        let family = |name: &str, id: u16| {
+            let genl = Genlmsghdr::new(CtrlCmd::Newfamily, 2, vec![
+                Nlattr::new_str_payload(None, CtrlAttr::FamilyName, name).unwrap(),
+                Nlattr::new_nl_payload(None, CtrlAttr::FamilyId, id).unwrap(),
+            ]).unwrap();
+            Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Multi], None, None, genl)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        family("nlctrl", 16).serialize(&mut mem).unwrap();
+        family("nl80211", 28).serialize(&mut mem).unwrap();
+
+        let mut mem = StreamReadBuffer::new(mem.as_ref());
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let msg = Nlmsghdr::<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>::deserialize(&mut mem)
+                .unwrap();
+            assert_eq!(msg.nl_payload.attrs().len(), 2);
+            ids.push(msg.nl_payload.get_attr_handle()
+                     .get_payload_with::<u16>(CtrlAttr::FamilyId, None).unwrap());
+        }
+        assert_eq!(ids, vec![16, 28]);
+    }
+
+    #[test]
+    pub fn test_family_header() {
+        let attrs = vec![Nlattr::new_nl_payload(None, 1u16, 7u32).unwrap()];
+        let genl = Genlmsghdr::new_with_header(1u8, 1, 0x0101u16, attrs).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        genl.serialize(&mut mem).unwrap();
+        assert_eq!(genl.size(), 16);
+        assert_eq!(mem.as_ref(), &[1, 1, 0, 0, 1, 1, 0, 0, 8, 0, 1, 0, 7, 0, 0, 0][..]);
+
+        let parsed = Genlmsghdr::<u8, u16, u16>::deserialize(
+            &mut StreamReadBuffer::new(mem.as_ref())
+        ).unwrap();
+        assert_eq!(parsed, genl);
+
+        let raw = Genlmsghdr::<u8, u16, Vec<u8>>::deserialize_with_hdrsize(
+            &mut StreamReadBuffer::new(mem.as_ref()), 2
+        ).unwrap();
+        assert_eq!(raw.family_header, vec![1, 1]);
+        assert_eq!(raw.get_attr_handle().get_payload_with::<u32>(1, None).unwrap(), 7);
+    }
+
+    #[test]
+    pub fn test_into_attr_type() {
+        let genl = Genlmsghdr::new(CtrlCmd::Newfamily, 2, vec![
+            Nlattr::new_nl_payload(None, u16::from(CtrlAttr::FamilyId), 16u16).unwrap(),
+        ]).unwrap();
+        let typed = genl.into_attr_type::<CtrlAttr>();
+        assert_eq!(typed.attrs()[0].nla_type, CtrlAttr::FamilyId);
+        assert_eq!(typed.get_attr_handle().get_payload_with::<u16>(CtrlAttr::FamilyId, None)
+                   .unwrap(), 16);
+    }
 }
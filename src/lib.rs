@@ -27,23 +27,76 @@ pub mod nl;
 pub mod genl;
 /// Route netlink bindings
 pub mod rtnl;
+/// Socket diagnostics (`NETLINK_SOCK_DIAG`) bindings
+pub mod sock_diag;
+/// Netfilter netlink (`NETLINK_NETFILTER`) message header and type bindings
+pub mod nfnetlink;
+/// Connection tracking (`NfnlSubsys::Ctnetlink`) bindings
+pub mod ctnl;
+/// Kernel device events (`NETLINK_KOBJECT_UEVENT`)
+pub mod uevent;
+/// Kernel connector (`NETLINK_CONNECTOR`) messages and process events
+pub mod connector;
+/// Audit (`NETLINK_AUDIT`) status and records
+pub mod audit;
+/// WireGuard device, peer and allowed IP payloads - requires the `wireguard` feature
+#[cfg(feature = "wireguard")]
+pub mod wireguard;
+/// Per-task accounting through the `TASKSTATS` family - requires the `taskstats` feature
+#[cfg(feature = "taskstats")]
+pub mod taskstats;
 /// Error module
 pub mod err;
+/// Human readable formatting of netlink messages
+pub mod nl_fmt;
+/// Re-exports of commonly used items
+pub mod prelude;
 
+use std::cell::Cell;
 use std::ffi::CString;
 use std::io::{Read,Write};
 use std::mem;
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
 use std::str;
+use std::time::Duration;
 
 pub use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 use byteorder::{NativeEndian,ReadBytesExt,WriteBytesExt};
 
-use consts::alignto;
+use consts::{alignto,Af};
 use err::{SerError,DeError};
 
 /// Max supported message length for netlink messages supported by the kernel
 pub const MAX_NL_LENGTH: usize = 32768;
 
+thread_local! {
+    // Set while `Nl::deserialize_strict` runs on this thread
+    static STRICT_DESERIALIZATION: Cell<bool> = Cell::new(false);
+}
+
+/// Returns `true` while `Nl::deserialize_strict` runs on the current thread - checked by the
+/// enums created with `impl_var`
+#[doc(hidden)]
+pub fn is_strict_deserialization() -> bool {
+    STRICT_DESERIALIZATION.with(|strict| strict.get())
+}
+
+// Enables strict deserialization until dropped, restoring the previous mode for nested calls
+struct StrictGuard(bool);
+
+impl StrictGuard {
+    fn new() -> Self {
+        StrictGuard(STRICT_DESERIALIZATION.with(|strict| strict.replace(true)))
+    }
+}
+
+impl Drop for StrictGuard {
+    fn drop(&mut self) {
+        let previous = self.0;
+        STRICT_DESERIALIZATION.with(|strict| strict.set(previous));
+    }
+}
+
 /// Trait defining basic actions required for netlink communication.
 /// Implementations for basic and `neli`'s types are provided (see below). Create new
 /// implementations if you have to work with a Netlink API that uses
@@ -73,12 +126,66 @@ pub trait Nl: Sized {
             where T: AsRef<[u8]> {
         unimplemented!()
     }
+    /// Deserialize like `deserialize` but fail with `DeError::UnknownConstant` wherever an enum
+    /// created with `impl_var` would be deserialized as `UnrecognizedVariant`
+    ///
+    /// As the `nl_type` of control messages such as `Nlmsg::Error` has no variant in the
+    /// message types of a family, deserialize those messages with `Nlmsg` as `nl_type`.
+    fn deserialize_strict<T>(m: &mut StreamReadBuffer<T>) -> Result<Self, DeError>
+            where T: AsRef<[u8]> {
+        let _strict = StrictGuard::new();
+        Self::deserialize(m)
+    }
     /// The size of the binary representation of a struct - not aligned to word size
     fn size(&self) -> usize;
     /// The size of the binary representation of a struct - aligned to word size
     fn asize(&self) -> usize {
         alignto(self.size())
     }
+    /// Serialize into a new buffer preallocated with `asize` bytes
+    fn serialize_to_vec(&self) -> Result<Vec<u8>, SerError> {
+        let mut mem = StreamWriteBuffer::new_growable(Some(self.asize()));
+        self.serialize(&mut mem)?;
+        Ok(mem.as_ref().to_vec())
+    }
+    /// Deserialize from a byte slice, failing if any bytes are left over
+    ///
+    /// ```
+    /// use neli::Nl;
+    /// use neli::consts::{CtrlAttr,CtrlCmd};
+    /// use neli::genl::Genlmsghdr;
+    ///
+    /// let genl = Genlmsghdr::<CtrlCmd, CtrlAttr>::from_bytes(
+    ///     &[3, 2, 0, 0, 6, 0, 1, 0, 16, 0, 0, 0]
+    /// ).unwrap();
+    /// assert_eq!(genl.cmd, CtrlCmd::Getfamily);
+    /// assert_eq!(genl.attrs()[0].nla_type, CtrlAttr::FamilyId);
+    /// ```
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DeError> {
+        let mut mem = StreamReadBuffer::new(bytes);
+        let t = Self::deserialize(&mut mem)?;
+        if mem.read(&mut [0u8])? != 0 {
+            return Err(DeError::new("Leftover bytes after deserialization"));
+        }
+        Ok(t)
+    }
+}
+
+impl Nl for () {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, _m: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        Ok(())
+    }
+
+    fn deserialize<T>(_m: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
 }
 
 impl Nl for u8 {
@@ -171,6 +278,60 @@ impl Nl for u64 {
     }
 }
 
+impl Nl for i64 {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_i64::<NativeEndian>(*self)?;
+        Ok(())
+    }
+
+    fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+        Ok(mem.read_i64::<NativeEndian>()?)
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<i64>()
+    }
+}
+
+impl Nl for u128 {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_u128::<NativeEndian>(*self)?;
+        Ok(())
+    }
+
+    fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+        Ok(mem.read_u128::<NativeEndian>()?)
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<u128>()
+    }
+}
+
+impl Nl for i128 {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_i128::<NativeEndian>(*self)?;
+        Ok(())
+    }
+
+    fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+        Ok(mem.read_i128::<NativeEndian>()?)
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<i128>()
+    }
+}
+
 impl<'a> Nl for &'a [u8] {
     type SerIn = ();
     type DeIn = &'a mut [u8];
@@ -311,12 +472,135 @@ impl Nl for String {
     }
 }
 
+/// IP addresses are serialized in network byte order - deserialization requires the address
+/// family to determine the length
+impl Nl for IpAddr {
+    type SerIn = ();
+    type DeIn = Af;
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        match *self {
+            IpAddr::V4(ref a) => mem.write_all(&a.octets())?,
+            IpAddr::V6(ref a) => mem.write_all(&a.octets())?,
+        };
+        Ok(())
+    }
+
+    fn deserialize_with<T>(mem: &mut StreamReadBuffer<T>, input: Af) -> Result<Self, DeError>
+            where T: AsRef<[u8]> {
+        match input {
+            Af::Inet => {
+                let mut octets = [0u8; 4];
+                mem.read_exact(&mut octets)?;
+                Ok(IpAddr::V4(Ipv4Addr::from(octets)))
+            },
+            Af::Inet6 => {
+                let mut octets = [0u8; 16];
+                mem.read_exact(&mut octets)?;
+                Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+            },
+            _ => Err(DeError::new("Unsupported address family")),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match *self {
+            IpAddr::V4(_) => 4,
+            IpAddr::V6(_) => 16,
+        }
+    }
+}
+
+/// Durations are represented as the kernel's `struct timespec` - seconds as `time_t` followed
+/// by nanoseconds as `long`
+impl Nl for Duration {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        let secs = self.as_secs();
+        if secs > libc::time_t::max_value() as u64 {
+            return Err(SerError::new("Duration is too long to be represented as a timespec"));
+        }
+        (secs as libc::time_t).serialize(mem)?;
+        (self.subsec_nanos() as libc::c_long).serialize(mem)?;
+        Ok(())
+    }
+
+    fn deserialize<T>(mem: &mut StreamReadBuffer<T>) -> Result<Self, DeError> where T: AsRef<[u8]> {
+        let secs = libc::time_t::deserialize(mem)?;
+        let nsecs = libc::c_long::deserialize(mem)?;
+        if secs < 0 || nsecs < 0 || nsecs >= 1_000_000_000 {
+            return Err(DeError::new("Timespec does not represent a valid duration"));
+        }
+        Ok(Duration::new(secs as u64, nsecs as u32))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<libc::timespec>()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     use std::io::Cursor;
 
+    #[test]
+    fn test_deserialize_strict() {
+        use consts::{CtrlAttr,CtrlCmd};
+        use genl::Genlmsghdr;
+
+        // `CTRL_CMD_GETFAMILY` with a `CtrlAttr::FamilyId` attribute
+        let known = [3, 2, 0, 0, 6, 0, 1, 0, 16, 0, 0, 0];
+        let genl = Genlmsghdr::<CtrlCmd, CtrlAttr>::deserialize_strict(
+            &mut StreamReadBuffer::new(&known[..])
+        ).unwrap();
+        assert_eq!(genl.attrs()[0].nla_type, CtrlAttr::FamilyId);
+
+        let unknown = [3, 2, 0, 0, 6, 0, 99, 0, 16, 0, 0, 0];
+        let genl = Genlmsghdr::<CtrlCmd, CtrlAttr>::from_bytes(&unknown).unwrap();
+        assert_eq!(genl.attrs()[0].nla_type, CtrlAttr::UnrecognizedVariant(99));
+        match Genlmsghdr::<CtrlCmd, CtrlAttr>::deserialize_strict(
+            &mut StreamReadBuffer::new(&unknown[..])
+        ) {
+            Err(DeError::UnknownConstant { type_name, value }) => {
+                assert_eq!(type_name, "CtrlAttr");
+                assert_eq!(value, 99);
+            },
+            r => panic!("Expected DeError::UnknownConstant, got {:?}", r),
+        }
+        assert!(!is_strict_deserialization());
+    }
+
+    #[test]
+    fn test_nl_unit() {
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ().serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), 0);
+        assert_eq!(().size(), 0);
+        assert_eq!(().asize(), 0);
+
+        let mut mem = StreamReadBuffer::new(&[1, 2]);
+        <()>::deserialize(&mut mem).unwrap();
+    }
+
+    #[test]
+    fn test_serialize_to_vec() {
+        let attr = nlattr::Nlattr::new_binary_payload(None, 1u16, vec![1, 2, 3, 4, 5]);
+        let bytes = attr.serialize_to_vec().unwrap();
+        assert_eq!(bytes.len(), attr.asize());
+        assert_eq!(bytes, vec![9, 0, 1, 0, 1, 2, 3, 4, 5, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        assert_eq!(u16::from_bytes(&[1, 0]).unwrap(), 1);
+        assert!(u16::from_bytes(&[1, 0, 0]).is_err());
+        assert!(u32::from_bytes(&[1, 0]).is_err());
+    }
+
     #[test]
     fn test_nl_u8() {
         let v: u8 = 5;
@@ -413,6 +697,66 @@ mod test {
         assert_eq!(test_int, deserialed_int);
     }
 
+    #[test]
+    fn test_nl_i64() {
+        let v: i64 = -12345678901234;
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        v.serialize(&mut mem).unwrap();
+        let mut c = Cursor::new(Vec::new());
+        c.write_i64::<NativeEndian>(v).unwrap();
+        assert_eq!(mem.as_ref(), c.get_ref().as_slice());
+        assert_eq!(v.size(), 8);
+        assert_eq!(i64::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), v);
+    }
+
+    #[test]
+    fn test_nl_u128() {
+        let v: u128 = 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10;
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        v.serialize(&mut mem).unwrap();
+        let mut c = Cursor::new(Vec::new());
+        c.write_u128::<NativeEndian>(v).unwrap();
+        assert_eq!(mem.as_ref(), c.get_ref().as_slice());
+        assert_eq!(v.size(), 16);
+        assert_eq!(u128::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), v);
+    }
+
+    #[test]
+    fn test_nl_i128() {
+        let v: i128 = -0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10;
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        v.serialize(&mut mem).unwrap();
+        let mut c = Cursor::new(Vec::new());
+        c.write_i128::<NativeEndian>(v).unwrap();
+        assert_eq!(mem.as_ref(), c.get_ref().as_slice());
+        assert_eq!(v.size(), 16);
+        assert_eq!(i128::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), v);
+    }
+
+    #[test]
+    fn test_nl_duration() {
+        let d = Duration::new(5, 250);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        d.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), d.size());
+        assert_eq!(Duration::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), d);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        (-1 as libc::time_t).serialize(&mut mem).unwrap();
+        (0 as libc::c_long).serialize(&mut mem).unwrap();
+        assert!(Duration::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).is_err());
+    }
+
+    #[test]
+    fn test_nl_ipaddr() {
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        addr.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), &[10, 0, 0, 1]);
+        let mut mem = StreamReadBuffer::new(&[10, 0, 0, 1]);
+        assert_eq!(IpAddr::deserialize_with(&mut mem, Af::Inet).unwrap(), addr);
+    }
+
     #[test]
     fn test_nl_vec() {
         let v = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
@@ -0,0 +1,168 @@
+//! Netfilter netlink messages address a subsystem such as conntrack or nfqueue in the upper
+//! byte of `nl_type` and a subsystem specific message type in the lower byte. Their payload
+//! starts with `struct nfgenmsg` followed by attributes.
+//!
+//! ```
+//! use neli::consts::{Af,NfnlSubsys,NlmF};
+//! use neli::nfnetlink::{Nfgenmsg,NfnlType};
+//! use neli::nl::Nlmsghdr;
+//!
+//! // IPCTNL_MSG_CT_GET from linux/netfilter/nfnetlink_conntrack.h
+//! let nl_type = NfnlType::new(NfnlSubsys::Ctnetlink, 1);
+//! let nfgen = Nfgenmsg::<u16>::new(Af::Inet, 0, Vec::new());
+//! let nlhdr = Nlmsghdr::new(None, nl_type, vec![NlmF::Request, NlmF::Dump], None, None, nfgen);
+//! ```
+
+use std::mem;
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+
+use Nl;
+use consts::{Af,NfnlSubsys,NlType};
+use err::{SerError,DeError};
+use nlattr::Nlattr;
+
+/// Value of `NFNETLINK_V0` - the only version of `Nfgenmsg`
+pub const NFNETLINK_V0: u8 = 0;
+
+/// `nl_type` of netfilter netlink messages composed of the subsystem and its message type
+/// like `NFNL_SUBSYS_ID` and `NFNL_MSG_TYPE` in C
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct NfnlType {
+    /// Subsystem the message is addressed to
+    pub subsys: NfnlSubsys,
+    /// Message type defined by the subsystem
+    pub msg_type: u8,
+}
+
+impl NfnlType {
+    /// Create a message type for the given subsystem
+    pub fn new(subsys: NfnlSubsys, msg_type: u8) -> Self {
+        NfnlType { subsys, msg_type }
+    }
+}
+
+impl From<u16> for NfnlType {
+    fn from(v: u16) -> Self {
+        NfnlType {
+            subsys: NfnlSubsys::from((v >> 8) as u8),
+            msg_type: v as u8,
+        }
+    }
+}
+
+impl From<NfnlType> for u16 {
+    fn from(v: NfnlType) -> Self {
+        u16::from(u8::from(v.subsys)) << 8 | u16::from(v.msg_type)
+    }
+}
+
+impl<'a> From<&'a NfnlType> for u16 {
+    fn from(v: &'a NfnlType) -> Self {
+        u16::from(u8::from(&v.subsys)) << 8 | u16::from(v.msg_type)
+    }
+}
+
+impl Nl for NfnlType {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        u16::from(self).serialize(mem)
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(NfnlType::from(u16::deserialize(mem)?))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<u16>()
+    }
+}
+
+impl NlType for NfnlType {}
+
+/// Header of netfilter netlink payloads (`struct nfgenmsg`) followed by its attributes
+///
+/// `res_id` is stored in host byte order and converted to network byte order on the wire.
+#[derive(Debug,PartialEq)]
+pub struct Nfgenmsg<T> {
+    /// Address family the message applies to - `Af::Unspec` for all families
+    pub nfgen_family: Af,
+    /// Version of the header - `NFNETLINK_V0`
+    pub version: u8,
+    /// Resource ID defined by the subsystem such as the nfqueue queue number
+    pub res_id: u16,
+    /// Attributes following the header
+    pub attrs: Vec<Nlattr<T>>,
+}
+
+impl<T> Nfgenmsg<T> {
+    /// Length of the header preceding the attributes
+    pub const HEADER_LEN: usize = mem::size_of::<u8>() * 2 + mem::size_of::<u16>();
+
+    /// Create a header of version `NFNETLINK_V0`
+    pub fn new(nfgen_family: Af, res_id: u16, attrs: Vec<Nlattr<T>>) -> Self {
+        Nfgenmsg { nfgen_family, version: NFNETLINK_V0, res_id, attrs }
+    }
+}
+
+impl<T> Nl for Nfgenmsg<T> where T: Nl + Into<u16> + From<u16> {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.nfgen_family.serialize(mem)?;
+        self.version.serialize(mem)?;
+        self.res_id.to_be().serialize(mem)?;
+        self.attrs.serialize(mem)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(Nfgenmsg {
+            nfgen_family: Af::deserialize(mem)?,
+            version: u8::deserialize(mem)?,
+            res_id: u16::from_be(u16::deserialize(mem)?),
+            attrs: Vec::<Nlattr<T>>::deserialize(mem)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::HEADER_LEN + self.attrs.size()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use consts::NlmF;
+    use nl::Nlmsghdr;
+
+    #[test]
+    fn test_nfnl_type() {
+        let nl_type = NfnlType::new(NfnlSubsys::Queue, 2);
+        assert_eq!(u16::from(&nl_type), 0x0302);
+        assert_eq!(NfnlType::from(0x0a10), NfnlType::new(NfnlSubsys::Nftables, 0x10));
+        assert_eq!(NfnlType::from(0xff01).subsys, NfnlSubsys::UnrecognizedVariant(0xff));
+    }
+
+    #[test]
+    fn test_nfgenmsg_res_id() {
+        let nfgen = Nfgenmsg::new(Af::Inet, 0x0102, vec![
+            Nlattr::new_nl_payload(None, 1u16, 7u32).unwrap(),
+        ]);
+        let bytes = nfgen.serialize_to_vec().unwrap();
+        assert_eq!(bytes, vec![2, 0, 1, 2, 8, 0, 1, 0, 7, 0, 0, 0]);
+        assert_eq!(Nfgenmsg::<u16>::from_bytes(&bytes).unwrap(), nfgen);
+
+        let nlhdr = Nlmsghdr::new(None, NfnlType::new(NfnlSubsys::Ctnetlink, 1),
+                                  vec![NlmF::Request], None, None, nfgen);
+        let bytes = nlhdr.serialize_to_vec().unwrap();
+        assert_eq!(&bytes[4..6], &[1, 1]);
+        let parsed = Nlmsghdr::<NfnlType, Nfgenmsg<u16>>::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.nl_type, NfnlType::new(NfnlSubsys::Ctnetlink, 1));
+        assert_eq!(parsed.nl_payload.res_id, 0x0102);
+    }
+}
@@ -3,13 +3,16 @@
 //! NlHdr is the structure representing a header that all netlink protocols require to be
 //! passed to the correct kernel handler.
 
+use std::io::{Read,Write};
 use std::mem;
 
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 
+use byteorder::{ByteOrder,NativeEndian};
+
 use Nl;
-use err::{SerError,DeError};
-use consts::{NlmF,NlType};
+use err::{NlError,SerError,DeError};
+use consts::{checked_alignto,Nlmsg,NlFlags,NlmF,NlType};
 
 /// Top level netlink header and payload
 #[derive(Debug,PartialEq)]
@@ -19,7 +22,7 @@ pub struct Nlmsghdr<T, P> {
     /// Type of the netlink message
     pub nl_type: T,
     /// Flags indicating properties of the request or response
-    pub nl_flags: Vec<NlmF>,
+    pub nl_flags: NlFlags<NlmF>,
     /// Sequence number for netlink protocol
     pub nl_seq: u32,
     /// ID of the netlink destination for requests and source for responses
@@ -28,21 +31,98 @@ pub struct Nlmsghdr<T, P> {
     pub nl_payload: P,
 }
 
+impl<T, P> Nlmsghdr<T, P> {
+    /// Length of the header fields preceding the payload
+    pub const HEADER_LEN: usize = mem::size_of::<u32>() + mem::size_of::<u16>()
+        + mem::size_of::<u16>() + mem::size_of::<u32>() + mem::size_of::<u32>();
+}
+
 impl<T, P> Nlmsghdr<T, P> where T: NlType, P: Nl {
-    /// Create a new top level netlink packet with a payload
-    pub fn new(nl_len: Option<u32>, nl_type: T, nl_flags: Vec<NlmF>,
-           nl_seq: Option<u32>, nl_pid: Option<u32>, nl_payload: P) -> Self {
+    /// Create a new top level netlink packet with a payload - `nl_flags` is an `NlFlags`, a
+    /// single `NlmF` or a `Vec<NlmF>`
+    pub fn new<F>(nl_len: Option<u32>, nl_type: T, nl_flags: F,
+           nl_seq: Option<u32>, nl_pid: Option<u32>, nl_payload: P) -> Self
+            where F: Into<NlFlags<NlmF>> {
         let mut nl = Nlmsghdr {
             nl_type,
-            nl_flags,
+            nl_flags: nl_flags.into(),
             nl_seq: nl_seq.unwrap_or(0),
             nl_pid: nl_pid.unwrap_or(0),
             nl_payload,
             nl_len: 0,
         };
-        nl.nl_len = nl_len.unwrap_or(nl.size() as u32);
+        match nl_len {
+            Some(len) => nl.nl_len = len,
+            None => nl.recompute_len(),
+        };
         nl
     }
+
+    /// Set `nl_len` to the size of the header and the current payload - call after mutating
+    /// `nl_payload` as the kernel rejects messages with a stale length with `EINVAL`
+    pub fn recompute_len(&mut self) {
+        self.nl_len = self.size() as u32;
+    }
+
+    /// Transform the payload of this message, preserving the type, flags, sequence number and
+    /// port ID and recomputing `nl_len` from the new payload
+    pub fn map_payload<Q, F>(self, f: F) -> Nlmsghdr<T, Q> where Q: Nl, F: FnOnce(P) -> Q {
+        Nlmsghdr::new(None, self.nl_type, self.nl_flags, Some(self.nl_seq), Some(self.nl_pid),
+                      f(self.nl_payload))
+    }
+
+    /// Transform the type of this message, preserving the flags, sequence number, port ID and
+    /// payload and recomputing `nl_len`
+    pub fn map_type<U, F>(self, f: F) -> Nlmsghdr<U, P> where U: NlType, F: FnOnce(T) -> U {
+        Nlmsghdr::new(None, f(self.nl_type), self.nl_flags, Some(self.nl_seq), Some(self.nl_pid),
+                      self.nl_payload)
+    }
+}
+
+impl<T, P> Nlmsghdr<T, P> where T: NlType + Clone, P: Nl {
+    /// Check that this message has the type `expected`
+    ///
+    /// An `Nlmsg::Error` message carrying an error code is reported as `NlError::Errno` and an
+    /// `Nlmsg::Done` message as `NlError::UnexpectedDone`. Any other mismatch, including an
+    /// acknowledgement, is reported as `NlError::UnexpectedType`.
+    pub fn expect_type(&self, expected: T) -> Result<(), NlError> {
+        let expected: u16 = expected.into();
+        let got: u16 = self.nl_type.clone().into();
+        if got == expected {
+            return Ok(());
+        }
+        if got == u16::from(Nlmsg::Done) {
+            return Err(NlError::UnexpectedDone);
+        }
+        if got == u16::from(Nlmsg::Error) {
+            let mut mem = StreamWriteBuffer::new_growable(Some(self.nl_payload.size()));
+            self.nl_payload.serialize(&mut mem)?;
+            if mem.as_ref().len() >= mem::size_of::<i32>() {
+                let error = NativeEndian::read_i32(mem.as_ref());
+                if error != 0 {
+                    return Err(NlError::Errno(-error));
+                }
+            }
+        }
+        Err(NlError::UnexpectedType { expected, got })
+    }
+}
+
+impl<T> Nlmsghdr<T, ()> where T: NlType {
+    /// Deserialize only the header fields of a message and leave the payload in `mem` - used
+    /// for the request header echoed in error messages, whose `nl_len` still counts a payload
+    /// that the kernel may have omitted
+    pub fn deserialize_header<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError>
+            where B: AsRef<[u8]> {
+        Ok(Nlmsghdr {
+            nl_len: u32::deserialize(mem)?,
+            nl_type: T::deserialize(mem)?,
+            nl_flags: NlFlags::<NlmF>::deserialize(mem)?,
+            nl_seq: u32::deserialize(mem)?,
+            nl_pid: u32::deserialize(mem)?,
+            nl_payload: (),
+        })
+    }
 }
 
 impl<T, P> Nl for Nlmsghdr<T, P> where T: NlType, P: Nl {
@@ -52,37 +132,52 @@ impl<T, P> Nl for Nlmsghdr<T, P> where T: NlType, P: Nl {
     fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.nl_len.serialize(mem)?;
         self.nl_type.serialize(mem)?;
-        let val = self.nl_flags.iter().fold(0, |acc: u16, val| {
-            let v: u16 = val.into();
-            acc | v
-        });
-        val.serialize(mem)?;
+        self.nl_flags.serialize(mem)?;
         self.nl_seq.serialize(mem)?;
         self.nl_pid.serialize(mem)?;
         self.nl_payload.serialize(mem)?;
         Ok(())
     }
 
+    /// Deserialize a message - the payload is limited to the `nl_len` bytes of this message so
+    /// that messages packed into the same buffer are not consumed by the payload
+    ///
+    /// A buffer ending before the header or before `nl_len` bytes results in
+    /// `DeError::Incomplete`.
     fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
-        let nl = Nlmsghdr::<T, P> {
-            nl_len: u32::deserialize(mem)?,
-            nl_type: T::deserialize(mem)?,
-            nl_flags: {
-                let flags = u16::deserialize(mem)?;
-                let mut nl_flags = Vec::new();
-                for i in 0..mem::size_of::<u16>() * 8 {
-                    let bit = 1 << i;
-                    if bit & flags == bit {
-                        nl_flags.push(bit.into());
-                    }
-                }
-                nl_flags
-            },
-            nl_seq: u32::deserialize(mem)?,
-            nl_pid: u32::deserialize(mem)?,
-            nl_payload: P::deserialize(mem)?,
-        };
-        Ok(nl)
+        let mut hdr_bytes = [0u8; Nlmsghdr::<(), ()>::HEADER_LEN];
+        let mut got = 0;
+        while got < hdr_bytes.len() {
+            match mem.read(&mut hdr_bytes[got..])? {
+                0 => return Err(DeError::Incomplete { needed: Self::HEADER_LEN, got }),
+                n => got += n,
+            }
+        }
+        let hdr = Nlmsghdr::<T, ()>::deserialize_header(
+            &mut StreamReadBuffer::new(&hdr_bytes[..])
+        )?;
+        let payload_len = (hdr.nl_len as usize).checked_sub(Self::HEADER_LEN)
+            .ok_or_else(|| DeError::new("Message length is shorter than the header"))?;
+        // nl_len is untrusted so the payload only grows with the bytes actually read
+        let mut payload = Vec::new();
+        let read = mem.by_ref().take(payload_len as u64).read_to_end(&mut payload)?;
+        if read < payload_len {
+            return Err(DeError::Incomplete {
+                needed: hdr.nl_len as usize,
+                got: Self::HEADER_LEN + read,
+            });
+        }
+        let padding_len = checked_alignto(hdr.nl_len as usize)? - hdr.nl_len as usize;
+        let padding = &mut [0u8; 4][0..padding_len];
+        let _ = mem.read_exact(padding);
+        Ok(Nlmsghdr {
+            nl_len: hdr.nl_len,
+            nl_type: hdr.nl_type,
+            nl_flags: hdr.nl_flags,
+            nl_seq: hdr.nl_seq,
+            nl_pid: hdr.nl_pid,
+            nl_payload: P::deserialize(&mut StreamReadBuffer::new(payload.as_slice()))?,
+        })
     }
 
     fn size(&self) -> usize {
@@ -91,27 +186,54 @@ impl<T, P> Nl for Nlmsghdr<T, P> where T: NlType, P: Nl {
     }
 }
 
-/// Struct indicating an empty payload
+/// Type indicating an empty payload
+#[deprecated(since = "0.4.0", note = "Use `()` as the empty payload type instead")]
+pub type NlEmpty = ();
+
+/// Struct holding the unparsed bytes of a netlink payload
+///
+/// This allows deserializing a header first and inspecting `nl_type` before choosing
+/// the payload type with `Nlmsghdr::parse_payload`.
 #[derive(Debug,PartialEq)]
-pub struct NlEmpty;
+pub struct RawPayload(pub Vec<u8>);
 
-impl Nl for NlEmpty {
+impl Nl for RawPayload {
     type SerIn = ();
     type DeIn = ();
 
-    #[inline]
-    fn serialize(&self, _cur: &mut StreamWriteBuffer) -> Result<(), SerError> {
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_all(self.0.as_slice())?;
         Ok(())
     }
 
-    #[inline]
-    fn deserialize<B>(_cur: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
-        Ok(NlEmpty)
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut v = Vec::new();
+        mem.read_to_end(&mut v)?;
+        Ok(RawPayload(v))
     }
 
-    #[inline]
     fn size(&self) -> usize {
-        0
+        self.0.len()
+    }
+}
+
+impl<T> Nlmsghdr<T, RawPayload> where T: NlType {
+    /// Deserialize the stored raw payload as the payload type `P`, preserving the header
+    pub fn parse_payload<P>(self) -> Result<Nlmsghdr<T, P>, DeError> where P: Nl {
+        let nl_payload = {
+            let mut mem = StreamReadBuffer::new(self.nl_payload.0.as_slice());
+            P::deserialize(&mut mem).map_err(|e| {
+                DeError::new(&format!("Failed to deserialize payload after header: {}", e))
+            })?
+        };
+        Ok(Nlmsghdr {
+            nl_len: self.nl_len,
+            nl_type: self.nl_type,
+            nl_flags: self.nl_flags,
+            nl_seq: self.nl_seq,
+            nl_pid: self.nl_pid,
+            nl_payload,
+        })
     }
 }
 
@@ -125,8 +247,7 @@ mod test {
     #[test]
     fn test_nlhdr_serialize() {
         let mut mem = StreamWriteBuffer::new_growable(None);
-        let nl = Nlmsghdr::<Nlmsg, NlEmpty>::new(None, Nlmsg::Noop,
-                                              Vec::new(), None, None, NlEmpty);
+        let nl = Nlmsghdr::<Nlmsg, ()>::new(None, Nlmsg::Noop, Vec::new(), None, None, ());
         nl.serialize(&mut mem).unwrap();
         let s: &mut [u8] = &mut [0; 16];
         {
@@ -137,6 +258,14 @@ mod test {
         assert_eq!(&mut *s, mem.as_ref())
     }
 
+    #[test]
+    fn test_nlhdr_header_len() {
+        let nl = Nlmsghdr::<Nlmsg, ()>::new(None, Nlmsg::Noop, Vec::new(), None, None, ());
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        nl.serialize(&mut mem).unwrap();
+        assert_eq!(Nlmsghdr::<Nlmsg, ()>::HEADER_LEN, mem.as_ref().len());
+    }
+
     #[test]
     fn test_nlhdr_deserialize() {
         let s: &mut [u8] = &mut [0; 16];
@@ -147,8 +276,134 @@ mod test {
             c.write_u16::<NativeEndian>(NlmF::Ack.into()).unwrap();
         }
         let mut mem = StreamReadBuffer::new(&*s);
-        let nl = Nlmsghdr::<Nlmsg, NlEmpty>::deserialize(&mut mem).unwrap();
-        assert_eq!(Nlmsghdr::<Nlmsg, NlEmpty>::new(None, Nlmsg::Noop,
-                                                 vec![NlmF::Ack], None, None, NlEmpty), nl);
+        let nl = Nlmsghdr::<Nlmsg, ()>::deserialize(&mut mem).unwrap();
+        assert_eq!(Nlmsghdr::<Nlmsg, ()>::new(None, Nlmsg::Noop, vec![NlmF::Ack], None, None, ()),
+                   nl);
+    }
+
+    #[test]
+    fn test_nlhdr_deserialize_incomplete() {
+        let nl = Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Ack], None, None, vec![1u8; 8]);
+        let bytes = nl.serialize_to_vec().unwrap();
+        assert_eq!(bytes.len(), 24);
+        match Nlmsghdr::<Nlmsg, Vec<u8>>::from_bytes(&bytes[..23]) {
+            Err(DeError::Incomplete { needed, got }) => {
+                assert_eq!(needed, 24);
+                assert_eq!(got, 23);
+            },
+            r => panic!("Expected an incomplete message, got {:?}", r),
+        }
+        match Nlmsghdr::<Nlmsg, Vec<u8>>::from_bytes(&bytes[..10]) {
+            Err(DeError::Incomplete { needed, got }) => {
+                assert_eq!(needed, 16);
+                assert_eq!(got, 10);
+            },
+            r => panic!("Expected an incomplete header, got {:?}", r),
+        }
+
+        let mut huge = bytes.clone();
+        NativeEndian::write_u32(&mut huge[..4], u32::max_value());
+        match Nlmsghdr::<Nlmsg, Vec<u8>>::from_bytes(&huge) {
+            Err(DeError::Incomplete { needed, got }) => {
+                assert_eq!(needed, u32::max_value() as usize);
+                assert_eq!(got, 24);
+            },
+            r => panic!("Expected an incomplete message, got {:?}", r),
+        }
+        assert_eq!(Nlmsghdr::<Nlmsg, Vec<u8>>::from_bytes(&bytes).unwrap(), nl);
+    }
+
+    #[test]
+    fn test_nlhdr_map() {
+        let nl = Nlmsghdr::<Nlmsg, ()>::new(None, Nlmsg::Noop, vec![NlmF::Ack], Some(3), Some(7),
+                                            ());
+        assert_eq!(nl.nl_len, 16);
+        let nl = nl.map_payload(|_| 5u32);
+        assert_eq!(nl, Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Ack], Some(3), Some(7), 5u32));
+        assert_eq!(nl.nl_len, 20);
+
+        let nl = nl.map_type(|_| Nlmsg::Done).map_payload(|p| vec![p as u8; 3]);
+        assert_eq!(nl.nl_type, Nlmsg::Done);
+        assert_eq!(nl.nl_flags, NlFlags::from(NlmF::Ack));
+        assert_eq!(nl.nl_seq, 3);
+        assert_eq!(nl.nl_pid, 7);
+        assert_eq!(nl.nl_len, 19);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        nl.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), nl.nl_len as usize);
+    }
+
+    #[test]
+    fn test_nlhdr_recompute_len() {
+        let mut nl = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None, vec![1u8; 4]);
+        assert_eq!(nl.nl_len, 20);
+        nl.nl_payload.extend_from_slice(&[2; 8]);
+        assert_eq!(nl.nl_len, 20);
+        nl.recompute_len();
+        assert_eq!(nl.nl_len, 28);
+        assert_eq!(nl.serialize_to_vec().unwrap().len(), 28);
+    }
+
+    #[test]
+    fn test_nlhdr_expect_type() {
+        use consts::Rtm;
+
+        let nl = Nlmsghdr::<Rtm, ()>::new(None, Rtm::Newaddr, Vec::new(), None, None, ());
+        assert!(nl.expect_type(Rtm::Newaddr).is_ok());
+        match nl.expect_type(Rtm::Newlink) {
+            Err(NlError::UnexpectedType { expected, got }) => {
+                assert_eq!(expected, u16::from(Rtm::Newlink));
+                assert_eq!(got, u16::from(Rtm::Newaddr));
+            },
+            _ => panic!("Expected NlError::UnexpectedType"),
+        }
+
+        let done = Nlmsghdr::<Rtm, ()>::new(None, Rtm::from(u16::from(Nlmsg::Done)), Vec::new(),
+                                            None, None, ());
+        match done.expect_type(Rtm::Newaddr) {
+            Err(NlError::UnexpectedDone) => (),
+            _ => panic!("Expected NlError::UnexpectedDone"),
+        }
+
+        let error = Nlmsghdr::<Rtm, RawPayload>::new(None, Rtm::from(u16::from(Nlmsg::Error)),
+                                                     Vec::new(), None, None,
+                                                     RawPayload(vec![0xed, 0xff, 0xff, 0xff]));
+        match error.expect_type(Rtm::Newaddr) {
+            Err(NlError::Errno(errno)) => assert_eq!(errno, 19),
+            _ => panic!("Expected NlError::Errno"),
+        }
+        let ack = Nlmsghdr::<Rtm, RawPayload>::new(None, Rtm::from(u16::from(Nlmsg::Error)),
+                                                   Vec::new(), None, None,
+                                                   RawPayload(vec![0; 4]));
+        match ack.expect_type(Rtm::Newaddr) {
+            Err(NlError::UnexpectedType { .. }) => (),
+            _ => panic!("Expected NlError::UnexpectedType"),
+        }
+    }
+
+    #[test]
+    fn test_nlhdr_parse_payload() {
+        let s: &mut [u8] = &mut [0; 20];
+        {
+            let mut c = Cursor::new(&mut *s);
+            c.write_u32::<NativeEndian>(20).unwrap();
+            c.write_u16::<NativeEndian>(1).unwrap();
+            c.write_u16::<NativeEndian>(0).unwrap();
+            c.write_u32::<NativeEndian>(0).unwrap();
+            c.write_u32::<NativeEndian>(0).unwrap();
+            c.write_u32::<NativeEndian>(600000).unwrap();
+        }
+        let mut mem = StreamReadBuffer::new(&*s);
+        let raw = Nlmsghdr::<Nlmsg, RawPayload>::deserialize(&mut mem).unwrap();
+        assert_eq!(raw.nl_type, Nlmsg::Noop);
+        assert_eq!(raw.nl_payload.size(), 4);
+        let nl = raw.parse_payload::<u32>().unwrap();
+        assert_eq!(Nlmsghdr::<Nlmsg, u32>::new(None, Nlmsg::Noop, Vec::new(), None, None,
+                                               600000), nl);
+
+        let raw = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None,
+                                RawPayload(vec![0, 1]));
+        assert!(raw.parse_payload::<u32>().is_err());
     }
 }
@@ -0,0 +1,175 @@
+//! # Human readable formatting for netlink messages
+//!
+//! This module provides `Display` implementations for the netlink message types to make
+//! debugging easier than staring at `Debug` output of nested structs and byte vectors. The
+//! header fields of a message are printed on one line followed by an indented tree of the
+//! payload:
+//!
+//! ```text
+//! NLMSG len=32 type=Ctrl flags=REQUEST|ACK seq=3 pid=0
+//!   GENL cmd=Getfamily version=2
+//!     NLA len=11 type=FamilyName payload=6e 6c 63 74 72 6c 00
+//! ```
+//!
+//! ## Design decisions
+//!
+//! * Attribute types are printed with the `Debug` output of the attribute type parameter, so
+//!   nested attributes, whose type is not known, are printed with numeric types.
+//! * Netlink does not reliably mark which attributes are nested so attribute payloads are
+//!   printed as hex by default. With the alternate flag (`{:#}`), payloads that parse
+//!   cleanly as a list of attributes are printed as a nested tree instead. This is a
+//!   heuristic - short integer payloads can occasionally be mistaken for nested attributes.
+
+use std::fmt::{self,Debug,Display};
+
+use buffering::copy::StreamReadBuffer;
+use byteorder::{ByteOrder,NativeEndian};
+
+use Nl;
+use consts::{checked_alignto,NlType};
+use genl::Genlmsghdr;
+use nl::{Nlmsghdr,RawPayload};
+use nlattr::{AttrHandle,Nlattr};
+
+/// Format a byte slice as space separated hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+// Format a child item, propagating the alternate flag
+fn fmt_child<D>(f: &fmt::Formatter, item: &D) -> String where D: Display {
+    if f.alternate() {
+        format!("{:#}", item)
+    } else {
+        format!("{}", item)
+    }
+}
+
+// Write each line of `s` on a new line indented by one level
+fn write_indented(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    for line in s.lines() {
+        write!(f, "\n  {}", line)?;
+    }
+    Ok(())
+}
+
+// Parse a buffer as a list of attributes only if it can be consumed completely without error
+fn parse_attrs<P>(bytes: &[u8]) -> Option<Vec<Nlattr<P>>> where P: Nl + Into<u16> + From<u16> {
+    let mut pos = 0;
+    let mut count = 0;
+    while pos < bytes.len() {
+        if bytes.len() - pos < Nlattr::<P>::HEADER_LEN {
+            return None;
+        }
+        let len = NativeEndian::read_u16(&bytes[pos..pos + 2]) as usize;
+        if len < Nlattr::<P>::HEADER_LEN || pos + len > bytes.len() {
+            return None;
+        }
+        pos += checked_alignto(len).ok()?;
+        count += 1;
+    }
+    let mut mem = StreamReadBuffer::new(bytes);
+    let mut attrs = Vec::with_capacity(count);
+    for _ in 0..count {
+        attrs.push(Nlattr::deserialize(&mut mem).ok()?);
+    }
+    Some(attrs)
+}
+
+impl<T, P> Display for Nlmsghdr<T, P> where T: NlType + Debug, P: Nl + Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = if self.nl_flags.is_empty() {
+            "NONE".to_string()
+        } else {
+            self.nl_flags.iter().map(|flag| format!("{:?}", flag).to_uppercase())
+                .collect::<Vec<_>>().join("|")
+        };
+        write!(f, "NLMSG len={} type={:?} flags={} seq={} pid={}", self.nl_len, self.nl_type,
+               flags, self.nl_seq, self.nl_pid)?;
+        let payload = fmt_child(f, &self.nl_payload);
+        write_indented(f, &payload)
+    }
+}
+
+impl Display for RawPayload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PAYLOAD {}", hex(self.0.as_slice()))
+    }
+}
+
+impl<C, T, H> Display for Genlmsghdr<C, T, H> where C: Nl + Debug + From<u8> + Into<u8>,
+        T: Nl + Debug + Into<u16> + From<u16> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GENL cmd={:?} version={}", self.cmd, self.version)?;
+        let attrs = fmt_child(f, &self.get_attr_handle());
+        write_indented(f, &attrs)
+    }
+}
+
+impl<T> Display for Nlattr<T> where T: Nl + Debug + Into<u16> + From<u16> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NLA len={} type={:?}", self.nla_len, self.nla_type)?;
+        let payload_len = (self.nla_len as usize).saturating_sub(Self::HEADER_LEN)
+            .min(self.payload.len());
+        let payload = &self.payload[..payload_len];
+        if f.alternate() {
+            if let Some(nested) = parse_attrs::<u16>(payload) {
+                if !nested.is_empty() {
+                    for attr in nested.iter() {
+                        write_indented(f, &format!("{:#}", attr))?;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        write!(f, " payload={}", hex(payload))
+    }
+}
+
+impl<'a, P> Display for AttrHandle<'a, P> where P: Nl + Debug + Into<u16> + From<u16> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines = match *self {
+            AttrHandle::Bin(bytes) => match parse_attrs::<P>(bytes) {
+                Some(attrs) => attrs.iter().map(|attr| fmt_child(f, attr)).collect::<Vec<_>>(),
+                None => vec![format!("MALFORMED ATTRS payload={}", hex(bytes))],
+            },
+            AttrHandle::Parsed(ref attrs) => {
+                attrs.iter().map(|attr| fmt_child(f, attr)).collect::<Vec<_>>()
+            },
+            AttrHandle::Borrowed(attrs) => {
+                attrs.iter().map(|attr| fmt_child(f, attr)).collect::<Vec<_>>()
+            },
+        };
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use consts::{CtrlAttr,CtrlCmd,GenlId,NlmF};
+
+    #[test]
+    fn test_fmt_genl() {
+        let attrs = vec![Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "nlctrl").unwrap()];
+        let genl = Genlmsghdr::new(CtrlCmd::Getfamily, 2, attrs).unwrap();
+        let nlhdr = Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Request, NlmF::Ack], Some(3),
+                                  None, genl);
+        assert_eq!(format!("{}", nlhdr), "NLMSG len=32 type=Ctrl flags=REQUEST|ACK seq=3 pid=0\n  \
+                                          GENL cmd=Getfamily version=2\n    \
+                                          NLA len=11 type=FamilyName payload=6e 6c 63 74 72 6c 00");
+        assert_eq!(format!("{}", nlhdr.nl_payload.get_attr_handle()),
+                   "NLA len=11 type=FamilyName payload=6e 6c 63 74 72 6c 00");
+    }
+
+    #[test]
+    fn test_fmt_nested() {
+        let attr = Nlattr::new_nested(None, 1u16, vec![
+            Nlattr::new_binary_payload(None, 2u16, vec![1, 2, 3, 4])
+        ]).unwrap();
+        assert_eq!(format!("{}", attr), "NLA len=12 type=1 payload=08 00 02 00 01 02 03 04");
+        assert_eq!(format!("{:#}", attr),
+                   "NLA len=12 type=1\n  NLA len=8 type=2 payload=01 02 03 04");
+    }
+}
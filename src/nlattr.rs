@@ -4,12 +4,12 @@
 //! ```no_run
 //! // This was received from the socket
 //! let nlmsg = neli::nl::Nlmsghdr::new(None, neli::consts::GenlId::Ctrl, Vec::new(), None, None,
-//!         neli::genl::Genlmsghdr::new::<u16>(0u8, 2, Vec::new()).unwrap());
+//!         neli::genl::Genlmsghdr::<u8, u16>::new(0u8, 2, Vec::new()).unwrap());
 //!
 //! // Get parsing handler for the attributes in this message where the next call
 //! // to either get_nested_attributes() or get_payload_with() will expect a u16 type
 //! // to be provided
-//! let mut handle = nlmsg.nl_payload.get_attr_handle::<u16>();
+//! let mut handle = nlmsg.nl_payload.get_attr_handle();
 //!
 //! // Get the nested attribute where the Nlattr field of nla_type is equal to 1 and return
 //! // a handler containing only this nested attribute internally
@@ -21,14 +21,22 @@
 //! ```
 
 use std::io::Read;
+use std::marker::PhantomData;
+use std::mem;
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
 use std::slice;
+use std::str;
 
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 use libc;
 
 use Nl;
 use err::{SerError,DeError};
-use consts::alignto;
+use consts::{checked_alignto,Af};
+
+/// Mask of the bits of `nla_type` holding the attribute type - the remaining bits are the
+/// `NLA_F_NESTED` and `NLA_F_NET_BYTEORDER` flags that some kernel subsystems set
+pub const NLA_TYPE_MASK: u16 = !(1 << 15 | 1 << 14);
 
 /// Struct representing netlink attributes and payloads
 #[derive(Debug,PartialEq)]
@@ -41,6 +49,11 @@ pub struct Nlattr<T> {
     pub payload: Vec<u8>,
 }
 
+impl<T> Nlattr<T> {
+    /// Length of the header fields preceding the payload
+    pub const HEADER_LEN: usize = mem::size_of::<u16>() + mem::size_of::<u16>();
+}
+
 impl<T> Nlattr<T> where T: Nl + Into<u16> + From<u16> {
     /// Create new netlink attribute with a payload
     pub fn new_binary_payload(nla_len: Option<u16>, nla_type: T, payload: Vec<u8>)
@@ -69,7 +82,7 @@ impl<T> Nlattr<T> where T: Nl + Into<u16> + From<u16> {
     }
 
     /// Create new netlink attribute with a nested payload
-    pub fn new_nested<P>(nla_len: Option<u16>, nla_type: T, mut payload: Vec<Nlattr<P>>)
+    pub fn new_nested<P>(nla_len: Option<u16>, nla_type: T, payload: Vec<Nlattr<P>>)
             -> Result<Self, SerError> where P: Nl + Into<u16> + From<u16> {
         let mut nla = Nlattr {
             nla_type,
@@ -77,13 +90,8 @@ impl<T> Nlattr<T> where T: Nl + Into<u16> + From<u16> {
                 let mut mem = StreamWriteBuffer::new_growable(Some(payload.iter().fold(0, |acc, item| {
                     acc + item.asize()
                 })));
-                for item in payload.iter_mut() {
+                for item in payload.iter() {
                     item.serialize(&mut mem)?;
-                    if item.payload.asize() != item.payload.size() {
-                        [0u8; libc::NLA_ALIGNTO as usize]
-                            [0..item.payload.asize() - item.payload.size()]
-                            .as_ref().serialize(&mut mem)?;
-                    }
                 }
                 mem.as_ref().to_vec()
             },
@@ -103,11 +111,6 @@ impl<T> Nlattr<T> where T: Nl + Into<u16> + From<u16> {
             payload: {
                 let mut mem = StreamWriteBuffer::new_growable(Some(string_payload.asize()));
                 string_payload.serialize(&mut mem)?;
-                if string_payload.asize() != string_payload.size() {
-                    [0u8; libc::NLA_ALIGNTO as usize]
-                        [0..string_payload.asize() - string_payload.size()]
-                        .as_ref().serialize(&mut mem)?;
-                }
                 mem.as_ref().to_vec()
             },
         };
@@ -121,20 +124,107 @@ impl<T> Nlattr<T> where T: Nl + Into<u16> + From<u16> {
         Self::new_string_payload(nla_len, nla_type, string_payload)
     }
 
+    /// Create new netlink attribute with an IP address payload - 4 bytes for IPv4 and 16 bytes
+    /// for IPv6, both in network byte order
+    pub fn new_ip_payload(nla_len: Option<u16>, nla_type: T, addr: IpAddr) -> Self {
+        let payload = match addr {
+            IpAddr::V4(a) => a.octets().to_vec(),
+            IpAddr::V6(a) => a.octets().to_vec(),
+        };
+        Self::new_binary_payload(nla_len, nla_type, payload)
+    }
+
+    /// Get the payload as a raw byte slice without the padding following the attribute
+    pub fn payload_bytes(&self) -> &[u8] {
+        self.payload.as_slice()
+    }
+
+    // Payload up to the first null byte - the kernel null terminates string attributes
+    fn str_bytes(&self) -> &[u8] {
+        match self.payload.iter().position(|b| *b == 0) {
+            Some(i) => &self.payload[..i],
+            None => self.payload.as_slice(),
+        }
+    }
+
+    /// Parse the payload as a string, failing if it is not valid UTF-8
+    pub fn get_payload_as_str(&self) -> Result<&str, DeError> {
+        Ok(str::from_utf8(self.str_bytes())?)
+    }
+
+    /// Parse the payload as a string, replacing invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`
+    pub fn get_payload_as_string_lossy(&self) -> String {
+        String::from_utf8_lossy(self.str_bytes()).into_owned()
+    }
+
+    /// Parse the payload as an IP address of the given address family
+    pub fn get_payload_as_ipaddr(&self, family: Af) -> Result<IpAddr, DeError> {
+        match family {
+            Af::Inet => {
+                if self.payload.len() != 4 {
+                    return Err(DeError::new("IPv4 address payload must be 4 bytes"));
+                }
+                let mut octets = [0u8; 4];
+                octets.copy_from_slice(self.payload.as_slice());
+                Ok(IpAddr::V4(Ipv4Addr::from(octets)))
+            },
+            Af::Inet6 => {
+                if self.payload.len() != 16 {
+                    return Err(DeError::new("IPv6 address payload must be 16 bytes"));
+                }
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(self.payload.as_slice());
+                Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+            },
+            _ => Err(DeError::new("Unsupported address family")),
+        }
+    }
+
+    /// Parse the payload as a `u16` in network byte order
+    pub fn get_payload_as_be_u16(&self) -> Result<u16, DeError> {
+        Ok(u16::from_be(u16::deserialize(&mut StreamReadBuffer::new(self.payload_bytes()))?))
+    }
+
+    /// Parse the payload as a `u32` in network byte order
+    pub fn get_payload_as_be_u32(&self) -> Result<u32, DeError> {
+        Ok(u32::from_be(u32::deserialize(&mut StreamReadBuffer::new(self.payload_bytes()))?))
+    }
+
+    /// Parse the payload as a `u64` in network byte order
+    pub fn get_payload_as_be_u64(&self) -> Result<u64, DeError> {
+        Ok(u64::from_be(u64::deserialize(&mut StreamReadBuffer::new(self.payload_bytes()))?))
+    }
+
     /// Get handle for attribute parsing and traversal
     pub fn get_attr_handle<'a, P>(&'a self) -> AttrHandle<'a, P> {
         AttrHandle::Bin(self.payload.as_slice())
     }
+
+    /// Parse the payload as a list of nested attributes and return a parsed handle for
+    /// traversing them
+    pub fn get_nested_attributes<'a, P>(&'a self) -> Result<AttrHandle<'a, P>, DeError>
+            where P: PartialEq + Nl + Into<u16> + From<u16> {
+        let mut handle = self.get_attr_handle();
+        handle.parse_nested_attributes()?;
+        Ok(handle)
+    }
 }
 
 impl<T> Nl for Nlattr<T> where T: Nl + Into<u16> + From<u16> {
     type SerIn = ();
     type DeIn = ();
 
+    /// Serialize the attribute followed by the padding required to align the next attribute
+    /// to `NLA_ALIGNTO` - `nla_len` does not include this padding
     fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.nla_len.serialize(mem)?;
         self.nla_type.serialize(mem)?;
         self.payload.serialize(mem)?;
+        let padding_len = self.asize() - self.size();
+        if padding_len > 0 {
+            [0u8; libc::NLA_ALIGNTO as usize][0..padding_len].as_ref().serialize(mem)?;
+        }
         Ok(())
     }
 
@@ -144,24 +234,61 @@ impl<T> Nl for Nlattr<T> where T: Nl + Into<u16> + From<u16> {
             nla_type: T::deserialize(mem)?,
             payload: Vec::new(),
         };
-        nla.payload = Vec::<u8>::deserialize_with(mem, nla.nla_len as usize -
-                                                  (nla.nla_len.size() + nla.nla_type.size()))?;
-        let padding = &mut [0u8; 4][0..alignto(nla.nla_len as usize) - nla.nla_len as usize];
+        let payload_len = (nla.nla_len as usize)
+            .checked_sub(nla.nla_len.size() + nla.nla_type.size())
+            .ok_or_else(|| DeError::new("Attribute length is shorter than the header"))?;
+        nla.payload = Vec::<u8>::deserialize_with(mem, payload_len)?;
+        let padding_len = checked_alignto(nla.nla_len as usize)? - nla.nla_len as usize;
+        let padding = &mut [0u8; 4][0..padding_len];
         let _ = mem.read_exact(padding);
         Ok(nla)
     }
 
+    /// Length of the header and the unpadded payload as stored in `nla_len` - `asize` includes
+    /// the padding emitted by `serialize`
     fn size(&self) -> usize {
         self.nla_len.size() + self.nla_type.size() + self.payload.size()
     }
 }
 
+impl<T> Nl for Vec<Nlattr<T>> where T: Nl + Into<u16> + From<u16> {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        for nla in self.iter() {
+            nla.serialize(mem)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        mem.read_to_end(&mut bytes)?;
+        let mut buf = StreamReadBuffer::new(bytes.as_slice());
+        let mut left = bytes.len();
+        let mut attrs = Vec::new();
+        while left > 0 {
+            let nla = Nlattr::<T>::deserialize(&mut buf)?;
+            left = left.saturating_sub(nla.asize());
+            attrs.push(nla);
+        }
+        Ok(attrs)
+    }
+
+    fn size(&self) -> usize {
+        self.iter().fold(0, |acc, nla| acc + nla.asize())
+    }
+}
+
 /// Handle returned by `Genlmsghdr` for traversing nested attribute structures
 pub enum AttrHandle<'a, P> {
     /// Binary internal representation of attributes
     Bin(&'a [u8]),
     /// Rust representation of attributes
     Parsed(Vec<Nlattr<P>>),
+    /// Rust representation of attributes owned by a message such as `Genlmsghdr`
+    Borrowed(&'a [Nlattr<P>]),
 }
 
 impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
@@ -169,6 +296,7 @@ impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
     pub fn len(&self) -> Option<usize> {
         match *self {
             AttrHandle::Parsed(ref v) => Some(v.len()),
+            AttrHandle::Borrowed(v) => Some(v.len()),
             _ => None,
         }
     }
@@ -177,6 +305,7 @@ impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
     pub fn iter(&self) -> Option<slice::Iter<Nlattr<P>>> {
         match *self {
             AttrHandle::Parsed(ref v) => Some(v.iter()),
+            AttrHandle::Borrowed(v) => Some(v.iter()),
             _ => None,
         }
     }
@@ -204,6 +333,33 @@ impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
         Ok(self)
     }
 
+    /// Call `f` with every attribute in order - a binary handle is parsed as it is walked
+    /// without storing the parsed attributes. Walking stops at the first error returned by
+    /// `f`.
+    ///
+    /// With a `match` on `nla_type`, this decodes attributes with differing payload types in
+    /// one pass.
+    pub fn parse_with<F>(&self, mut f: F) -> Result<(), DeError>
+            where F: FnMut(&Nlattr<P>) -> Result<(), DeError> {
+        match *self {
+            AttrHandle::Bin(bytes) => {
+                let mut mem = StreamReadBuffer::new(bytes);
+                let mut left = bytes.len();
+                while left > 0 {
+                    let attr = Nlattr::deserialize(&mut mem)?;
+                    left = left.saturating_sub(attr.asize());
+                    f(&attr)?;
+                }
+            },
+            _ => {
+                for attr in self.iter().into_iter().flatten() {
+                    f(attr)?;
+                }
+            },
+        };
+        Ok(())
+    }
+
     /// Get the payload of an attribute as a handle for parsing nested attributes
     pub fn get_nested_attributes<S>(&mut self, payload: P) -> Result<AttrHandle<S>, DeError> {
         let nested = self.parse_nested_attributes()?.get_attribute(payload);
@@ -215,20 +371,22 @@ impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
 
     /// Get nested attributes from a parsed handle
     pub fn get_attribute(&'a self, p: P) -> Option<&'a Nlattr<P>> {
-        match *self {
-            AttrHandle::Parsed(ref parsed) => {
-                for item in parsed {
-                    if item.nla_type == p {
-                        return Some(&item);
-                    }
-                }
-                None
+        self.iter().and_then(|mut iter| iter.find(|item| item.nla_type == p))
+    }
+
+    /// Get the raw payload of an attribute from a parsed handle - fails for a binary handle
+    /// that has not been parsed with `parse_nested_attributes`
+    pub fn get_attr_payload(&self, p: P) -> Result<Option<&[u8]>, DeError> {
+        match self.iter() {
+            Some(mut iter) => {
+                Ok(iter.find(|item| item.nla_type == p).map(|item| item.payload_bytes()))
             },
-            _ => None,
+            None => Err(DeError::new("Attribute handle has not been parsed")),
         }
     }
 
-    /// Mutably get nested attributes from a parsed handle
+    /// Mutably get nested attributes from a parsed handle - always `None` for
+    /// `AttrHandle::Borrowed`
     pub fn get_attribute_mut(&'a mut self, p: P) -> Option<&'a mut Nlattr<P>> {
         match *self {
             AttrHandle::Parsed(ref mut parsed) => {
@@ -260,3 +418,258 @@ impl<'a, P> AttrHandle<'a, P> where P: PartialEq + Nl + Into<u16> + From<u16> {
         }
     }
 }
+
+/// Incremental parser for a stream of attributes arriving in chunks - complete attributes
+/// are returned as soon as their bytes have been fed and partial ones are buffered until the
+/// rest arrives
+pub struct StreamingAttrParser<T> {
+    buf: Vec<u8>,
+    // Padding of the last returned attribute that has not arrived yet
+    skip: usize,
+    nla_type: PhantomData<T>,
+}
+
+impl<T> StreamingAttrParser<T> where T: Nl + Into<u16> + From<u16> {
+    /// Create a parser with nothing buffered
+    pub fn new() -> Self {
+        StreamingAttrParser { buf: Vec::new(), skip: 0, nla_type: PhantomData }
+    }
+
+    /// Feed the next chunk of the stream and return the attributes completed by it
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<Nlattr<T>>, DeError> {
+        let skipped = self.skip.min(bytes.len());
+        self.skip -= skipped;
+        self.buf.extend_from_slice(&bytes[skipped..]);
+
+        let mut attrs = Vec::new();
+        let mut pos = 0;
+        while self.buf.len() - pos >= Nlattr::<T>::HEADER_LEN {
+            let nla_len = u16::deserialize(&mut StreamReadBuffer::new(&self.buf[pos..]))? as usize;
+            if nla_len < Nlattr::<T>::HEADER_LEN {
+                return Err(DeError::new("Attribute length is shorter than its header"));
+            }
+            if self.buf.len() - pos < nla_len {
+                break;
+            }
+            attrs.push(Nlattr::deserialize(
+                &mut StreamReadBuffer::new(&self.buf[pos..pos + nla_len])
+            )?);
+            let aligned_len = checked_alignto(nla_len)?;
+            let consumed = aligned_len.min(self.buf.len() - pos);
+            self.skip = aligned_len - consumed;
+            pos += consumed;
+        }
+        self.buf.drain(..pos);
+        Ok(attrs)
+    }
+
+    /// Number of bytes of a partial attribute currently buffered
+    pub fn pending(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// End the stream, failing if a partial attribute is still buffered
+    pub fn finish(self) -> Result<(), DeError> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            Err(DeError::new("Stream ended in the middle of an attribute"))
+        }
+    }
+}
+
+impl<T> Default for StreamingAttrParser<T> where T: Nl + Into<u16> + From<u16> {
+    fn default() -> Self {
+        StreamingAttrParser::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use consts::{CtrlAttr,CtrlAttrOp,Ifla};
+    use rtnl::RtAttr;
+
+    #[test]
+    fn test_serialize_padding() {
+        let attr = Nlattr::new_binary_payload(None, 1u16, vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(attr.nla_len, 11);
+        assert_eq!(attr.size(), 11);
+        assert_eq!(attr.asize(), 12);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), &[11, 0, 1, 0, 1, 2, 3, 4, 5, 6, 7, 0]);
+
+        let parsed = Nlattr::<u16>::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, attr);
+    }
+
+    #[test]
+    fn test_deserialize_short_len() {
+        for nla_len in 0..4u8 {
+            let bytes = [nla_len, 0, 1, 0, 0xff, 0xff, 0xff, 0xff];
+            assert!(Nlattr::<u16>::from_bytes(&bytes).is_err());
+        }
+        assert_eq!(Nlattr::<u16>::from_bytes(&[4, 0, 1, 0]).unwrap().payload, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_streaming_attr_parser() {
+        let first = Nlattr::new_binary_payload(None, 1u16, vec![1, 2, 3, 4, 5]);
+        let second = Nlattr::new_nl_payload(None, 2u16, 7u32).unwrap();
+        let mut bytes = first.serialize_to_vec().unwrap();
+        bytes.extend(second.serialize_to_vec().unwrap());
+
+        // Split inside the payload of the first attribute, then inside its padding
+        let mut parser = StreamingAttrParser::<u16>::new();
+        assert!(parser.feed(&bytes[..6]).unwrap().is_empty());
+        assert_eq!(parser.pending(), 6);
+        assert_eq!(parser.feed(&bytes[6..10]).unwrap(), vec![first]);
+        assert_eq!(parser.pending(), 0);
+        assert_eq!(parser.feed(&bytes[10..]).unwrap(), vec![second]);
+        parser.finish().unwrap();
+
+        let mut parser = StreamingAttrParser::<u16>::new();
+        parser.feed(&bytes[..14]).unwrap();
+        assert!(parser.finish().is_err());
+        assert!(StreamingAttrParser::<u16>::new().feed(&[2, 0, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn test_header_len() {
+        let attr = Nlattr::new_binary_payload(None, 1u16, Vec::new());
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        assert_eq!(Nlattr::<u16>::HEADER_LEN, mem.as_ref().len());
+    }
+
+    #[test]
+    fn test_nested_ops() {
+        let op = |id: u32, flags: u32| {
+            vec![Nlattr::new_nl_payload(None, CtrlAttrOp::Id, id).unwrap(),
+                 Nlattr::new_nl_payload(None, CtrlAttrOp::Flags, flags).unwrap()]
+        };
+        let ops = Nlattr::new_nested(None, CtrlAttr::Ops, vec![
+            Nlattr::new_nested(None, 1u16, op(3, 0x0e)).unwrap(),
+            Nlattr::new_nested(None, 2u16, op(10, 0x0c)).unwrap(),
+        ]).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ops.serialize(&mut mem).unwrap();
+        let parsed = Nlattr::<CtrlAttr>::deserialize(&mut StreamReadBuffer::new(mem.as_ref()))
+            .unwrap();
+        assert_eq!(parsed.nla_type, CtrlAttr::Ops);
+
+        let handle = parsed.get_nested_attributes::<u16>().unwrap();
+        assert_eq!(handle.len(), Some(2));
+        let mut decoded = Vec::new();
+        for entry in handle.iter().unwrap() {
+            let mut op_handle = entry.get_nested_attributes::<CtrlAttrOp>().unwrap();
+            decoded.push((op_handle.get_payload_with::<u32>(CtrlAttrOp::Id, None).unwrap(),
+                          op_handle.get_payload_with::<u32>(CtrlAttrOp::Flags, None).unwrap()));
+        }
+        assert_eq!(decoded, vec![(3, 0x0e), (10, 0x0c)]);
+    }
+
+    #[test]
+    fn test_payload_bytes() {
+        let payload = vec![1, 2, 3, 4, 5, 6, 7];
+        let attrs = vec![Nlattr::new_binary_payload(None, 1u16, payload.clone()),
+                         Nlattr::new_nl_payload(None, 2u16, 8u32).unwrap()];
+        assert_eq!(attrs[0].payload_bytes(), payload.as_slice());
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        for attr in attrs.iter() {
+            attr.serialize(&mut mem).unwrap();
+        }
+
+        let mut handle = AttrHandle::<u16>::Bin(mem.as_ref());
+        assert!(handle.get_attr_payload(1).is_err());
+        handle.parse_nested_attributes().unwrap();
+        assert_eq!(handle.get_attr_payload(1).unwrap(), Some(payload.as_slice()));
+        assert_eq!(handle.get_attr_payload(2).unwrap().map(|p| p.len()), Some(4));
+        assert_eq!(handle.get_attr_payload(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_str_payload() {
+        let attr = Nlattr::new_str_payload(None, 1u16, "eth0").unwrap();
+        assert_eq!(attr.get_payload_as_str().unwrap(), "eth0");
+        assert_eq!(attr.get_payload_as_string_lossy(), "eth0");
+
+        let attr = Nlattr::new_binary_payload(None, 1u16, b"eth0".to_vec());
+        assert_eq!(attr.get_payload_as_str().unwrap(), "eth0");
+        assert_eq!(attr.get_payload_as_string_lossy(), "eth0");
+
+        let attr = Nlattr::new_binary_payload(None, 1u16, vec![b'e', 0xff, b'0', 0]);
+        assert!(attr.get_payload_as_str().is_err());
+        assert_eq!(attr.get_payload_as_string_lossy(), "e\u{fffd}0");
+    }
+
+    #[test]
+    fn test_ipv4_payload() {
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        let attr = Nlattr::new_ip_payload(None, 1u16, addr);
+        assert_eq!(attr.payload, vec![192, 168, 1, 1]);
+        assert_eq!(attr.nla_len, 8);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        let parsed = Nlattr::<u16>::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed.get_payload_as_ipaddr(Af::Inet).unwrap(), addr);
+        assert!(parsed.get_payload_as_ipaddr(Af::Inet6).is_err());
+    }
+
+    #[test]
+    fn test_ipv6_payload() {
+        let addr = IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0x1, 0x2, 0x3, 0x4));
+        let attr = Nlattr::new_ip_payload(None, 1u16, addr);
+        assert_eq!(attr.payload.len(), 16);
+        assert_eq!(attr.nla_len, 20);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        let parsed = Nlattr::<u16>::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed.get_payload_as_ipaddr(Af::Inet6).unwrap(), addr);
+        assert!(parsed.get_payload_as_ipaddr(Af::Inet).is_err());
+    }
+
+    #[test]
+    fn test_parse_with() {
+        #[derive(Debug,Default,PartialEq)]
+        struct LinkAttrs {
+            name: Option<String>,
+            mtu: Option<u32>,
+        }
+
+        let rtattrs = vec![
+            RtAttr::new(None, Ifla::Ifname, "eth0".to_string()).unwrap(),
+            RtAttr::new(None, Ifla::Link, 2i32).unwrap(),
+            RtAttr::new(None, Ifla::Mtu, 1500u32).unwrap(),
+        ];
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rtattrs.serialize(&mut mem).unwrap();
+
+        let mut link = LinkAttrs::default();
+        let handle = AttrHandle::<Ifla>::Bin(mem.as_ref());
+        handle.parse_with(|attr| {
+            match attr.nla_type {
+                Ifla::Ifname => link.name = Some(attr.get_payload_as_str()?.to_string()),
+                Ifla::Mtu => {
+                    link.mtu = Some(u32::deserialize(&mut StreamReadBuffer::new(&attr.payload))?)
+                },
+                _ => (),
+            };
+            Ok(())
+        }).unwrap();
+        assert_eq!(link, LinkAttrs { name: Some("eth0".to_string()), mtu: Some(1500) });
+
+        let mut count = 0;
+        let err = handle.parse_with(|_| {
+            count += 1;
+            Err(DeError::new("Stop"))
+        });
+        assert!(err.is_err());
+        assert_eq!(count, 1);
+    }
+}
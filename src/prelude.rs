@@ -0,0 +1,27 @@
+//! This module re-exports the types and traits that most netlink code needs so that they can
+//! be imported with a single `use neli::prelude::*;`.
+//!
+//! Protocol specific constants (for example `CtrlCmd` or the rtnetlink enums) are not
+//! included to avoid polluting the namespace of users that do not need them - import these
+//! from `neli::consts`.
+//!
+//! ```
+//! use neli::prelude::*;
+//! use neli::consts::{CtrlAttr,CtrlCmd,GenlId};
+//!
+//! let attrs = vec![Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "nlctrl").unwrap()];
+//! let genl = Genlmsghdr::new(CtrlCmd::Getfamily, 2, attrs).unwrap();
+//! let nlhdr = Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Request], None, None, genl);
+//!
+//! let mut mem = StreamWriteBuffer::new_growable(Some(nlhdr.asize()));
+//! nlhdr.serialize(&mut mem).unwrap();
+//! assert_eq!(mem.as_ref().len(), nlhdr.size());
+//! ```
+
+pub use {Nl,StreamReadBuffer,StreamWriteBuffer};
+pub use consts::{NlFamily,NlmF,NlType};
+pub use err::{DeError,NlError,SerError};
+pub use genl::Genlmsghdr;
+pub use nl::Nlmsghdr;
+pub use nlattr::{AttrHandle,Nlattr};
+pub use socket::NlSocket;
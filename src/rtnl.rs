@@ -1,29 +1,138 @@
+use std::fmt;
+use std::io::{Read,Write};
 use std::mem;
+use std::net::{IpAddr,Ipv4Addr};
 
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
 use libc;
 
 use Nl;
-use consts::{Af,Arphrd,AddrFamily,IfaF,Iff,Ntf,Nud,RtaType,RtmF,Rtn,Rtprot,RtScope,RtTable};
-use err::{SerError,DeError};
+use consts::{alignto,checked_alignto,AddrFamily,Af,Arphrd,FibRuleF,FrAct,Fra,Ifa,IfaF,Iff,IfOper,
+             Ifla,IflaInfo,IflaVlan,Nda,Ndta,Ndtpa,NlFlags,NlmF,Ntf,Nud,Rta,RtaType,Rtax,Rtm,RtmF,
+             Rtn,RtnhF,Rtprot,RtScope,RtTable,Tca,VethInfo,VlanFlag};
+use err::{NlError,SerError,DeError};
+use nl::Nlmsghdr;
+use nlattr::{AttrHandle,Nlattr};
+use socket::NlSocket;
+
+/// Struct representing the generic route netlink message used as the payload of simple dump
+/// requests such as `RTM_GETLINK` with `NlmF::Dump`
+///
+/// `nl_len` only covers the single family byte, matching `NLMSG_LENGTH(sizeof(struct rtgenmsg))`
+/// in C, while the padding that the kernel adds to align the following message is skipped on
+/// deserialization.
+#[derive(Debug,PartialEq)]
+pub struct Rtgenmsg {
+    /// Address family for the request
+    pub rtgen_family: AddrFamily,
+}
+
+impl Rtgenmsg {
+    /// Length of the message excluding the alignment padding
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>();
+
+    /// Create a new generic route netlink message for the given family
+    pub fn new(rtgen_family: AddrFamily) -> Self {
+        Rtgenmsg { rtgen_family }
+    }
+}
+
+impl Nl for Rtgenmsg {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        (libc::c_int::from(self.rtgen_family) as libc::c_uchar).serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let family = libc::c_uchar::deserialize(buf)?;
+        let padding = &mut [0u8; 4][0..alignto(mem::size_of::<libc::c_uchar>())
+            - mem::size_of::<libc::c_uchar>()];
+        let _ = buf.read_exact(padding);
+        Ok(Rtgenmsg { rtgen_family: AddrFamily::from(libc::c_int::from(family)) })
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<libc::c_uchar>()
+    }
+}
 
 /// Struct representing interface information messages
+#[derive(Debug,PartialEq)]
 pub struct Ifinfomsg {
     /// Interface address family
-    pub ifi_family: AddrFamily,
+    pub ifi_family: Af,
     /// Interface type
     pub ifi_type: Arphrd,
     /// Interface index
     pub ifi_index: libc::c_int,
     /// Interface flags
-    pub ifi_flags: Vec<Iff>,
-    ifi_change: libc::c_uint,
+    pub ifi_flags: NlFlags<Iff>,
+    /// Mask of the interface flags that should be changed
+    pub ifi_change: libc::c_uint,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Ifla>>,
 }
 
 impl Ifinfomsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() + mem::size_of::<u8>()
+        + mem::size_of::<libc::c_ushort>() + mem::size_of::<libc::c_int>()
+        + mem::size_of::<libc::c_uint>() + mem::size_of::<libc::c_uint>();
+
     /// Create a fully initialized interface info struct
-    pub fn new(ifi_family: AddrFamily, ifi_type: Arphrd, ifi_index: libc::c_int, ifi_flags: Vec<Iff>) -> Self {
-        Ifinfomsg { ifi_family, ifi_type, ifi_index, ifi_flags, ifi_change: 0xffffffff }
+    pub fn new(ifi_family: Af, ifi_type: Arphrd, ifi_index: libc::c_int,
+               ifi_flags: NlFlags<Iff>, rtattrs: Vec<RtAttr<Ifla>>) -> Self {
+        Ifinfomsg { ifi_family, ifi_type, ifi_index, ifi_flags, ifi_change: 0xffffffff, rtattrs }
+    }
+
+    /// Create an interface info struct for a dump request (`RTM_GETLINK` with `NlmF::Dump`)
+    /// where every field except the family is zeroed
+    pub fn dump(ifi_family: Af) -> Self {
+        Ifinfomsg {
+            ifi_family,
+            ifi_type: Arphrd::from(0),
+            ifi_index: 0,
+            ifi_flags: NlFlags::empty(),
+            ifi_change: 0,
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the 32 bit interface statistics from the `Ifla::Stats` attribute
+    pub fn get_stats(&self) -> Result<Option<LinkStats>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Stats) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<LinkStats>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the 64 bit interface statistics from the `Ifla::Stats64` attribute
+    pub fn get_stats64(&self) -> Result<Option<LinkStats64>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Stats64) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<LinkStats64>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the kind of a virtual interface such as `"dummy"` or `"veth"` from the
+    /// `IflaInfo::Kind` attribute nested in `Ifla::Linkinfo`
+    pub fn get_link_kind(&self) -> Result<Option<String>, DeError> {
+        let linkinfo = match self.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Linkinfo) {
+            Some(rta) => rta.get_nested::<IflaInfo>()?,
+            None => return Ok(None),
+        };
+        match linkinfo.iter().find(|rta| rta.rta_type == IflaInfo::Kind) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<String>(Some(rta.rta_payload.len()))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Ifla> {
+        rtattr_handle(&self.rtattrs)
     }
 }
 
@@ -33,53 +142,638 @@ impl Nl for Ifinfomsg {
 
     fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.ifi_family.serialize(buf)?;
+        // Padding after ifi_family
+        0u8.serialize(buf)?;
         self.ifi_type.serialize(buf)?;
         self.ifi_index.serialize(buf)?;
-        self.ifi_flags.iter().fold(0, |acc: libc::c_uint, next| {
-            let next_uint: libc::c_uint = next.into();
-            acc | next_uint
-        }).serialize(buf)?;
+        self.ifi_flags.serialize(buf)?;
         self.ifi_change.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
         Ok(())
     }
 
     fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
         Ok(Ifinfomsg {
-            ifi_family: AddrFamily::deserialize(buf)?,
-            ifi_type: Arphrd::deserialize(buf)?,
+            ifi_family: Af::deserialize(buf)?,
+            ifi_type: {
+                u8::deserialize(buf)?;
+                Arphrd::deserialize(buf)?
+            },
             ifi_index: libc::c_int::deserialize(buf)?,
-            ifi_flags: {
-                let flags = libc::c_uint::deserialize(buf)?;
-                let mut nl_flags = Vec::new();
-                for i in 0..mem::size_of::<libc::c_int>() * 8 {
-                    let bit = 1 << i;
-                    if bit & flags == bit {
-                        nl_flags.push(bit.into());
-                    }
-                }
-                nl_flags
+            ifi_flags: NlFlags::deserialize(buf)?,
+            ifi_change: libc::c_uint::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<Ifla>>::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.ifi_family.size() + mem::size_of::<u8>() + self.ifi_type.size()
+            + self.ifi_index.size() + self.ifi_flags.size() + self.ifi_change.size()
+            + self.rtattrs.size()
+    }
+}
+
+/// Summary of a network interface as reported by `RTM_GETLINK` - see `get_links`
+#[derive(Debug,PartialEq)]
+pub struct Link {
+    /// Interface index
+    pub index: libc::c_int,
+    /// Interface name from `Ifla::Ifname`
+    pub name: String,
+    /// Hardware address from `Ifla::Address` - not present for interfaces such as tunnels
+    pub mac: Option<Vec<u8>>,
+    /// MTU from `Ifla::Mtu`
+    pub mtu: Option<u32>,
+    /// Interface flags
+    pub flags: NlFlags<Iff>,
+    /// Operational state from `Ifla::Operstate`
+    pub oper_state: Option<IfOper>,
+    /// All attributes of the link for anything not covered by the fields above
+    pub rtattrs: Vec<RtAttr<Ifla>>,
+}
+
+impl Link {
+    /// Extract the link information from an interface info message returned by the kernel
+    pub fn from_ifinfomsg(ifinfomsg: Ifinfomsg) -> Result<Self, DeError> {
+        let mut name = None;
+        let mut mac = None;
+        let mut mtu = None;
+        let mut oper_state = None;
+        for rta in ifinfomsg.rtattrs.iter() {
+            match rta.rta_type {
+                Ifla::Ifname => {
+                    name = Some(rta.get_payload_with::<String>(Some(rta.rta_payload.len()))?)
+                },
+                Ifla::Address => mac = Some(rta.rta_payload.clone()),
+                Ifla::Mtu => mtu = Some(rta.get_payload_with::<u32>(None)?),
+                Ifla::Operstate => oper_state = Some(rta.get_payload_with::<IfOper>(None)?),
+                _ => (),
+            }
+        }
+        Ok(Link {
+            index: ifinfomsg.ifi_index,
+            name: name.ok_or_else(|| DeError::new("Link is missing the Ifla::Ifname attribute"))?,
+            mac,
+            mtu,
+            flags: ifinfomsg.ifi_flags,
+            oper_state,
+            rtattrs: ifinfomsg.rtattrs,
+        })
+    }
+}
+
+/// Enumerate all network interfaces by sending an `RTM_GETLINK` dump request and collecting
+/// the responses until `Nlmsg::Done`
+pub fn get_links(sock: &mut NlSocket<Rtm, Ifinfomsg>) -> Result<Vec<Link>, NlError> {
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Getlink, vec![NlmF::Request, NlmF::Dump], None, None,
+                               Ifinfomsg::dump(Af::Unspec)))?;
+    let mut links = Vec::new();
+    for msg in sock.iter() {
+        links.push(Link::from_ifinfomsg(msg?.nl_payload)?);
+    }
+    Ok(links)
+}
+
+/// Changes to apply to a link with `link_set` - fields left as `None` are not changed
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct LinkChanges {
+    /// Set the administrative state of the link to up (`true`) or down (`false`)
+    pub up: Option<bool>,
+    /// New MTU
+    pub mtu: Option<u32>,
+    /// New interface name
+    pub name: Option<String>,
+    /// New hardware address
+    pub mac: Option<Vec<u8>>,
+}
+
+impl LinkChanges {
+    /// Create a set of changes that does not change anything
+    pub fn new() -> Self {
+        LinkChanges::default()
+    }
+
+    /// Bring the link up
+    pub fn up(mut self) -> Self {
+        self.up = Some(true);
+        self
+    }
+
+    /// Bring the link down
+    pub fn down(mut self) -> Self {
+        self.up = Some(false);
+        self
+    }
+
+    /// Change the MTU
+    pub fn mtu(mut self, mtu: u32) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Rename the link
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Change the hardware address
+    pub fn mac(mut self, mac: Vec<u8>) -> Self {
+        self.mac = Some(mac);
+        self
+    }
+
+    /// Build the interface info message applying these changes to the link with index
+    /// `ifindex`
+    ///
+    /// `ifi_change` only covers `Iff::Up` when the administrative state is changed so that
+    /// no other flags of the link are touched.
+    pub fn into_ifinfomsg(self, ifindex: libc::c_int) -> Result<Ifinfomsg, SerError> {
+        let mut ifinfomsg = Ifinfomsg { ifi_index: ifindex, ..Ifinfomsg::dump(Af::Unspec) };
+        if let Some(up) = self.up {
+            if up {
+                ifinfomsg.ifi_flags.insert(Iff::Up);
+            }
+            ifinfomsg.ifi_change = libc::c_uint::from(Iff::Up);
+        }
+        if let Some(mtu) = self.mtu {
+            ifinfomsg.rtattrs.push(RtAttr::new(None, Ifla::Mtu, mtu)?);
+        }
+        if let Some(name) = self.name {
+            if name.is_empty() || name.len() >= libc::IFNAMSIZ {
+                return Err(SerError::new("Interface names must be 1 to 15 bytes long"));
+            }
+            ifinfomsg.rtattrs.push(RtAttr::new(None, Ifla::Ifname, name.as_str())?);
+        }
+        if let Some(mac) = self.mac {
+            ifinfomsg.rtattrs.push(RtAttr::new(None, Ifla::Address, mac)?);
+        }
+        Ok(ifinfomsg)
+    }
+}
+
+/// Apply `changes` to the link with index `ifindex` with `RTM_NEWLINK` and wait for the
+/// acknowledgement - the error number of errors from the kernel is returned by
+/// `NlError::errno`
+pub fn link_set(sock: &mut NlSocket<Rtm, Ifinfomsg>, ifindex: libc::c_int, changes: LinkChanges)
+        -> Result<(), NlError> {
+    let ifinfomsg = changes.into_ifinfomsg(ifindex)?;
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Newlink, vec![NlmF::Request, NlmF::Ack], None, None,
+                               ifinfomsg))?;
+    sock.recv_ack(None)?;
+    Ok(())
+}
+
+/// Kind of virtual link to create with `link_add`
+#[derive(Clone,Debug,PartialEq)]
+pub enum LinkKind {
+    /// Dummy interface dropping all traffic
+    Dummy,
+    /// Ethernet bridge
+    Bridge,
+    /// VLAN interface on top of another interface
+    Vlan {
+        /// Index of the parent interface
+        parent: libc::c_int,
+        /// VLAN ID
+        id: u16,
+    },
+    /// Pair of connected virtual ethernet interfaces
+    Veth {
+        /// Name of the peer interface
+        peer_name: String,
+    },
+}
+
+/// Description of a virtual link to create with `link_add`
+#[derive(Clone,Debug,PartialEq)]
+pub struct LinkSpec {
+    /// Name of the new interface
+    pub name: String,
+    /// Kind of the new interface
+    pub kind: LinkKind,
+}
+
+impl LinkSpec {
+    /// Describe a dummy interface
+    pub fn dummy(name: &str) -> Self {
+        LinkSpec { name: name.to_string(), kind: LinkKind::Dummy }
+    }
+
+    /// Describe a bridge
+    pub fn bridge(name: &str) -> Self {
+        LinkSpec { name: name.to_string(), kind: LinkKind::Bridge }
+    }
+
+    /// Describe a VLAN interface with the given ID on top of the interface with index
+    /// `parent`
+    pub fn vlan(name: &str, parent: libc::c_int, id: u16) -> Self {
+        LinkSpec { name: name.to_string(), kind: LinkKind::Vlan { parent, id } }
+    }
+
+    /// Describe a veth pair
+    pub fn veth(name: &str, peer_name: &str) -> Self {
+        LinkSpec {
+            name: name.to_string(),
+            kind: LinkKind::Veth { peer_name: peer_name.to_string() },
+        }
+    }
+
+    /// Build the interface info message creating this link
+    ///
+    /// The peer of a veth pair is described by a complete `Ifinfomsg` nested in
+    /// `VethInfo::Peer` inside `IflaInfo::Data`.
+    pub fn into_ifinfomsg(self) -> Result<Ifinfomsg, SerError> {
+        let mut rtattrs = vec![RtAttr::new(None, Ifla::Ifname, self.name.as_str())?];
+        match self.kind {
+            LinkKind::Dummy => rtattrs.push(RtAttr::new_linkinfo("dummy")?),
+            LinkKind::Bridge => rtattrs.push(RtAttr::new_linkinfo("bridge")?),
+            LinkKind::Vlan { parent, id } => {
+                rtattrs.push(RtAttr::new(None, Ifla::Link, parent)?);
+                rtattrs.push(RtAttr::new_linkinfo_with_data("vlan", vec![
+                    RtAttr::new(None, IflaVlan::Id, id)?,
+                ])?);
+            },
+            LinkKind::Veth { peer_name } => {
+                let peer = Ifinfomsg {
+                    rtattrs: vec![RtAttr::new(None, Ifla::Ifname, peer_name.as_str())?],
+                    ..Ifinfomsg::dump(Af::Unspec)
+                };
+                rtattrs.push(RtAttr::new_linkinfo_with_data("veth", vec![
+                    RtAttr::new(None, VethInfo::Peer, peer)?,
+                ])?);
             },
-            ifi_change: 0xffffffff,
+        }
+        Ok(Ifinfomsg { rtattrs, ..Ifinfomsg::dump(Af::Unspec) })
+    }
+}
+
+/// Link identified either by index or by name
+#[derive(Clone,Debug,PartialEq)]
+pub enum LinkId {
+    /// Interface index
+    Index(libc::c_int),
+    /// Interface name
+    Name(String),
+}
+
+impl From<libc::c_int> for LinkId {
+    fn from(v: libc::c_int) -> Self {
+        LinkId::Index(v)
+    }
+}
+
+impl<'a> From<&'a str> for LinkId {
+    fn from(v: &'a str) -> Self {
+        LinkId::Name(v.to_string())
+    }
+}
+
+/// Create a virtual link with `RTM_NEWLINK` and wait for the acknowledgement - creating a
+/// link with the name of an existing link fails with an error whose `NlError::errno` is
+/// `libc::EEXIST`
+pub fn link_add(sock: &mut NlSocket<Rtm, Ifinfomsg>, spec: LinkSpec) -> Result<(), NlError> {
+    let ifinfomsg = spec.into_ifinfomsg()?;
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Newlink,
+                               vec![NlmF::Request, NlmF::Create, NlmF::Excl, NlmF::Ack], None,
+                               None, ifinfomsg))?;
+    sock.recv_ack(None)?;
+    Ok(())
+}
+
+/// Delete a link by index or name with `RTM_DELLINK` and wait for the acknowledgement -
+/// deleting one interface of a veth pair also deletes its peer
+pub fn link_del<L>(sock: &mut NlSocket<Rtm, Ifinfomsg>, link: L) -> Result<(), NlError>
+        where L: Into<LinkId> {
+    let ifinfomsg = match link.into() {
+        LinkId::Index(ifi_index) => Ifinfomsg { ifi_index, ..Ifinfomsg::dump(Af::Unspec) },
+        LinkId::Name(name) => Ifinfomsg {
+            rtattrs: vec![RtAttr::new(None, Ifla::Ifname, name.as_str())?],
+            ..Ifinfomsg::dump(Af::Unspec)
+        },
+    };
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Dellink, vec![NlmF::Request, NlmF::Ack], None, None,
+                               ifinfomsg))?;
+    sock.recv_ack(None)?;
+    Ok(())
+}
+
+// Define a link statistics struct with every field of the given integer type in the order of
+// `struct rtnl_link_stats`
+macro_rules! link_stats {
+    ( $(#[$outer:meta])* $name:ident, $ty:ty, $( $(#[$doc:meta])* $field:ident ),* ) => {
+        $(#[$outer])*
+        #[derive(Clone,Debug,Default,PartialEq)]
+        pub struct $name {
+            $(
+                $(#[$doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        /// Newer kernels append fields to the statistics structs so any bytes following the
+        /// known fields are ignored on deserialization
+        impl Nl for $name {
+            type SerIn = ();
+            type DeIn = ();
+
+            fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+                $( self.$field.serialize(buf)?; )*
+                Ok(())
+            }
+
+            fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError>
+                    where B: AsRef<[u8]> {
+                let stats = $name { $( $field: <$ty>::deserialize(buf)?, )* };
+                let mut trailing = Vec::new();
+                buf.read_to_end(&mut trailing)?;
+                Ok(stats)
+            }
+
+            fn size(&self) -> usize {
+                0 $( + self.$field.size() )*
+            }
+        }
+    };
+}
+
+link_stats!(
+    /// Interface statistics from the `Ifla::Stats` attribute (`struct rtnl_link_stats`)
+    LinkStats, u32,
+    /// Total packets received
+    rx_packets,
+    /// Total packets transmitted
+    tx_packets,
+    /// Total bytes received
+    rx_bytes,
+    /// Total bytes transmitted
+    tx_bytes,
+    /// Bad packets received
+    rx_errors,
+    /// Packet transmit problems
+    tx_errors,
+    /// Packets dropped on receive, for example because of no buffer space
+    rx_dropped,
+    /// Packets dropped on transmit, for example because of no buffer space
+    tx_dropped,
+    /// Multicast packets received
+    multicast,
+    /// Collisions while transmitting
+    collisions,
+    /// Received packets with a bad length
+    rx_length_errors,
+    /// Receiver ring buffer overflows
+    rx_over_errors,
+    /// Received packets with a CRC error
+    rx_crc_errors,
+    /// Received frame alignment errors
+    rx_frame_errors,
+    /// Receiver FIFO overruns
+    rx_fifo_errors,
+    /// Packets missed by the receiver
+    rx_missed_errors,
+    /// Aborted transmissions
+    tx_aborted_errors,
+    /// Transmissions with carrier errors
+    tx_carrier_errors,
+    /// Transmitter FIFO underruns
+    tx_fifo_errors,
+    /// Transmissions with heartbeat errors
+    tx_heartbeat_errors,
+    /// Transmissions with late collisions
+    tx_window_errors,
+    /// Compressed packets received
+    rx_compressed,
+    /// Compressed packets transmitted
+    tx_compressed
+);
+
+link_stats!(
+    /// Interface statistics from the `Ifla::Stats64` attribute (`struct rtnl_link_stats64`)
+    LinkStats64, u64,
+    /// Total packets received
+    rx_packets,
+    /// Total packets transmitted
+    tx_packets,
+    /// Total bytes received
+    rx_bytes,
+    /// Total bytes transmitted
+    tx_bytes,
+    /// Bad packets received
+    rx_errors,
+    /// Packet transmit problems
+    tx_errors,
+    /// Packets dropped on receive, for example because of no buffer space
+    rx_dropped,
+    /// Packets dropped on transmit, for example because of no buffer space
+    tx_dropped,
+    /// Multicast packets received
+    multicast,
+    /// Collisions while transmitting
+    collisions,
+    /// Received packets with a bad length
+    rx_length_errors,
+    /// Receiver ring buffer overflows
+    rx_over_errors,
+    /// Received packets with a CRC error
+    rx_crc_errors,
+    /// Received frame alignment errors
+    rx_frame_errors,
+    /// Receiver FIFO overruns
+    rx_fifo_errors,
+    /// Packets missed by the receiver
+    rx_missed_errors,
+    /// Aborted transmissions
+    tx_aborted_errors,
+    /// Transmissions with carrier errors
+    tx_carrier_errors,
+    /// Transmitter FIFO underruns
+    tx_fifo_errors,
+    /// Transmissions with heartbeat errors
+    tx_heartbeat_errors,
+    /// Transmissions with late collisions
+    tx_window_errors,
+    /// Compressed packets received
+    rx_compressed,
+    /// Compressed packets transmitted
+    tx_compressed
+);
+
+/// VLAN flags to change from the `IflaVlan::Flags` attribute (`struct ifla_vlan_flags`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct IflaVlanFlags {
+    /// Values of the flags set in `mask`
+    pub flags: NlFlags<VlanFlag>,
+    /// Flags that should be changed
+    pub mask: NlFlags<VlanFlag>,
+}
+
+impl Nl for IflaVlanFlags {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.flags.serialize(buf)?;
+        self.mask.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(IflaVlanFlags {
+            flags: NlFlags::deserialize(buf)?,
+            mask: NlFlags::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.flags.size() + self.mask.size()
+    }
+}
+
+/// Mapping between priorities and VLAN QoS values from the `IflaVlanQos::Mapping` attribute
+/// (`struct ifla_vlan_qos_mapping`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct IflaVlanQosMapping {
+    /// Priority or QoS value mapped from
+    pub from: u32,
+    /// Priority or QoS value mapped to
+    pub to: u32,
+}
+
+impl Nl for IflaVlanQosMapping {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.from.serialize(buf)?;
+        self.to.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(IflaVlanQosMapping {
+            from: u32::deserialize(buf)?,
+            to: u32::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.from.size() + self.to.size()
+    }
+}
+
+// Lifetime value in `struct ifa_cacheinfo` meaning that the address never expires
+const INFINITY_LIFE_TIME: u32 = 0xffffffff;
+
+/// Address lifetimes and timestamps from the `Ifa::Cacheinfo` attribute
+/// (`struct ifa_cacheinfo`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct IfaCacheinfo {
+    /// Remaining preferred lifetime in seconds
+    pub ifa_prefered: u32,
+    /// Remaining valid lifetime in seconds
+    pub ifa_valid: u32,
+    /// Creation timestamp in hundredths of seconds since boot
+    pub cstamp: u32,
+    /// Last update timestamp in hundredths of seconds since boot
+    pub tstamp: u32,
+}
+
+impl IfaCacheinfo {
+    /// Returns `true` if the address never stops being preferred
+    pub fn is_prefered_forever(&self) -> bool {
+        self.ifa_prefered == INFINITY_LIFE_TIME
+    }
+
+    /// Returns `true` if the address never expires
+    pub fn is_valid_forever(&self) -> bool {
+        self.ifa_valid == INFINITY_LIFE_TIME
+    }
+}
+
+impl Nl for IfaCacheinfo {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.ifa_prefered.serialize(buf)?;
+        self.ifa_valid.serialize(buf)?;
+        self.cstamp.serialize(buf)?;
+        self.tstamp.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(IfaCacheinfo {
+            ifa_prefered: u32::deserialize(buf)?,
+            ifa_valid: u32::deserialize(buf)?,
+            cstamp: u32::deserialize(buf)?,
+            tstamp: u32::deserialize(buf)?,
         })
     }
 
     fn size(&self) -> usize {
-        self.ifi_family.size() + self.ifi_type.size() + self.ifi_index.size() + mem::size_of::<libc::c_uint>()
+        self.ifa_prefered.size() + self.ifa_valid.size() + self.cstamp.size() + self.tstamp.size()
     }
 }
 
 /// Struct representing interface address messages
+#[derive(Debug,PartialEq)]
 pub struct Ifaddrmsg {
     /// Interface address family
     pub ifa_family: Af,
     /// Interface address prefix length
     pub ifa_prefixlen: libc::c_uchar,
-    /// Interface address flags
-    pub ifa_flags: Vec<IfaF>,
+    /// Interface address flags - when an `Ifa::Flags` attribute is present, deserialization
+    /// uses its full 32 bit value instead of the truncated header field
+    pub ifa_flags: NlFlags<IfaF>,
     /// Interface address scope
-    pub ifa_scope: libc::c_uchar,
+    pub ifa_scope: RtScope,
     /// Interface address index
     pub ifa_index: libc::c_int,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Ifa>>,
+}
+
+impl Ifaddrmsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 4
+        + mem::size_of::<libc::c_int>();
+
+    /// Create an interface address struct for a dump request (`RTM_GETADDR` with
+    /// `NlmF::Dump`) where every field except the family is zeroed
+    pub fn dump(ifa_family: Af) -> Self {
+        Ifaddrmsg {
+            ifa_family,
+            ifa_prefixlen: 0,
+            ifa_flags: NlFlags::empty(),
+            ifa_scope: RtScope::Universe,
+            ifa_index: 0,
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the address contained in the given attribute (usually `Ifa::Address` or
+    /// `Ifa::Local`) parsed according to `ifa_family`
+    pub fn get_addr(&self, rta_type: Ifa) -> Result<Option<IpAddr>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == rta_type) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<IpAddr>(Some(self.ifa_family))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the address lifetimes from the `Ifa::Cacheinfo` attribute
+    pub fn get_cacheinfo(&self) -> Result<Option<IfaCacheinfo>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ifa::Cacheinfo) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<IfaCacheinfo>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Ifa> {
+        rtattr_handle(&self.rtattrs)
+    }
 }
 
 impl Nl for Ifaddrmsg {
@@ -89,52 +783,295 @@ impl Nl for Ifaddrmsg {
     fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.ifa_family.serialize(buf)?;
         self.ifa_prefixlen.serialize(buf)?;
-        self.ifa_flags.iter().fold(0, |acc: libc::c_uchar, next| {
-            let next_uint: u32 = next.into();
-            acc | next_uint as libc::c_uchar
-        }).serialize(buf)?;
+        (self.ifa_flags.bits() as libc::c_uchar).serialize(buf)?;
         self.ifa_scope.serialize(buf)?;
         self.ifa_index.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
         Ok(())
     }
 
     fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let ifa_family = Af::deserialize(buf)?;
+        let ifa_prefixlen = libc::c_uchar::deserialize(buf)?;
+        let header_flags = libc::c_uchar::deserialize(buf)?;
+        let ifa_scope = RtScope::deserialize(buf)?;
+        let ifa_index = libc::c_int::deserialize(buf)?;
+        let rtattrs = Vec::<RtAttr<Ifa>>::deserialize(buf)?;
+        let flags = match rtattrs.iter().find(|rta| rta.rta_type == Ifa::Flags) {
+            Some(rta) => rta.get_payload_with::<u32>(None)?,
+            None => u32::from(header_flags),
+        };
         Ok(Ifaddrmsg {
-            ifa_family: Af::deserialize(buf)?,
-            ifa_prefixlen: libc::c_uchar::deserialize(buf)?,
-            ifa_flags: {
-                let flags = libc::c_uchar::deserialize(buf)?;
-                let mut nl_flags = Vec::new();
-                for i in 0..mem::size_of::<libc::c_uchar>() * 8 {
-                    let bit = 1 << i;
-                    if bit & flags == bit {
-                        nl_flags.push((bit as u32).into());
-                    }
-                }
-                nl_flags
-            },
-            ifa_scope: libc::c_uchar::deserialize(buf)?,
-            ifa_index: libc::c_int::deserialize(buf)?,
+            ifa_family,
+            ifa_prefixlen,
+            ifa_flags: NlFlags::from_bits(flags),
+            ifa_scope,
+            ifa_index,
+            rtattrs,
         })
     }
 
     fn size(&self) -> usize {
         self.ifa_family.size() + self.ifa_prefixlen.size() + mem::size_of::<libc::c_uchar>()
-            + self.ifa_scope.size() + self.ifa_index.size()
+            + self.ifa_scope.size() + self.ifa_index.size() + self.rtattrs.size()
+    }
+}
+
+/// Summary of an interface address as reported by `RTM_GETADDR` - see `get_addrs`
+///
+/// On broadcast interfaces `Ifa::Local` and `Ifa::Address` hold the same address. On
+/// point-to-point interfaces `Ifa::Local` is the local address and `Ifa::Address` is the
+/// address of the peer.
+#[derive(Debug,PartialEq)]
+pub struct Addr {
+    /// Address family
+    pub family: Af,
+    /// Index of the interface the address is assigned to
+    pub index: libc::c_int,
+    /// Prefix length of the address
+    pub prefix_len: libc::c_uchar,
+    /// Scope of the address
+    pub scope: RtScope,
+    /// Address flags
+    pub flags: NlFlags<IfaF>,
+    /// The address of the interface - `local` if present and `address` otherwise
+    pub addr: IpAddr,
+    /// Address from `Ifa::Local`
+    pub local: Option<IpAddr>,
+    /// Address from `Ifa::Address`
+    pub address: Option<IpAddr>,
+    /// All attributes of the address for anything not covered by the fields above
+    pub rtattrs: Vec<RtAttr<Ifa>>,
+}
+
+impl Addr {
+    /// Extract the address information from an interface address message returned by the
+    /// kernel
+    pub fn from_ifaddrmsg(ifaddrmsg: Ifaddrmsg) -> Result<Self, DeError> {
+        let local = ifaddrmsg.get_addr(Ifa::Local)?;
+        let address = ifaddrmsg.get_addr(Ifa::Address)?;
+        let addr = local.or(address).ok_or_else(|| {
+            DeError::new("Address is missing both the Ifa::Local and Ifa::Address attributes")
+        })?;
+        Ok(Addr {
+            family: ifaddrmsg.ifa_family,
+            index: ifaddrmsg.ifa_index,
+            prefix_len: ifaddrmsg.ifa_prefixlen,
+            scope: ifaddrmsg.ifa_scope,
+            flags: ifaddrmsg.ifa_flags,
+            addr,
+            local,
+            address,
+            rtattrs: ifaddrmsg.rtattrs,
+        })
+    }
+
+    /// Get the address of the peer on a point-to-point interface
+    pub fn peer(&self) -> Option<IpAddr> {
+        match (self.local, self.address) {
+            (Some(local), Some(address)) if local != address => Some(address),
+            _ => None,
+        }
+    }
+}
+
+/// Enumerate the addresses of all interfaces by sending an `RTM_GETADDR` dump request
+///
+/// When `family` is given the kernel only dumps addresses of that family. Messages of other
+/// families are also skipped in case the kernel does not filter the dump.
+pub fn get_addrs(sock: &mut NlSocket<Rtm, Ifaddrmsg>, family: Option<Af>)
+        -> Result<Vec<Addr>, NlError> {
+    let request = Ifaddrmsg::dump(family.unwrap_or(Af::Unspec));
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Getaddr, vec![NlmF::Request, NlmF::Dump], None, None,
+                               request))?;
+    let mut addrs = Vec::new();
+    for msg in sock.iter() {
+        let ifaddrmsg = msg?.nl_payload;
+        if family.is_none() || family.as_ref() == Some(&ifaddrmsg.ifa_family) {
+            addrs.push(Addr::from_ifaddrmsg(ifaddrmsg)?);
+        }
+    }
+    Ok(addrs)
+}
+
+/// IP address with a prefix length as assigned to an interface
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct IpNet {
+    /// The address
+    pub addr: IpAddr,
+    /// Prefix length of the network the address belongs to
+    pub prefix_len: u8,
+}
+
+impl IpNet {
+    /// Create an address with a prefix length, failing if the prefix length is too long for
+    /// the address family
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, SerError> {
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_len {
+            return Err(SerError::new("Prefix length is too long for the address family"));
+        }
+        Ok(IpNet { addr, prefix_len })
+    }
+
+    /// Get the broadcast address of an IPv4 network - networks with a prefix length of 31 or
+    /// 32 and IPv6 networks have none
+    pub fn broadcast(&self) -> Option<IpAddr> {
+        match self.addr {
+            IpAddr::V4(a) if self.prefix_len < 31 => {
+                let host_mask = !0u32 >> self.prefix_len;
+                Some(IpAddr::from(Ipv4Addr::from(u32::from(a) | host_mask)))
+            },
+            _ => None,
+        }
+    }
+
+    fn family(&self) -> Af {
+        match self.addr {
+            IpAddr::V4(_) => Af::Inet,
+            IpAddr::V6(_) => Af::Inet6,
+        }
+    }
+
+    // IPv4 uses `Ifa::Local` as the address of the interface while IPv6 uses `Ifa::Address`
+    fn addr_attrs(&self) -> Result<Vec<RtAttr<Ifa>>, SerError> {
+        Ok(match self.addr {
+            IpAddr::V4(_) => vec![
+                RtAttr::new(None, Ifa::Local, self.addr)?,
+                RtAttr::new(None, Ifa::Address, self.addr)?,
+            ],
+            IpAddr::V6(_) => vec![RtAttr::new(None, Ifa::Address, self.addr)?],
+        })
+    }
+}
+
+/// Assign an address to the interface with index `ifindex` with `RTM_NEWADDR` and wait for
+/// the acknowledgement
+///
+/// An address that is already assigned fails with an error whose `NlError::errno` is
+/// `libc::EEXIST`.
+pub fn addr_add<F>(sock: &mut NlSocket<Rtm, Ifaddrmsg>, ifindex: libc::c_int, net: IpNet,
+                   flags: F) -> Result<(), NlError> where F: Into<NlFlags<IfaF>> {
+    let flags = flags.into();
+    let mut rtattrs = net.addr_attrs()?;
+    if let Some(brd) = net.broadcast() {
+        rtattrs.push(RtAttr::new(None, Ifa::Broadcast, brd)?);
+    }
+    if flags.bits() > u32::from(libc::c_uchar::max_value()) {
+        rtattrs.push(RtAttr::new(None, Ifa::Flags, flags.bits())?);
+    }
+    let ifa_scope = match net.addr {
+        IpAddr::V4(a) if a.is_loopback() => RtScope::Host,
+        IpAddr::V6(a) if a.is_loopback() => RtScope::Host,
+        _ => RtScope::Universe,
+    };
+    let ifaddrmsg = Ifaddrmsg {
+        ifa_family: net.family(),
+        ifa_prefixlen: net.prefix_len,
+        ifa_flags: flags,
+        ifa_scope,
+        ifa_index: ifindex,
+        rtattrs,
+    };
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Newaddr,
+                               vec![NlmF::Request, NlmF::Create, NlmF::Excl, NlmF::Ack], None,
+                               None, ifaddrmsg))?;
+    sock.recv_ack(None)?;
+    Ok(())
+}
+
+/// Remove an address from the interface with index `ifindex` with `RTM_DELADDR` and wait for
+/// the acknowledgement
+///
+/// An address that is not assigned fails with an error whose `NlError::errno` is
+/// `libc::EADDRNOTAVAIL`.
+pub fn addr_del(sock: &mut NlSocket<Rtm, Ifaddrmsg>, ifindex: libc::c_int, net: IpNet)
+        -> Result<(), NlError> {
+    let ifaddrmsg = Ifaddrmsg {
+        ifa_prefixlen: net.prefix_len,
+        ifa_index: ifindex,
+        rtattrs: net.addr_attrs()?,
+        ..Ifaddrmsg::dump(net.family())
+    };
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Deladdr, vec![NlmF::Request, NlmF::Ack], None, None,
+                               ifaddrmsg))?;
+    sock.recv_ack(None)?;
+    Ok(())
+}
+
+/// Route cache statistics from the `Rta::Cacheinfo` attribute (`struct rta_cacheinfo`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct RtaCacheinfo {
+    /// Reference count of the route
+    pub rta_clntref: u32,
+    /// Time since the route was last used in jiffies
+    pub rta_lastuse: u32,
+    /// Time until the route expires in jiffies - 0 if it does not expire
+    pub rta_expires: i32,
+    /// Error of the route
+    pub rta_error: u32,
+    /// Number of times the route was used
+    pub rta_used: u32,
+    /// IP ID
+    pub rta_id: u32,
+    /// TCP timestamp
+    pub rta_ts: u32,
+    /// Age of the TCP timestamp in seconds
+    pub rta_tsage: u32,
+}
+
+impl Nl for RtaCacheinfo {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.rta_clntref.serialize(buf)?;
+        self.rta_lastuse.serialize(buf)?;
+        self.rta_expires.serialize(buf)?;
+        self.rta_error.serialize(buf)?;
+        self.rta_used.serialize(buf)?;
+        self.rta_id.serialize(buf)?;
+        self.rta_ts.serialize(buf)?;
+        self.rta_tsage.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(RtaCacheinfo {
+            rta_clntref: u32::deserialize(buf)?,
+            rta_lastuse: u32::deserialize(buf)?,
+            rta_expires: i32::deserialize(buf)?,
+            rta_error: u32::deserialize(buf)?,
+            rta_used: u32::deserialize(buf)?,
+            rta_id: u32::deserialize(buf)?,
+            rta_ts: u32::deserialize(buf)?,
+            rta_tsage: u32::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.rta_clntref.size() + self.rta_lastuse.size() + self.rta_expires.size()
+            + self.rta_error.size() + self.rta_used.size() + self.rta_id.size()
+            + self.rta_ts.size() + self.rta_tsage.size()
     }
 }
 
 /// Route message
+#[derive(Debug,PartialEq)]
 pub struct Rtmsg {
     /// Address family of route
-    pub rtm_family: libc::c_uchar,
+    pub rtm_family: Af,
     /// Length of destination
     pub rtm_dst_len: libc::c_uchar,
     /// Length of source
     pub rtm_src_len: libc::c_uchar,
     /// TOS filter
     pub rtm_tos: libc::c_uchar,
-    /// Routing table ID
+    /// Routing table ID - tables with IDs above 255 are only identified by the `Rta::Table`
+    /// attribute
     pub rtm_table: RtTable,
     /// Routing protocol
     pub rtm_protocol: Rtprot,
@@ -143,9 +1080,198 @@ pub struct Rtmsg {
     /// Routing type
     pub rtm_type: Rtn,
     /// Routing flags
-    pub rtm_flags: Vec<RtmF>,
+    pub rtm_flags: NlFlags<RtmF>,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Rta>>,
+}
+
+impl Rtmsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 8
+        + mem::size_of::<libc::c_uint>();
+
+    /// Create a route message for a dump request (`RTM_GETROUTE` with `NlmF::Dump`) of every
+    /// routing table where every field except the family is zeroed
+    pub fn dump(rtm_family: Af) -> Self {
+        Rtmsg {
+            rtm_family,
+            rtm_dst_len: 0,
+            rtm_src_len: 0,
+            rtm_tos: 0,
+            rtm_table: RtTable::Unspec,
+            rtm_protocol: Rtprot::Unspec,
+            rtm_scope: RtScope::Universe,
+            rtm_type: Rtn::Unspec,
+            rtm_flags: NlFlags::empty(),
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the address contained in the given attribute (usually `Rta::Dst`, `Rta::Gateway`
+    /// or `Rta::Prefsrc`) parsed according to `rtm_family`
+    pub fn get_addr(&self, rta_type: Rta) -> Result<Option<IpAddr>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == rta_type) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<IpAddr>(Some(self.rtm_family))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the next hops of a multipath route from the `Rta::Multipath` attribute
+    pub fn get_multipath(&self) -> Result<Option<Vec<Rtnexthop>>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Rta::Multipath) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<Vec<Rtnexthop>>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the route cache statistics from the `Rta::Cacheinfo` attribute
+    pub fn get_cacheinfo(&self) -> Result<Option<RtaCacheinfo>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Rta::Cacheinfo) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<RtaCacheinfo>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the route metrics nested in the `Rta::Metrics` attribute
+    pub fn get_metrics(&self) -> Result<Option<Vec<RtAttr<Rtax>>>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Rta::Metrics) {
+            Some(rta) => Ok(Some(rta.get_nested::<Rtax>()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the value of a single route metric such as `Rtax::Mtu` from the `Rta::Metrics`
+    /// attribute
+    pub fn get_metric(&self, rtax: Rtax) -> Result<Option<u32>, DeError> {
+        match self.get_metrics()? {
+            Some(metrics) => match metrics.iter().find(|rta| rta.rta_type == rtax) {
+                Some(rta) => Ok(Some(rta.get_payload_with::<u32>(None)?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Get the full routing table ID from the `Rta::Table` attribute, falling back to
+    /// `rtm_table` when it is not present
+    pub fn get_table(&self) -> Result<u32, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Rta::Table) {
+            Some(rta) => rta.get_payload_with::<u32>(None),
+            None => Ok(u32::from(libc::c_uchar::from(&self.rtm_table))),
+        }
+    }
+
+    /// Set the routing table ID - IDs above 255 are carried in an `Rta::Table` attribute with
+    /// `rtm_table` set to `RtTable::Unspec` like `ip route` does. The reserved IDs are the
+    /// values of `RtTable`.
+    pub fn set_table(&mut self, table: u32) -> Result<(), SerError> {
+        self.rtattrs.retain(|rta| rta.rta_type != Rta::Table);
+        if table <= u32::from(libc::c_uchar::max_value()) {
+            self.rtm_table = RtTable::from(table as libc::c_uchar);
+        } else {
+            self.rtm_table = RtTable::Unspec;
+            self.rtattrs.push(RtAttr::new(None, Rta::Table, table)?);
+        }
+        Ok(())
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Rta> {
+        rtattr_handle(&self.rtattrs)
+    }
+
+    fn get_u32(&self, rta_type: Rta) -> Result<Option<u32>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == rta_type) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<u32>(None)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Summary of a route as reported by `RTM_GETROUTE` - see `get_routes` and `route_get`
+#[derive(Debug,PartialEq)]
+pub struct Route {
+    /// Address family
+    pub family: Af,
+    /// Destination prefix from `Rta::Dst` - `None` for the default route
+    pub dst: Option<IpAddr>,
+    /// Length of the destination prefix
+    pub dst_len: libc::c_uchar,
+    /// Gateway from `Rta::Gateway`
+    pub gateway: Option<IpAddr>,
+    /// Preferred source address from `Rta::Prefsrc`
+    pub prefsrc: Option<IpAddr>,
+    /// Index of the output interface from `Rta::Oif`
+    pub oif: Option<u32>,
+    /// Priority (metric) from `Rta::Priority`
+    pub priority: Option<u32>,
+    /// Full routing table ID
+    pub table: u32,
+    /// Routing protocol that installed the route
+    pub protocol: Rtprot,
+    /// Scope of the route
+    pub scope: RtScope,
+    /// Type of the route
+    pub route_type: Rtn,
+    /// All attributes of the route for anything not covered by the fields above
+    pub rtattrs: Vec<RtAttr<Rta>>,
 }
 
+impl Route {
+    /// Extract the route information from a route message returned by the kernel
+    pub fn from_rtmsg(rtmsg: Rtmsg) -> Result<Self, DeError> {
+        Ok(Route {
+            dst: rtmsg.get_addr(Rta::Dst)?,
+            gateway: rtmsg.get_addr(Rta::Gateway)?,
+            prefsrc: rtmsg.get_addr(Rta::Prefsrc)?,
+            oif: rtmsg.get_u32(Rta::Oif)?,
+            priority: rtmsg.get_u32(Rta::Priority)?,
+            table: rtmsg.get_table()?,
+            family: rtmsg.rtm_family,
+            dst_len: rtmsg.rtm_dst_len,
+            protocol: rtmsg.rtm_protocol,
+            scope: rtmsg.rtm_scope,
+            route_type: rtmsg.rtm_type,
+            rtattrs: rtmsg.rtattrs,
+        })
+    }
+}
+
+/// Dump the routes of all routing tables for the given family with `RTM_GETROUTE`
+pub fn get_routes(sock: &mut NlSocket<Rtm, Rtmsg>, family: Af) -> Result<Vec<Route>, NlError> {
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Getroute, vec![NlmF::Request, NlmF::Dump], None, None,
+                               Rtmsg::dump(family)))?;
+    let mut routes = Vec::new();
+    for msg in sock.iter() {
+        routes.push(Route::from_rtmsg(msg?.nl_payload)?);
+    }
+    Ok(routes)
+}
+
+/// Look up the route that the kernel would use to reach `dst` like `ip route get` - the
+/// reported table is the table the route was found in
+pub fn route_get(sock: &mut NlSocket<Rtm, Rtmsg>, dst: IpAddr) -> Result<Route, NlError> {
+    let (family, dst_len) = match dst {
+        IpAddr::V4(_) => (Af::Inet, 32),
+        IpAddr::V6(_) => (Af::Inet6, 128),
+    };
+    let rtmsg = Rtmsg {
+        rtm_dst_len: dst_len,
+        rtm_flags: NlFlags::from(RtmF::LookupTable),
+        rtattrs: vec![RtAttr::new(None, Rta::Dst, dst)?],
+        ..Rtmsg::dump(family)
+    };
+    sock.send_nl(Nlmsghdr::new(None, Rtm::Getroute, vec![NlmF::Request], None, None, rtmsg))?;
+    let msg = match sock.iter().next() {
+        Some(msg) => msg?,
+        None => return Err(NlError::new("No response received for route lookup")),
+    };
+    msg.expect_type(Rtm::Newroute)?;
+    Ok(Route::from_rtmsg(msg.nl_payload)?)
+}
+
+/// Attributes are read until the end of the buffer so the buffer passed to `deserialize` must
+/// be bounded by `nl_len` of the enclosing message as `NlSocket::iter` does
 impl Nl for Rtmsg {
     type SerIn = ();
     type DeIn = ();
@@ -159,40 +1285,826 @@ impl Nl for Rtmsg {
         self.rtm_protocol.serialize(buf)?;
         self.rtm_scope.serialize(buf)?;
         self.rtm_type.serialize(buf)?;
-        self.rtm_flags.iter().fold(0, |acc: libc::c_uint, next| {
-            let next_uint: libc::c_uint = next.into();
-            acc | next_uint
-        }).serialize(buf)?;
+        self.rtm_flags.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
         Ok(())
     }
 
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(Rtmsg {
+            rtm_family: Af::deserialize(buf)?,
+            rtm_dst_len: libc::c_uchar::deserialize(buf)?,
+            rtm_src_len: libc::c_uchar::deserialize(buf)?,
+            rtm_tos: libc::c_uchar::deserialize(buf)?,
+            rtm_table: RtTable::deserialize(buf)?,
+            rtm_protocol: Rtprot::deserialize(buf)?,
+            rtm_scope: RtScope::deserialize(buf)?,
+            rtm_type: Rtn::deserialize(buf)?,
+            rtm_flags: NlFlags::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<Rta>>::deserialize(buf)?,
+        })
+    }
+
     fn size(&self) -> usize {
         self.rtm_family.size() + self.rtm_dst_len.size() + self.rtm_src_len.size()
             + self.rtm_tos.size() + self.rtm_table.size() + self.rtm_protocol.size()
-            + self.rtm_scope.size() + self.rtm_type.size() + mem::size_of::<libc::c_uint>()
+            + self.rtm_scope.size() + self.rtm_type.size() + self.rtm_flags.size()
+            + self.rtattrs.size()
     }
 }
 
-/// Represents an ARP (neighbor table) entry
-pub struct Ndmsg {
-    /// Address family of entry
+/// Next hop of a multipath route in the `Rta::Multipath` attribute (`struct rtnexthop`)
+/// followed by the attributes of this hop such as `Rta::Gateway`
+#[derive(Debug,PartialEq)]
+pub struct Rtnexthop {
+    /// Length of the next hop including its attributes
+    pub rtnh_len: libc::c_ushort,
+    /// Next hop flags
+    pub rtnh_flags: NlFlags<RtnhF>,
+    /// Weight of the next hop minus one
+    pub rtnh_hops: libc::c_uchar,
+    /// Interface index of the next hop
+    pub rtnh_ifindex: libc::c_int,
+    /// Route netlink attributes of the next hop
+    pub rtattrs: Vec<RtAttr<Rta>>,
+}
+
+impl Rtnexthop {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_ushort>()
+        + mem::size_of::<libc::c_uchar>() * 2 + mem::size_of::<libc::c_int>();
+
+    /// Create a next hop for building a multipath route, computing `rtnh_len` from the
+    /// attributes
+    pub fn new(rtnh_flags: NlFlags<RtnhF>, rtnh_hops: libc::c_uchar, rtnh_ifindex: libc::c_int,
+               rtattrs: Vec<RtAttr<Rta>>) -> Self {
+        let mut rtnh = Rtnexthop { rtnh_len: 0, rtnh_flags, rtnh_hops, rtnh_ifindex, rtattrs };
+        rtnh.rtnh_len = rtnh.size() as libc::c_ushort;
+        rtnh
+    }
+
+    /// Get the address of the gateway of this hop from the `Rta::Gateway` attribute parsed
+    /// according to the family of the enclosing route
+    pub fn get_gateway(&self, family: Af) -> Result<Option<IpAddr>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Rta::Gateway) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<IpAddr>(Some(family))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Rta> {
+        rtattr_handle(&self.rtattrs)
+    }
+}
+
+impl Nl for Rtnexthop {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.rtnh_len.serialize(buf)?;
+        self.rtnh_flags.serialize(buf)?;
+        self.rtnh_hops.serialize(buf)?;
+        self.rtnh_ifindex.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let rtnh_len = libc::c_ushort::deserialize(buf)?;
+        if (rtnh_len as usize) < Rtnexthop::LEN {
+            return Err(DeError::new("Next hop length is shorter than its header"));
+        }
+        let rtnh_flags = NlFlags::deserialize(buf)?;
+        let rtnh_hops = libc::c_uchar::deserialize(buf)?;
+        let rtnh_ifindex = libc::c_int::deserialize(buf)?;
+        let mut attrs = vec![0; rtnh_len as usize - Rtnexthop::LEN];
+        buf.read_exact(attrs.as_mut_slice())?;
+        let rtattrs = Vec::<RtAttr<Rta>>::deserialize(&mut StreamReadBuffer::new(attrs))?;
+        Ok(Rtnexthop { rtnh_len, rtnh_flags, rtnh_hops, rtnh_ifindex, rtattrs })
+    }
+
+    fn size(&self) -> usize {
+        self.rtnh_len.size() + self.rtnh_flags.size() + self.rtnh_hops.size()
+            + self.rtnh_ifindex.size() + self.rtattrs.size()
+    }
+}
+
+/// Next hop lists are walked by `rtnh_len` and fail if a length does not fit in the buffer
+impl Nl for Vec<Rtnexthop> {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        for rtnh in self.iter() {
+            rtnh.serialize(buf)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        buf.read_to_end(&mut bytes)?;
+        let mut pos = 0;
+        let mut hops = Vec::new();
+        while pos < bytes.len() {
+            if bytes.len() - pos < Rtnexthop::LEN {
+                return Err(DeError::new("Truncated next hop header"));
+            }
+            let rtnh_len = libc::c_ushort::deserialize(
+                &mut StreamReadBuffer::new(&bytes[pos..pos + 2])
+            )? as usize;
+            if rtnh_len < Rtnexthop::LEN || pos + rtnh_len > bytes.len() {
+                return Err(DeError::new("Next hop length does not match the attribute payload"));
+            }
+            hops.push(Rtnexthop::deserialize(
+                &mut StreamReadBuffer::new(&bytes[pos..pos + rtnh_len])
+            )?);
+            pos += checked_alignto(rtnh_len)?;
+        }
+        Ok(hops)
+    }
+
+    fn size(&self) -> usize {
+        self.iter().fold(0, |acc, rtnh| acc + rtnh.asize())
+    }
+}
+
+/// Link layer address of an Ethernet device such as the payload of `Nda::Lladdr` in
+/// bridge forwarding database entries
+#[derive(Clone,Copy,Debug,Default,Eq,Hash,PartialEq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(v: [u8; 6]) -> Self {
+        MacAddr(v)
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let o = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", o[0], o[1], o[2], o[3], o[4], o[5])
+    }
+}
+
+impl Nl for MacAddr {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        mem.write_all(&self.0)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut octets = [0u8; 6];
+        mem.read_exact(&mut octets)?;
+        Ok(MacAddr(octets))
+    }
+
+    fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Represents an ARP (neighbor table) entry
+#[derive(Debug,PartialEq)]
+pub struct Ndmsg {
+    /// Address family of entry
     pub ndm_family: Af,
     /// Index of entry
-    pub ndm_index: libc::c_int,
+    pub ndm_ifindex: libc::c_int,
     /// State of entry
-    pub ndm_state: Vec<Nud>,
+    pub ndm_state: NlFlags<Nud>,
     /// Flags for entry
-    pub ndm_flags: Vec<Ntf>,
+    pub ndm_flags: NlFlags<Ntf>,
     /// Type of entry
     pub ndm_type: Rtn,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Nda>>,
+}
+
+impl Ndmsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 2
+        + mem::size_of::<u16>() + mem::size_of::<libc::c_int>() + mem::size_of::<u16>()
+        + mem::size_of::<u8>() + mem::size_of::<libc::c_uchar>();
+
+    /// Create a neighbor table struct for a dump request (`RTM_GETNEIGH` with `NlmF::Dump`)
+    /// where every field except the family is zeroed
+    pub fn dump(ndm_family: Af) -> Self {
+        Ndmsg {
+            ndm_family,
+            ndm_ifindex: 0,
+            ndm_state: NlFlags::empty(),
+            ndm_flags: NlFlags::empty(),
+            ndm_type: Rtn::Unspec,
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the address contained in the `Nda::Dst` attribute parsed according to `ndm_family`
+    pub fn get_dst(&self) -> Result<Option<IpAddr>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Nda::Dst) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<IpAddr>(Some(self.ndm_family))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the link layer address contained in the `Nda::Lladdr` attribute
+    pub fn get_lladdr(&self) -> Option<&[u8]> {
+        self.rtattrs.iter().find(|rta| rta.rta_type == Nda::Lladdr)
+            .map(|rta| rta.rta_payload.as_slice())
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Nda> {
+        rtattr_handle(&self.rtattrs)
+    }
+}
+
+impl<'a> AttrHandle<'a, Nda> {
+    /// Get the link layer address contained in the `Nda::Lladdr` attribute from a parsed
+    /// handle - fails if the address is not 6 bytes long
+    pub fn get_mac_addr(&self) -> Result<Option<MacAddr>, DeError> {
+        match self.get_attr_payload(Nda::Lladdr)? {
+            Some(p) if p.len() == 6 => Ok(Some(MacAddr::from_bytes(p)?)),
+            Some(_) => Err(DeError::new("Link layer address payload must be 6 bytes")),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the address contained in the `Nda::Dst` attribute from a parsed handle
+    ///
+    /// The address family is taken from the length of the payload as the destination of
+    /// bridge forwarding database entries (`Af::Bridge`) is the IP address of a remote
+    /// tunnel endpoint.
+    pub fn get_dst_addr(&self) -> Result<Option<IpAddr>, DeError> {
+        match self.get_attr_payload(Nda::Dst)? {
+            Some(p) => {
+                let family = match p.len() {
+                    4 => Af::Inet,
+                    16 => Af::Inet6,
+                    _ => return Err(DeError::new("Destination payload must be 4 or 16 bytes")),
+                };
+                Ok(Some(IpAddr::deserialize_with(&mut StreamReadBuffer::new(p), family)?))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+impl Nl for Ndmsg {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.ndm_family.serialize(buf)?;
+        // Padding fields ndm_pad1 and ndm_pad2
+        0u8.serialize(buf)?;
+        0u16.serialize(buf)?;
+        self.ndm_ifindex.serialize(buf)?;
+        self.ndm_state.serialize(buf)?;
+        self.ndm_flags.serialize(buf)?;
+        self.ndm_type.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(Ndmsg {
+            ndm_family: Af::deserialize(buf)?,
+            ndm_ifindex: {
+                u8::deserialize(buf)?;
+                u16::deserialize(buf)?;
+                libc::c_int::deserialize(buf)?
+            },
+            ndm_state: NlFlags::deserialize(buf)?,
+            ndm_flags: NlFlags::deserialize(buf)?,
+            ndm_type: Rtn::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<Nda>>::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.ndm_family.size() + mem::size_of::<u8>() + mem::size_of::<u16>()
+            + self.ndm_ifindex.size() + self.ndm_state.size() + self.ndm_flags.size()
+            + self.ndm_type.size() + self.rtattrs.size()
+    }
+}
+
+/// Neighbor table parameters message (`struct ndtmsg`) used with `RTM_GETNEIGHTBL` and
+/// `RTM_SETNEIGHTBL`
+#[derive(Debug,PartialEq)]
+pub struct Ndtmsg {
+    /// Address family of the neighbor table
+    pub ndtm_family: Af,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Ndta>>,
+}
+
+impl Ndtmsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 2 + mem::size_of::<u16>();
+
+    /// Create a neighbor table message for a dump request (`RTM_GETNEIGHTBL` with
+    /// `NlmF::Dump`) of the tables of the given family
+    pub fn dump(ndtm_family: Af) -> Self {
+        Ndtmsg { ndtm_family, rtattrs: Vec::new() }
+    }
+
+    /// Get the name of the neighbor table such as `"arp_cache"` from the `Ndta::Name`
+    /// attribute
+    pub fn get_name(&self) -> Result<Option<String>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ndta::Name) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<String>(Some(rta.rta_payload.len()))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the table configuration from the `Ndta::Config` attribute
+    pub fn get_config(&self) -> Result<Option<NdtConfig>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ndta::Config) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<NdtConfig>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the table statistics from the `Ndta::Stats` attribute
+    pub fn get_stats(&self) -> Result<Option<NdtStats>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ndta::Stats) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<NdtStats>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the parameters nested in the `Ndta::Parms` attribute - the default parameters of
+    /// the table come without `Ndtpa::Ifindex` and each interface overriding them is sent in
+    /// a separate message with it
+    pub fn get_parms(&self) -> Result<Option<Vec<RtAttr<Ndtpa>>>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Ndta::Parms) {
+            Some(rta) => Ok(Some(rta.get_nested::<Ndtpa>()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Ndta> {
+        rtattr_handle(&self.rtattrs)
+    }
+}
+
+impl Nl for Ndtmsg {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.ndtm_family.serialize(buf)?;
+        // Padding fields ndtm_pad1 and ndtm_pad2
+        0u8.serialize(buf)?;
+        0u16.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(Ndtmsg {
+            ndtm_family: Af::deserialize(buf)?,
+            rtattrs: {
+                u8::deserialize(buf)?;
+                u16::deserialize(buf)?;
+                Vec::<RtAttr<Ndta>>::deserialize(buf)?
+            },
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.ndtm_family.size() + mem::size_of::<u8>() + mem::size_of::<u16>()
+            + self.rtattrs.size()
+    }
+}
+
+/// Neighbor table configuration from the `Ndta::Config` attribute (`struct ndt_config`)
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct NdtConfig {
+    /// Length of the keys of the table
+    pub ndtc_key_len: u16,
+    /// Size of an entry of the table
+    pub ndtc_entry_size: u16,
+    /// Number of entries in the table
+    pub ndtc_entries: u32,
+    /// Time since the last flush in milliseconds
+    pub ndtc_last_flush: u32,
+    /// Time since the last randomization of the reachable time in milliseconds
+    pub ndtc_last_rand: u32,
+    /// Random seed of the hash function
+    pub ndtc_hash_rnd: u32,
+    /// Mask of the hash table
+    pub ndtc_hash_mask: u32,
+    /// Length of the hash chain used for garbage collection
+    pub ndtc_hash_chain_gc: u32,
+    /// Length of the proxy queue
+    pub ndtc_proxy_qlen: u32,
+}
+
+impl Nl for NdtConfig {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.ndtc_key_len.serialize(buf)?;
+        self.ndtc_entry_size.serialize(buf)?;
+        self.ndtc_entries.serialize(buf)?;
+        self.ndtc_last_flush.serialize(buf)?;
+        self.ndtc_last_rand.serialize(buf)?;
+        self.ndtc_hash_rnd.serialize(buf)?;
+        self.ndtc_hash_mask.serialize(buf)?;
+        self.ndtc_hash_chain_gc.serialize(buf)?;
+        self.ndtc_proxy_qlen.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(NdtConfig {
+            ndtc_key_len: u16::deserialize(buf)?,
+            ndtc_entry_size: u16::deserialize(buf)?,
+            ndtc_entries: u32::deserialize(buf)?,
+            ndtc_last_flush: u32::deserialize(buf)?,
+            ndtc_last_rand: u32::deserialize(buf)?,
+            ndtc_hash_rnd: u32::deserialize(buf)?,
+            ndtc_hash_mask: u32::deserialize(buf)?,
+            ndtc_hash_chain_gc: u32::deserialize(buf)?,
+            ndtc_proxy_qlen: u32::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.ndtc_key_len.size() + self.ndtc_entry_size.size() + self.ndtc_entries.size()
+            + self.ndtc_last_flush.size() + self.ndtc_last_rand.size() + self.ndtc_hash_rnd.size()
+            + self.ndtc_hash_mask.size() + self.ndtc_hash_chain_gc.size()
+            + self.ndtc_proxy_qlen.size()
+    }
+}
+
+/// Neighbor table statistics from the `Ndta::Stats` attribute (`struct ndt_stats`) -
+/// `ndts_table_fulls` is zero when reported by kernels that predate it
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct NdtStats {
+    /// Number of allocated entries
+    pub ndts_allocs: u64,
+    /// Number of destroyed entries
+    pub ndts_destroys: u64,
+    /// Number of times the hash table grew
+    pub ndts_hash_grows: u64,
+    /// Number of failed resolutions
+    pub ndts_res_failed: u64,
+    /// Number of lookups
+    pub ndts_lookups: u64,
+    /// Number of successful lookups
+    pub ndts_hits: u64,
+    /// Number of received multicast probes
+    pub ndts_rcv_probes_mcast: u64,
+    /// Number of received unicast probes
+    pub ndts_rcv_probes_ucast: u64,
+    /// Number of periodic garbage collection runs
+    pub ndts_periodic_gc_runs: u64,
+    /// Number of forced garbage collection runs
+    pub ndts_forced_gc_runs: u64,
+    /// Number of times the table was full
+    pub ndts_table_fulls: u64,
+}
+
+impl Nl for NdtStats {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.ndts_allocs.serialize(buf)?;
+        self.ndts_destroys.serialize(buf)?;
+        self.ndts_hash_grows.serialize(buf)?;
+        self.ndts_res_failed.serialize(buf)?;
+        self.ndts_lookups.serialize(buf)?;
+        self.ndts_hits.serialize(buf)?;
+        self.ndts_rcv_probes_mcast.serialize(buf)?;
+        self.ndts_rcv_probes_ucast.serialize(buf)?;
+        self.ndts_periodic_gc_runs.serialize(buf)?;
+        self.ndts_forced_gc_runs.serialize(buf)?;
+        self.ndts_table_fulls.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut stats = NdtStats {
+            ndts_allocs: u64::deserialize(buf)?,
+            ndts_destroys: u64::deserialize(buf)?,
+            ndts_hash_grows: u64::deserialize(buf)?,
+            ndts_res_failed: u64::deserialize(buf)?,
+            ndts_lookups: u64::deserialize(buf)?,
+            ndts_hits: u64::deserialize(buf)?,
+            ndts_rcv_probes_mcast: u64::deserialize(buf)?,
+            ndts_rcv_probes_ucast: u64::deserialize(buf)?,
+            ndts_periodic_gc_runs: u64::deserialize(buf)?,
+            ndts_forced_gc_runs: u64::deserialize(buf)?,
+            ndts_table_fulls: 0,
+        };
+        if let Ok(table_fulls) = u64::deserialize(buf) {
+            stats.ndts_table_fulls = table_fulls;
+        }
+        Ok(stats)
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<u64>() * 11
+    }
+}
+
+/// Traffic control message for qdiscs, classes and filters
+#[derive(Debug,PartialEq)]
+pub struct Tcmsg {
+    /// Family of the traffic control object - usually `Af::Unspec`
+    pub tcm_family: Af,
+    /// Interface index
+    pub tcm_ifindex: libc::c_int,
+    /// Handle of the qdisc, class or filter
+    pub tcm_handle: u32,
+    /// Handle of the parent qdisc or class
+    pub tcm_parent: u32,
+    /// Protocol and priority for filters
+    pub tcm_info: u32,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Tca>>,
+}
+
+impl Tcmsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 2
+        + mem::size_of::<u16>() + mem::size_of::<libc::c_int>() + mem::size_of::<u32>() * 3;
+
+    /// Create a traffic control struct for a dump request (`RTM_GETQDISC`, `RTM_GETTCLASS` or
+    /// `RTM_GETTFILTER` with `NlmF::Dump`) - set `tcm_ifindex` and `tcm_parent` to limit
+    /// class and filter dumps
+    pub fn dump() -> Self {
+        Tcmsg {
+            tcm_family: Af::Unspec,
+            tcm_ifindex: 0,
+            tcm_handle: 0,
+            tcm_parent: 0,
+            tcm_info: 0,
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the name of the qdisc, class or filter from the `Tca::Kind` attribute
+    pub fn get_kind(&self) -> Result<Option<String>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Tca::Kind) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<String>(Some(rta.rta_payload.len()))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Tca> {
+        rtattr_handle(&self.rtattrs)
+    }
+}
+
+impl Nl for Tcmsg {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.tcm_family.serialize(buf)?;
+        // Padding fields tcm__pad1 and tcm__pad2
+        0u8.serialize(buf)?;
+        0u16.serialize(buf)?;
+        self.tcm_ifindex.serialize(buf)?;
+        self.tcm_handle.serialize(buf)?;
+        self.tcm_parent.serialize(buf)?;
+        self.tcm_info.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(Tcmsg {
+            tcm_family: Af::deserialize(buf)?,
+            tcm_ifindex: {
+                u8::deserialize(buf)?;
+                u16::deserialize(buf)?;
+                libc::c_int::deserialize(buf)?
+            },
+            tcm_handle: u32::deserialize(buf)?,
+            tcm_parent: u32::deserialize(buf)?,
+            tcm_info: u32::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<Tca>>::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.tcm_family.size() + mem::size_of::<u8>() + mem::size_of::<u16>()
+            + self.tcm_ifindex.size() + self.tcm_handle.size() + self.tcm_parent.size()
+            + self.tcm_info.size() + self.rtattrs.size()
+    }
+}
+
+/// FIB rule message for policy routing rules (`struct fib_rule_hdr`)
+#[derive(Debug,PartialEq)]
+pub struct FibRuleHdr {
+    /// Address family of the rule
+    pub family: Af,
+    /// Length of the destination prefix
+    pub dst_len: libc::c_uchar,
+    /// Length of the source prefix
+    pub src_len: libc::c_uchar,
+    /// TOS to match
+    pub tos: libc::c_uchar,
+    /// Table to look up - tables with IDs above 255 are only identified by the `Fra::Table`
+    /// attribute
+    pub table: RtTable,
+    /// Action to take when the rule matches
+    pub action: FrAct,
+    /// Rule flags
+    pub flags: NlFlags<FibRuleF>,
+    /// Route netlink attributes following the header
+    pub rtattrs: Vec<RtAttr<Fra>>,
+}
+
+impl FibRuleHdr {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<libc::c_uchar>() * 8 + mem::size_of::<u32>();
+
+    /// Create a rule message for a dump request (`RTM_GETRULE` with `NlmF::Dump`) where every
+    /// field except the family is zeroed
+    pub fn dump(family: Af) -> Self {
+        FibRuleHdr {
+            family,
+            dst_len: 0,
+            src_len: 0,
+            tos: 0,
+            table: RtTable::Unspec,
+            action: FrAct::Unspec,
+            flags: NlFlags::empty(),
+            rtattrs: Vec::new(),
+        }
+    }
+
+    /// Get the ID of the table looked up by this rule from the `Fra::Table` attribute, falling
+    /// back to the `table` field
+    pub fn get_table(&self) -> Result<u32, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Fra::Table) {
+            Some(rta) => rta.get_payload_with::<u32>(None),
+            None => Ok(u32::from(libc::c_uchar::from(&self.table))),
+        }
+    }
+
+    /// Set the ID of the table looked up by this rule - IDs above 255 are carried in an
+    /// `Fra::Table` attribute with `table` set to `RtTable::Unspec`
+    pub fn set_table(&mut self, table: u32) -> Result<(), SerError> {
+        self.rtattrs.retain(|rta| rta.rta_type != Fra::Table);
+        if table <= u32::from(libc::c_uchar::max_value()) {
+            self.table = RtTable::from(table as libc::c_uchar);
+        } else {
+            self.table = RtTable::Unspec;
+            self.rtattrs.push(RtAttr::new(None, Fra::Table, table)?);
+        }
+        Ok(())
+    }
+
+    /// Get the range of user IDs matched by this rule from the `Fra::UidRange` attribute
+    pub fn get_uid_range(&self) -> Result<Option<FibRuleUidRange>, DeError> {
+        match self.rtattrs.iter().find(|rta| rta.rta_type == Fra::UidRange) {
+            Some(rta) => Ok(Some(rta.get_payload_with::<FibRuleUidRange>(None)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get handle for attribute parsing and traversal
+    pub fn get_attr_handle(&self) -> AttrHandle<Fra> {
+        rtattr_handle(&self.rtattrs)
+    }
+}
+
+impl Nl for FibRuleHdr {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.family.serialize(buf)?;
+        self.dst_len.serialize(buf)?;
+        self.src_len.serialize(buf)?;
+        self.tos.serialize(buf)?;
+        self.table.serialize(buf)?;
+        // Reserved fields res1 and res2
+        0u8.serialize(buf)?;
+        0u8.serialize(buf)?;
+        self.action.serialize(buf)?;
+        self.flags.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(FibRuleHdr {
+            family: Af::deserialize(buf)?,
+            dst_len: libc::c_uchar::deserialize(buf)?,
+            src_len: libc::c_uchar::deserialize(buf)?,
+            tos: libc::c_uchar::deserialize(buf)?,
+            table: RtTable::deserialize(buf)?,
+            action: {
+                u8::deserialize(buf)?;
+                u8::deserialize(buf)?;
+                FrAct::deserialize(buf)?
+            },
+            flags: NlFlags::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<Fra>>::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.family.size() + self.dst_len.size() + self.src_len.size() + self.tos.size()
+            + self.table.size() + mem::size_of::<u8>() * 2 + self.action.size()
+            + self.flags.size() + self.rtattrs.size()
+    }
+}
+
+/// Range of user IDs matched by a rule from the `Fra::UidRange` attribute
+/// (`struct fib_rule_uid_range`)
+#[derive(Clone,Debug,PartialEq)]
+pub struct FibRuleUidRange {
+    /// First user ID in the range
+    pub start: u32,
+    /// Last user ID in the range
+    pub end: u32,
+}
+
+impl Nl for FibRuleUidRange {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.start.serialize(buf)?;
+        self.end.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(FibRuleUidRange {
+            start: u32::deserialize(buf)?,
+            end: u32::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.start.size() + self.end.size()
+    }
 }
 
 /// Struct representing route netlink attributes
+#[derive(Debug,PartialEq)]
 pub struct RtAttr<T> {
     /// Length of the attribute
     pub rta_len: libc::c_ushort,
     /// Type of the attribute
     pub rta_type: T,
+    /// Payload of the attribute
+    pub rta_payload: Vec<u8>,
+}
+
+impl<T> RtAttr<T> {
+    /// Length of the header fields preceding the payload
+    pub const HEADER_LEN: usize = mem::size_of::<libc::c_ushort>()
+        + mem::size_of::<libc::c_ushort>();
+}
+
+impl<T> RtAttr<T> where T: RtaType {
+    /// Create a new route netlink attribute with a payload from an object implementing `Nl`
+    pub fn new<P>(rta_len: Option<libc::c_ushort>, rta_type: T, rta_payload: P)
+            -> Result<Self, SerError> where P: Nl {
+        let mut mem = StreamWriteBuffer::new_growable(Some(rta_payload.asize()));
+        rta_payload.serialize(&mut mem)?;
+        let mut rta = RtAttr { rta_len: 0, rta_type, rta_payload: mem.as_ref().to_vec() };
+        rta.rta_len = rta_len.unwrap_or(rta.size() as libc::c_ushort);
+        Ok(rta)
+    }
+
+    /// Parse binary payload as a type that implements `Nl` using `deserialize_with` if `with` is
+    /// not `None`
+    pub fn get_payload_with<R>(&self, with: Option<R::DeIn>) -> Result<R, DeError> where R: Nl {
+        let mut mem = StreamReadBuffer::new(&self.rta_payload);
+        match with {
+            Some(w) => R::deserialize_with(&mut mem, w),
+            None => R::deserialize(&mut mem),
+        }
+    }
+
+    /// Parse binary payload as a list of nested route netlink attributes such as the `Rtax`
+    /// metrics in `Rta::Metrics` - nested lists are built by passing a `Vec<RtAttr<P>>` as
+    /// the payload to `RtAttr::new`
+    pub fn get_nested<P>(&self) -> Result<Vec<RtAttr<P>>, DeError> where P: RtaType {
+        self.get_payload_with::<Vec<RtAttr<P>>>(None)
+    }
 }
 
 impl<T> Nl for RtAttr<T> where T: RtaType {
@@ -202,17 +2114,1130 @@ impl<T> Nl for RtAttr<T> where T: RtaType {
     fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
         self.rta_len.serialize(buf)?;
         self.rta_type.serialize(buf)?;
+        self.rta_payload.serialize(buf)?;
+        let padding_len = self.asize() - self.size();
+        if padding_len > 0 {
+            [0u8; libc::NLA_ALIGNTO as usize][0..padding_len].as_ref().serialize(buf)?;
+        }
         Ok(())
     }
 
     fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
-        Ok(RtAttr {
-            rta_len: libc::c_ushort::deserialize(buf)?,
-            rta_type: T::deserialize(buf)?,
-        })
+        let rta_len = libc::c_ushort::deserialize(buf)?;
+        let rta_type = T::deserialize(buf)?;
+        let hdr_len = Self::HEADER_LEN;
+        if (rta_len as usize) < hdr_len {
+            return Err(DeError::new("Route attribute length is shorter than its header"));
+        }
+        let mut rta_payload = vec![0; rta_len as usize - hdr_len];
+        buf.read_exact(rta_payload.as_mut_slice())?;
+        let padding_len = checked_alignto(rta_len as usize)? - rta_len as usize;
+        let padding = &mut [0u8; 4][0..padding_len];
+        let _ = buf.read_exact(padding);
+        Ok(RtAttr { rta_len, rta_type, rta_payload })
+    }
+
+    fn size(&self) -> usize {
+        self.rta_len.size() + self.rta_type.size() + self.rta_payload.size()
+    }
+}
+
+impl RtAttr<Ifla> {
+    /// Create an `Ifla::Linkinfo` attribute for creating a virtual interface of the given
+    /// kind such as `"dummy"` that needs no kind specific configuration
+    pub fn new_linkinfo(kind: &str) -> Result<Self, SerError> {
+        RtAttr::new(None, Ifla::Linkinfo, vec![RtAttr::new(None, IflaInfo::Kind, kind)?])
+    }
+
+    /// Create an `Ifla::Linkinfo` attribute for creating a virtual interface of the given
+    /// kind with kind specific attributes nested in `IflaInfo::Data`
+    pub fn new_linkinfo_with_data<P>(kind: &str, data: Vec<RtAttr<P>>) -> Result<Self, SerError>
+            where P: RtaType {
+        RtAttr::new(None, Ifla::Linkinfo, vec![
+            RtAttr::new(None, IflaInfo::Kind, kind)?,
+            RtAttr::new(None, IflaInfo::Data, data)?,
+        ])
+    }
+}
+
+impl RtAttr<IflaVlan> {
+    /// Create an `IflaVlan::Protocol` attribute from an ethernet protocol such as
+    /// `ETH_P_8021Q` - the kernel expects the protocol in network byte order
+    pub fn new_vlan_protocol(protocol: u16) -> Result<Self, SerError> {
+        RtAttr::new(None, IflaVlan::Protocol, protocol.to_be())
+    }
+
+    /// Parse the payload of an `IflaVlan::Protocol` attribute as an ethernet protocol in host
+    /// byte order
+    pub fn get_vlan_protocol(&self) -> Result<u16, DeError> {
+        Ok(u16::from_be(self.get_payload_with::<u16>(None)?))
+    }
+}
+
+/// Route netlink attributes share the layout of `Nlattr` so they convert without
+/// reserializing
+impl<'a, T> From<&'a RtAttr<T>> for Nlattr<T> where T: RtaType + Clone {
+    fn from(rta: &'a RtAttr<T>) -> Self {
+        Nlattr {
+            nla_len: rta.rta_len,
+            nla_type: rta.rta_type.clone(),
+            payload: rta.rta_payload.clone(),
+        }
+    }
+}
+
+// Handle over copies of the attributes of a route netlink message
+fn rtattr_handle<'a, T>(rtattrs: &[RtAttr<T>]) -> AttrHandle<'a, T> where T: RtaType + Clone {
+    AttrHandle::Parsed(rtattrs.iter().map(Nlattr::from).collect())
+}
+
+/// Attribute lists serialize each attribute with padding and deserialize attributes until the
+/// end of the buffer
+impl<T> Nl for Vec<RtAttr<T>> where T: RtaType {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        for rta in self.iter() {
+            rta.serialize(buf)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        buf.read_to_end(&mut bytes)?;
+        let mut mem = StreamReadBuffer::new(bytes.as_slice());
+        let mut left = bytes.len();
+        let mut rtattrs = Vec::new();
+        while left > 0 {
+            let rta = RtAttr::<T>::deserialize(&mut mem)?;
+            left = left.saturating_sub(rta.asize());
+            rtattrs.push(rta);
+        }
+        Ok(rtattrs)
     }
 
     fn size(&self) -> usize {
-        self.rta_len.size() + self.rta_type.size()
+        self.iter().fold(0, |acc, rta| acc + rta.asize())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::{Cursor,Write};
+    use std::net::Ipv4Addr;
+
+    use byteorder::{NativeEndian,WriteBytesExt};
+
+    use consts::{IflaVlanQos,NlFamily,NlmF,Rtm};
+    use nl::Nlmsghdr;
+    use nlattr::{AttrHandle,Nlattr};
+    use socket::NlSocket;
+
+    // Modeled after `ip link show lo` on a default system
+    fn loopback_ifinfomsg_bytes() -> Vec<u8> {
+        let mut c = Cursor::new(Vec::new());
+        c.write_u8(0).unwrap();
+        c.write_u8(0).unwrap();
+        c.write_u16::<NativeEndian>(772).unwrap();
+        c.write_i32::<NativeEndian>(1).unwrap();
+        c.write_u32::<NativeEndian>(0x10049).unwrap();
+        c.write_u32::<NativeEndian>(0).unwrap();
+        c.write_u16::<NativeEndian>(7).unwrap();
+        c.write_u16::<NativeEndian>(libc::IFLA_IFNAME).unwrap();
+        c.write_all(&[b'l', b'o', 0, 0]).unwrap();
+        c.write_u16::<NativeEndian>(8).unwrap();
+        c.write_u16::<NativeEndian>(libc::IFLA_MTU).unwrap();
+        c.write_u32::<NativeEndian>(65536).unwrap();
+        c.into_inner()
+    }
+
+    #[test]
+    fn test_ifinfomsg_round_trip() {
+        let bytes = loopback_ifinfomsg_bytes();
+        let ifinfomsg = Ifinfomsg::deserialize(&mut StreamReadBuffer::new(bytes.as_slice()))
+            .unwrap();
+        assert_eq!(ifinfomsg.ifi_family, Af::Unspec);
+        assert_eq!(ifinfomsg.ifi_index, 1);
+        assert_eq!(ifinfomsg.ifi_flags.iter().collect::<Vec<_>>(),
+                   vec![Iff::Up, Iff::Loopback, Iff::Running, Iff::LowerUp]);
+        assert!(ifinfomsg.ifi_flags.contains(&Iff::Up)
+                && ifinfomsg.ifi_flags.contains(&Iff::Running));
+        assert_eq!(ifinfomsg.ifi_change, 0);
+        assert_eq!(ifinfomsg.rtattrs.len(), 2);
+        assert_eq!(ifinfomsg.rtattrs[0].rta_type, Ifla::Ifname);
+        assert_eq!(ifinfomsg.rtattrs[0].get_payload_with::<String>(Some(3)).unwrap(), "lo");
+        assert_eq!(ifinfomsg.rtattrs[1].get_payload_with::<u32>(None).unwrap(), 65536);
+        assert_eq!(ifinfomsg.size(), bytes.len());
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ifinfomsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_ifaddrmsg_round_trip() {
+        let mut c = Cursor::new(Vec::new());
+        c.write_u8(libc::AF_INET as u8).unwrap();
+        c.write_u8(8).unwrap();
+        c.write_u8(libc::IFA_F_PERMANENT as u8).unwrap();
+        c.write_u8(libc::RT_SCOPE_HOST).unwrap();
+        c.write_i32::<NativeEndian>(1).unwrap();
+        c.write_u16::<NativeEndian>(8).unwrap();
+        c.write_u16::<NativeEndian>(libc::IFA_ADDRESS).unwrap();
+        c.write_all(&[127, 0, 0, 1]).unwrap();
+        c.write_u16::<NativeEndian>(8).unwrap();
+        c.write_u16::<NativeEndian>(libc::IFA_FLAGS).unwrap();
+        c.write_u32::<NativeEndian>(libc::IFA_F_PERMANENT | libc::IFA_F_NOPREFIXROUTE).unwrap();
+        let bytes = c.into_inner();
+
+        let ifaddrmsg = Ifaddrmsg::deserialize(&mut StreamReadBuffer::new(bytes.as_slice()))
+            .unwrap();
+        assert_eq!(ifaddrmsg.ifa_family, Af::Inet);
+        assert_eq!(ifaddrmsg.ifa_prefixlen, 8);
+        assert_eq!(ifaddrmsg.ifa_scope, RtScope::Host);
+        assert_eq!(ifaddrmsg.ifa_flags, IfaF::Permanent | IfaF::Noprefixroute);
+        assert_eq!(ifaddrmsg.get_addr(Ifa::Address).unwrap(),
+                   Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))));
+        assert_eq!(ifaddrmsg.get_addr(Ifa::Local).unwrap(), None);
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ifaddrmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_cacheinfo() {
+        let ifa_cacheinfo = IfaCacheinfo { ifa_prefered: 3600, ifa_valid: 0xffffffff, cstamp: 10,
+                                           tstamp: 20 };
+        assert!(!ifa_cacheinfo.is_prefered_forever());
+        assert!(ifa_cacheinfo.is_valid_forever());
+        let ifaddrmsg = Ifaddrmsg {
+            rtattrs: vec![RtAttr::new(None, Ifa::Cacheinfo, ifa_cacheinfo.clone()).unwrap()],
+            ..Ifaddrmsg::dump(Af::Inet)
+        };
+        assert_eq!(ifaddrmsg.get_cacheinfo().unwrap(), Some(ifa_cacheinfo));
+
+        let mut c = Cursor::new(Vec::new());
+        c.write_u32::<NativeEndian>(1).unwrap();
+        c.write_u32::<NativeEndian>(2).unwrap();
+        c.write_i32::<NativeEndian>(-3).unwrap();
+        for i in 4..9 {
+            c.write_u32::<NativeEndian>(i).unwrap();
+        }
+        let rtmsg = Rtmsg {
+            rtattrs: vec![RtAttr::new(None, Rta::Cacheinfo, c.into_inner()).unwrap()],
+            ..Rtmsg::dump(Af::Inet)
+        };
+        let rta_cacheinfo = rtmsg.get_cacheinfo().unwrap().unwrap();
+        assert_eq!(rta_cacheinfo.rta_lastuse, 2);
+        assert_eq!(rta_cacheinfo.rta_expires, -3);
+        assert_eq!(rta_cacheinfo.rta_tsage, 8);
+        assert_eq!(rta_cacheinfo.size(), 32);
+    }
+
+    #[test]
+    fn test_ifaddrmsg_dump_loopback() {
+        let mut sock = NlSocket::<Rtm, Ifaddrmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getaddr, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, Ifaddrmsg::dump(Af::Inet))).unwrap();
+        let loopback = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut cacheinfo = None;
+        for msg in sock.iter() {
+            let ifaddrmsg = msg.unwrap().nl_payload;
+            if ifaddrmsg.get_addr(Ifa::Local).unwrap() == Some(loopback) {
+                cacheinfo = ifaddrmsg.get_cacheinfo().unwrap();
+            }
+        }
+        let cacheinfo = cacheinfo.unwrap();
+        assert!(cacheinfo.is_prefered_forever());
+        assert!(cacheinfo.is_valid_forever());
+    }
+
+    fn multipath_route(num_hops: u8) -> Rtmsg {
+        let hops = (0..num_hops).map(|i| {
+            Rtnexthop::new(NlFlags::from(RtnhF::Onlink), i, i32::from(i) + 2, vec![
+                RtAttr::new(None, Rta::Gateway, IpAddr::V4(Ipv4Addr::new(10, 0, i, 1))).unwrap(),
+            ])
+        }).collect::<Vec<_>>();
+        Rtmsg {
+            rtattrs: vec![
+                RtAttr::new(None, Rta::Dst, IpAddr::V4(Ipv4Addr::new(192, 168, 0, 0))).unwrap(),
+                RtAttr::new(None, Rta::Multipath, hops).unwrap(),
+                RtAttr::new(None, Rta::Priority, 100u32).unwrap(),
+            ],
+            ..Rtmsg::dump(Af::Inet)
+        }
+    }
+
+    #[test]
+    fn test_multipath() {
+        for num_hops in 1..4 {
+            let rtmsg = multipath_route(num_hops);
+            let mut mem = StreamWriteBuffer::new_growable(None);
+            rtmsg.serialize(&mut mem).unwrap();
+            let parsed = Rtmsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+            assert_eq!(parsed, rtmsg);
+
+            let hops = parsed.get_multipath().unwrap().unwrap();
+            assert_eq!(hops.len(), num_hops as usize);
+            for (i, hop) in hops.iter().enumerate() {
+                assert_eq!(hop.rtnh_len, 16);
+                assert!(hop.rtnh_flags.contains(&RtnhF::Onlink));
+                assert_eq!(hop.rtnh_hops as usize, i);
+                assert_eq!(hop.rtnh_ifindex, i as i32 + 2);
+                assert_eq!(hop.get_gateway(Af::Inet).unwrap(),
+                           Some(IpAddr::V4(Ipv4Addr::new(10, 0, i as u8, 1))));
+            }
+            // Attributes after the multipath attribute are not corrupted by the hops
+            assert_eq!(parsed.rtattrs[2].get_payload_with::<u32>(None).unwrap(), 100);
+        }
+    }
+
+    #[test]
+    fn test_multipath_bad_length() {
+        let rtmsg = multipath_route(2);
+        let mut payload = rtmsg.rtattrs[1].rta_payload.clone();
+        // Second hop claims to be longer than the remaining payload
+        payload[16] = 24;
+        let rta = RtAttr { rta_len: rtmsg.rtattrs[1].rta_len, rta_type: Rta::Multipath,
+                           rta_payload: payload.clone() };
+        let rtmsg = Rtmsg { rtattrs: vec![rta], ..Rtmsg::dump(Af::Inet) };
+        assert!(rtmsg.get_multipath().is_err());
+
+        payload.truncate(20);
+        let hops = Vec::<Rtnexthop>::deserialize(&mut StreamReadBuffer::new(payload));
+        assert!(hops.is_err());
+    }
+
+    // Payload of the RTM_NEWROUTE request sent by `ip route add 10.0.0.0/24 dev lo mtu 1400`
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_rtmsg_metrics() {
+        let bytes: &[u8] = &[
+            0x02, 0x18, 0x00, 0x00, 0xfe, 0x03, 0xfd, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00,
+            0x08, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x0c, 0x00, 0x08, 0x00, 0x08, 0x00, 0x02, 0x00, 0x78, 0x05, 0x00, 0x00,
+        ];
+        let rtmsg = Rtmsg {
+            rtm_dst_len: 24,
+            rtm_table: RtTable::Main,
+            rtm_protocol: Rtprot::Boot,
+            rtm_scope: RtScope::Link,
+            rtm_type: Rtn::Unicast,
+            rtattrs: vec![
+                RtAttr::new(None, Rta::Dst, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0))).unwrap(),
+                RtAttr::new(None, Rta::Oif, 1u32).unwrap(),
+                RtAttr::new(None, Rta::Metrics, vec![
+                    RtAttr::new(None, Rtax::Mtu, 1400u32).unwrap(),
+                ]).unwrap(),
+            ],
+            ..Rtmsg::dump(Af::Inet)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rtmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes);
+
+        let parsed = Rtmsg::deserialize(&mut StreamReadBuffer::new(bytes)).unwrap();
+        assert_eq!(parsed, rtmsg);
+        assert_eq!(parsed.get_metrics().unwrap().unwrap().len(), 1);
+        assert_eq!(parsed.get_metric(Rtax::Mtu).unwrap(), Some(1400));
+        assert_eq!(parsed.get_metric(Rtax::Hoplimit).unwrap(), None);
+        assert_eq!(Rtmsg::dump(Af::Inet).get_metric(Rtax::Mtu).unwrap(), None);
+    }
+
+    #[test]
+    fn test_rtmsg_round_trip() {
+        let mut c = Cursor::new(Vec::new());
+        c.write_u8(libc::AF_INET as u8).unwrap();
+        c.write_u8(32).unwrap();
+        c.write_u8(0).unwrap();
+        c.write_u8(0).unwrap();
+        c.write_u8(libc::RT_TABLE_LOCAL).unwrap();
+        c.write_u8(libc::RTPROT_KERNEL).unwrap();
+        c.write_u8(libc::RT_SCOPE_HOST).unwrap();
+        c.write_u8(libc::RTN_LOCAL).unwrap();
+        c.write_u32::<NativeEndian>(libc::RTM_F_CLONED).unwrap();
+        c.write_u16::<NativeEndian>(8).unwrap();
+        c.write_u16::<NativeEndian>(libc::RTA_TABLE).unwrap();
+        c.write_u32::<NativeEndian>(libc::RT_TABLE_LOCAL as u32).unwrap();
+        c.write_u16::<NativeEndian>(8).unwrap();
+        c.write_u16::<NativeEndian>(libc::RTA_DST).unwrap();
+        c.write_all(&[127, 0, 0, 1]).unwrap();
+        let bytes = c.into_inner();
+
+        let rtmsg = Rtmsg::deserialize(&mut StreamReadBuffer::new(bytes.as_slice())).unwrap();
+        assert_eq!(rtmsg.rtm_family, Af::Inet);
+        assert_eq!(rtmsg.rtm_dst_len, 32);
+        assert_eq!(rtmsg.rtm_table, RtTable::Local);
+        assert_eq!(rtmsg.rtm_protocol, Rtprot::Kernel);
+        assert_eq!(rtmsg.rtm_scope, RtScope::Host);
+        assert_eq!(rtmsg.rtm_type, Rtn::Local);
+        assert_eq!(rtmsg.rtm_flags, NlFlags::from(RtmF::Cloned));
+        assert_eq!(rtmsg.get_addr(Rta::Dst).unwrap(),
+                   Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))));
+        assert_eq!(rtmsg.size(), bytes.len());
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rtmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_rtmsg_set_table() {
+        let mut rtmsg = Rtmsg::dump(Af::Inet);
+        rtmsg.set_table(1000).unwrap();
+        assert_eq!(rtmsg.rtm_table, RtTable::Unspec);
+        assert_eq!(rtmsg.rtattrs.len(), 1);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rtmsg.serialize(&mut mem).unwrap();
+        let parsed = Rtmsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed.get_table().unwrap(), 1000);
+
+        // Replies of the kernel set `rtm_table` to `RtTable::Compat` for such tables
+        let reply = Rtmsg { rtm_table: RtTable::Compat, ..parsed };
+        assert_eq!(reply.get_table().unwrap(), 1000);
+
+        rtmsg.set_table(u32::from(libc::RT_TABLE_MAIN)).unwrap();
+        assert_eq!(rtmsg.rtm_table, RtTable::Main);
+        assert!(rtmsg.rtattrs.is_empty());
+        assert_eq!(rtmsg.get_table().unwrap(), u32::from(libc::RT_TABLE_MAIN));
+
+        let mut rule = FibRuleHdr::dump(Af::Inet);
+        rule.set_table(1000).unwrap();
+        assert_eq!(rule.table, RtTable::Unspec);
+        assert_eq!(rule.get_table().unwrap(), 1000);
+        rule.set_table(100).unwrap();
+        assert_eq!(rule.table, RtTable::from(100));
+        assert!(rule.rtattrs.is_empty());
+    }
+
+    #[test]
+    fn test_rtmsg_attr_handle() {
+        // Generic over the marker trait like code shared by several rtnetlink messages
+        fn find_u32<T>(handle: &mut AttrHandle<T>, rta_type: T) -> u32 where T: RtaType {
+            handle.get_payload_with::<u32>(rta_type, None).unwrap()
+        }
+
+        let mut rtmsg = Rtmsg::dump(Af::Inet);
+        rtmsg.rtattrs.push(RtAttr::new(None, Rta::Oif, 3u32).unwrap());
+        rtmsg.rtattrs.push(RtAttr::new(None, Rta::Metrics, vec![
+            RtAttr::new(None, Rtax::Mtu, 1400u32).unwrap(),
+        ]).unwrap());
+        let mut handle = rtmsg.get_attr_handle();
+        assert_eq!(handle.len(), Some(2));
+        assert_eq!(handle.get_attribute(Rta::Oif).unwrap().nla_len, 8);
+        assert_eq!(find_u32(&mut handle, Rta::Oif), 3);
+        let mut metrics = handle.get_nested_attributes::<Rtax>(Rta::Metrics).unwrap();
+        assert_eq!(find_u32(&mut metrics, Rtax::Mtu), 1400);
+        assert!(handle.get_attribute(Rta::Gateway).is_none());
+    }
+
+    #[test]
+    fn test_rtmsg_dump_local() {
+        let mut sock = NlSocket::<Rtm, Rtmsg>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getroute, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, Rtmsg::dump(Af::Inet))).unwrap();
+        let loopback = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut found = false;
+        for msg in sock.iter() {
+            let rtmsg = msg.unwrap().nl_payload;
+            if rtmsg.rtm_type == Rtn::Local && rtmsg.get_addr(Rta::Dst).unwrap() == Some(loopback) {
+                found = true;
+            }
+        }
+        assert!(found);
+    }
+
+    #[test]
+    fn test_ndtmsg_round_trip() {
+        let config = NdtConfig { ndtc_key_len: 4, ndtc_entry_size: 440, ndtc_entries: 3,
+                                 ndtc_hash_mask: 7, ..NdtConfig::default() };
+        let stats = NdtStats { ndts_allocs: 9, ndts_lookups: 120, ndts_hits: 100,
+                               ..NdtStats::default() };
+        let ndtmsg = Ndtmsg {
+            rtattrs: vec![
+                RtAttr::new(None, Ndta::Name, "arp_cache").unwrap(),
+                RtAttr::new(None, Ndta::Thresh1, 128u32).unwrap(),
+                RtAttr::new(None, Ndta::GcInterval, 30000u64).unwrap(),
+                RtAttr::new(None, Ndta::Config, config.clone()).unwrap(),
+                RtAttr::new(None, Ndta::Stats, stats.clone()).unwrap(),
+                RtAttr::new(None, Ndta::Parms, vec![
+                    RtAttr::new(None, Ndtpa::Ifindex, 2u32).unwrap(),
+                    RtAttr::new(None, Ndtpa::BaseReachableTime, 30000u64).unwrap(),
+                    RtAttr::new(None, Ndtpa::UcastProbes, 3u32).unwrap(),
+                    RtAttr::new(None, Ndtpa::McastReprobes, 0u32).unwrap(),
+                ]).unwrap(),
+            ],
+            ..Ndtmsg::dump(Af::Inet)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ndtmsg.serialize(&mut mem).unwrap();
+        assert_eq!(&mem.as_ref()[..Ndtmsg::LEN], &[libc::AF_INET as u8, 0, 0, 0]);
+        assert_eq!(ndtmsg.size(), mem.as_ref().len());
+
+        let parsed = Ndtmsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, ndtmsg);
+        assert_eq!(parsed.get_name().unwrap(), Some("arp_cache".to_string()));
+        assert_eq!(parsed.get_config().unwrap(), Some(config));
+        assert_eq!(parsed.get_stats().unwrap(), Some(stats));
+        let parms = parsed.get_parms().unwrap().unwrap();
+        assert_eq!(parms.len(), 4);
+        assert_eq!(parms[0].get_payload_with::<u32>(None).unwrap(), 2);
+        assert_eq!(parms[1].rta_type, Ndtpa::BaseReachableTime);
+        assert_eq!(parms[1].get_payload_with::<u64>(None).unwrap(), 30000);
+
+        // Statistics from kernels without ndts_table_fulls
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        NdtStats { ndts_hits: 5, ..NdtStats::default() }.serialize(&mut mem).unwrap();
+        let short = &mem.as_ref()[..mem.as_ref().len() - 8];
+        let parsed = NdtStats::deserialize(&mut StreamReadBuffer::new(short)).unwrap();
+        assert_eq!(parsed.ndts_hits, 5);
+        assert_eq!(parsed.ndts_table_fulls, 0);
+    }
+
+    #[test]
+    fn test_ndtmsg_dump() {
+        let mut sock = NlSocket::<Rtm, Ndtmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getneightbl, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, Ndtmsg::dump(Af::Inet))).unwrap();
+        let mut found = false;
+        for msg in sock.iter() {
+            let ndtmsg = msg.unwrap().nl_payload;
+            if ndtmsg.get_name().unwrap() == Some("arp_cache".to_string())
+                    && ndtmsg.get_config().unwrap().is_some() {
+                assert!(ndtmsg.get_parms().unwrap().is_some());
+                found = true;
+            }
+        }
+        assert!(found);
+    }
+
+    // Captured from `ip neigh show` on x86_64: 192.168.1.1 on interface 2 in state REACHABLE
+    // with link layer address 52:54:00:12:34:56
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_ndmsg_round_trip() {
+        let bytes: &[u8] = &[
+            0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+            0x08, 0x00, 0x01, 0x00, 0xc0, 0xa8, 0x01, 0x01,
+            0x0a, 0x00, 0x02, 0x00, 0x52, 0x54, 0x00, 0x12, 0x34, 0x56, 0x00, 0x00,
+            0x08, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let ndmsg = Ndmsg::deserialize(&mut StreamReadBuffer::new(bytes)).unwrap();
+        assert_eq!(ndmsg.ndm_family, Af::Inet);
+        assert_eq!(ndmsg.ndm_ifindex, 2);
+        assert_eq!(ndmsg.ndm_state, NlFlags::from(Nud::Reachable));
+        assert!(ndmsg.ndm_flags.is_empty());
+        assert_eq!(ndmsg.ndm_type, Rtn::Unicast);
+        assert_eq!(ndmsg.rtattrs.len(), 3);
+        assert_eq!(ndmsg.get_dst().unwrap(), Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))));
+        assert_eq!(ndmsg.get_lladdr(), Some(&[0x52, 0x54, 0x00, 0x12, 0x34, 0x56][..]));
+        assert_eq!(ndmsg.rtattrs[2].get_payload_with::<u32>(None).unwrap(), 0);
+        assert_eq!(ndmsg.size(), bytes.len());
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ndmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes);
+    }
+
+    #[test]
+    fn test_fdb_attrs_round_trip() {
+        // Entry of a VXLAN device as added by `bridge fdb append 02:42:ac:11:00:02 dev vxlan0
+        // dst 192.0.2.1 vni 100 self permanent`
+        let mac = MacAddr([0x02, 0x42, 0xac, 0x11, 0x00, 0x02]);
+        let remote = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let ndmsg = Ndmsg {
+            ndm_family: Af::Bridge,
+            ndm_ifindex: 5,
+            ndm_state: NlFlags::from(Nud::Permanent),
+            ndm_flags: NlFlags::from(Ntf::Self_),
+            ndm_type: Rtn::Unspec,
+            rtattrs: vec![
+                RtAttr::new(None, Nda::Lladdr, mac).unwrap(),
+                RtAttr::new(None, Nda::Dst, remote).unwrap(),
+                RtAttr::new(None, Nda::Vni, 100u32).unwrap(),
+                RtAttr::new(None, Nda::Vlan, 10u16).unwrap(),
+                RtAttr::new(None, Nda::Master, 3u32).unwrap(),
+            ],
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ndmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), ndmsg.size());
+        assert_eq!(mem.as_ref()[0], libc::AF_BRIDGE as u8);
+
+        let parsed = Ndmsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, ndmsg);
+        let handle = parsed.get_attr_handle();
+        assert_eq!(handle.get_mac_addr().unwrap(), Some(mac));
+        assert_eq!(handle.get_dst_addr().unwrap(), Some(remote));
+        let vni = handle.get_attr_payload(Nda::Vni).unwrap().unwrap();
+        assert_eq!(u32::from_bytes(vni).unwrap(), 100);
+        assert_eq!(handle.get_attr_payload(Nda::Port).unwrap(), None);
+        assert_eq!(mac.to_string(), "02:42:ac:11:00:02");
+
+        let bad = rtattr_handle(&[RtAttr::new(None, Nda::Lladdr, vec![0u8; 4]).unwrap()]);
+        assert!(bad.get_mac_addr().is_err());
+        assert_eq!(bad.get_dst_addr().unwrap(), None);
+        assert_eq!(Nda::from(17), Nda::NdmFlagsMask);
+    }
+
+    #[test]
+    fn test_rtgenmsg() {
+        let nlhdr = Nlmsghdr::new(None, Rtm::Getlink, vec![NlmF::Request, NlmF::Dump], Some(1),
+                                  None, Rtgenmsg::new(AddrFamily::Packet));
+        assert_eq!(nlhdr.nl_len, 17);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        nlhdr.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), 17);
+        assert_eq!(mem.as_ref()[16], libc::AF_PACKET as u8);
+
+        let mut padded = mem.as_ref().to_vec();
+        padded.extend_from_slice(&[0, 0, 0]);
+        padded.extend_from_slice(&[0xff; 4]);
+        let mut mem = StreamReadBuffer::new(padded.as_slice());
+        let parsed = Nlmsghdr::<Rtm, Rtgenmsg>::deserialize(&mut mem).unwrap();
+        assert_eq!(parsed.nl_type, Rtm::Getlink);
+        assert_eq!(parsed.nl_payload, Rtgenmsg::new(AddrFamily::Packet));
+        assert_eq!(u32::deserialize(&mut mem).unwrap(), 0xffffffff);
+    }
+
+    // Payload of the RTM_NEWRULE request sent by `ip rule add from all lookup 100 priority 1000`
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_fib_rule_round_trip() {
+        let bytes: &[u8] = &[
+            0x02, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x08, 0x00, 0x06, 0x00, 0xe8, 0x03, 0x00, 0x00,
+        ];
+        let rule = FibRuleHdr {
+            table: RtTable::from(100),
+            action: FrAct::ToTbl,
+            rtattrs: vec![RtAttr::new(None, Fra::Priority, 1000u32).unwrap()],
+            ..FibRuleHdr::dump(Af::Inet)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rule.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes);
+        assert_eq!(rule.size(), bytes.len());
+
+        let parsed = FibRuleHdr::deserialize(&mut StreamReadBuffer::new(bytes)).unwrap();
+        assert_eq!(parsed, rule);
+        assert_eq!(parsed.get_table().unwrap(), 100);
+        assert_eq!(parsed.get_uid_range().unwrap(), None);
+    }
+
+    #[test]
+    fn test_fib_rule_dump() {
+        let mut sock = NlSocket::<Rtm, FibRuleHdr>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getrule, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, FibRuleHdr::dump(Af::Inet))).unwrap();
+        let mut tables = Vec::new();
+        for msg in sock.iter() {
+            tables.push(msg.unwrap().nl_payload.get_table().unwrap());
+        }
+        // The rule looking up the local table is always present
+        assert!(tables.contains(&u32::from(libc::RT_TABLE_LOCAL)));
+    }
+
+    #[test]
+    fn test_fib_rule_attrs() {
+        let uid_range = FibRuleUidRange { start: 1000, end: 1999 };
+        let rule = FibRuleHdr {
+            table: RtTable::Unspec,
+            action: FrAct::ToTbl,
+            flags: NlFlags::from(FibRuleF::Invert),
+            rtattrs: vec![
+                RtAttr::new(None, Fra::Table, 1000u32).unwrap(),
+                RtAttr::new(None, Fra::Oifname, "eth0").unwrap(),
+                RtAttr::new(None, Fra::UidRange, uid_range.clone()).unwrap(),
+            ],
+            ..FibRuleHdr::dump(Af::Inet6)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        rule.serialize(&mut mem).unwrap();
+        assert_eq!(FibRuleHdr::dump(Af::Inet6).size(), FibRuleHdr::LEN);
+
+        let parsed = FibRuleHdr::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, rule);
+        assert!(parsed.flags.contains(&FibRuleF::Invert));
+        assert_eq!(parsed.get_table().unwrap(), 1000);
+        assert_eq!(parsed.get_uid_range().unwrap(), Some(uid_range));
+    }
+
+    #[test]
+    fn test_tcmsg_round_trip() {
+        let mut c = Cursor::new(Vec::new());
+        c.write_u8(0).unwrap();
+        c.write_u8(0).unwrap();
+        c.write_u16::<NativeEndian>(0).unwrap();
+        c.write_i32::<NativeEndian>(1).unwrap();
+        c.write_u32::<NativeEndian>(0).unwrap();
+        c.write_u32::<NativeEndian>(0xffffffff).unwrap();
+        c.write_u32::<NativeEndian>(2).unwrap();
+        c.write_u16::<NativeEndian>(12).unwrap();
+        c.write_u16::<NativeEndian>(1).unwrap();
+        c.write_all(b"noqueue\0").unwrap();
+        let bytes = c.into_inner();
+
+        let tcmsg = Tcmsg::deserialize(&mut StreamReadBuffer::new(bytes.as_slice())).unwrap();
+        assert_eq!(tcmsg.tcm_ifindex, 1);
+        assert_eq!(tcmsg.tcm_parent, 0xffffffff);
+        assert_eq!(tcmsg.tcm_info, 2);
+        assert_eq!(tcmsg.get_kind().unwrap(), Some("noqueue".to_string()));
+        assert_eq!(tcmsg.size(), bytes.len());
+
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        tcmsg.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_tcmsg_dump_loopback() {
+        let mut sock = NlSocket::<Rtm, Tcmsg>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getqdisc, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, Tcmsg::dump())).unwrap();
+        let mut kinds = Vec::new();
+        for msg in sock.iter() {
+            let tcmsg = msg.unwrap().nl_payload;
+            if tcmsg.tcm_ifindex == 1 {
+                kinds.push(tcmsg.get_kind().unwrap());
+            }
+        }
+        assert!(!kinds.is_empty());
+        assert!(kinds.iter().all(|kind| kind.is_some()));
+    }
+
+    #[test]
+    fn test_link_stats() {
+        let mut c = Cursor::new(Vec::new());
+        for i in 1..24u64 {
+            c.write_u64::<NativeEndian>(i).unwrap();
+        }
+        // rx_nohandler from newer kernels
+        c.write_u64::<NativeEndian>(100).unwrap();
+        let stats64 = c.into_inner();
+        let mut c = Cursor::new(Vec::new());
+        for i in 1..24u32 {
+            c.write_u32::<NativeEndian>(i * 10).unwrap();
+        }
+        let stats = c.into_inner();
+
+        let ifinfomsg = Ifinfomsg::new(Af::Unspec, Arphrd::Ether, 1, NlFlags::empty(), vec![
+            RtAttr::new(None, Ifla::Stats, stats).unwrap(),
+            RtAttr::new(None, Ifla::Stats64, stats64).unwrap(),
+        ]);
+        let stats = ifinfomsg.get_stats().unwrap().unwrap();
+        assert_eq!(stats.rx_packets, 10);
+        assert_eq!(stats.tx_compressed, 230);
+        assert_eq!(stats.size(), 23 * 4);
+        let stats64 = ifinfomsg.get_stats64().unwrap().unwrap();
+        assert_eq!(stats64.rx_bytes, 3);
+        assert_eq!(stats64.collisions, 10);
+        assert_eq!(stats64.tx_compressed, 23);
+
+        let attr = Nlattr::new_binary_payload(None, 1u16, ifinfomsg.rtattrs[1].rta_payload.clone());
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        attr.serialize(&mut mem).unwrap();
+        let mut handle = AttrHandle::<u16>::Bin(mem.as_ref());
+        assert_eq!(handle.get_payload_with::<LinkStats64>(1, None).unwrap(), stats64);
+    }
+
+    fn serialized_len<N: Nl>(msg: N) -> usize {
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        msg.serialize(&mut mem).unwrap();
+        mem.as_ref().len()
+    }
+
+    #[test]
+    fn test_fixed_lens() {
+        assert_eq!(Rtgenmsg::LEN, serialized_len(Rtgenmsg::new(AddrFamily::Packet)));
+        assert_eq!(Ifinfomsg::LEN, serialized_len(Ifinfomsg::dump(Af::Unspec)));
+        assert_eq!(Ifaddrmsg::LEN, serialized_len(Ifaddrmsg::dump(Af::Inet)));
+        assert_eq!(Rtmsg::LEN, serialized_len(Rtmsg::dump(Af::Inet)));
+        assert_eq!(Rtnexthop::LEN, serialized_len(Rtnexthop::new(NlFlags::empty(), 0, 1,
+                                                                   Vec::new())));
+        assert_eq!(Ndmsg::LEN, serialized_len(Ndmsg::dump(Af::Inet)));
+        assert_eq!(Tcmsg::LEN, serialized_len(Tcmsg::dump()));
+        assert_eq!(RtAttr::<Rta>::HEADER_LEN,
+                   serialized_len(RtAttr::new(None, Rta::Unspec, ()).unwrap()));
+    }
+
+    #[test]
+    fn test_ifinfomsg_dump() {
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        Ifinfomsg::dump(Af::Inet).serialize(&mut mem).unwrap();
+        let mut expected = vec![0; 16];
+        expected[0] = libc::AF_INET as u8;
+        assert_eq!(mem.as_ref(), expected.as_slice());
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_linkinfo() {
+        let linkinfo = RtAttr::new_linkinfo("dummy").unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        linkinfo.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), &[
+            0x10, 0x00, 0x12, 0x00,
+            0x0a, 0x00, 0x01, 0x00, 0x64, 0x75, 0x6d, 0x6d, 0x79, 0x00, 0x00, 0x00,
+        ][..]);
+
+        let ifinfomsg = Ifinfomsg { rtattrs: vec![linkinfo], ..Ifinfomsg::dump(Af::Unspec) };
+        assert_eq!(ifinfomsg.get_link_kind().unwrap(), Some("dummy".to_string()));
+        assert_eq!(Ifinfomsg::dump(Af::Unspec).get_link_kind().unwrap(), None);
+
+        let linkinfo = RtAttr::new_linkinfo_with_data("vlan", vec![
+            RtAttr::new(None, IflaInfo::Unspec, 10u16).unwrap(),
+        ]).unwrap();
+        let nested = linkinfo.get_nested::<IflaInfo>().unwrap();
+        assert_eq!(nested[1].rta_type, IflaInfo::Data);
+        assert_eq!(nested[1].get_nested::<IflaInfo>().unwrap()[0]
+                   .get_payload_with::<u16>(None).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_vlan_link() {
+        let qos = vec![
+            RtAttr::new(None, IflaVlanQos::Mapping, IflaVlanQosMapping { from: 1, to: 3 })
+                .unwrap(),
+            RtAttr::new(None, IflaVlanQos::Mapping, IflaVlanQosMapping { from: 2, to: 5 })
+                .unwrap(),
+        ];
+        let flags = IflaVlanFlags {
+            flags: NlFlags::from(VlanFlag::ReorderHdr),
+            mask: NlFlags::from(vec![VlanFlag::ReorderHdr, VlanFlag::Gvrp]),
+        };
+        let ifinfomsg = Ifinfomsg {
+            rtattrs: vec![
+                RtAttr::new(None, Ifla::Link, 2u32).unwrap(),
+                RtAttr::new(None, Ifla::Ifname, "eth0.100").unwrap(),
+                RtAttr::new_linkinfo_with_data("vlan", vec![
+                    RtAttr::new(None, IflaVlan::Id, 100u16).unwrap(),
+                    RtAttr::new_vlan_protocol(0x8100).unwrap(),
+                    RtAttr::new(None, IflaVlan::Flags, flags.clone()).unwrap(),
+                    RtAttr::new(None, IflaVlan::EgressQos, qos).unwrap(),
+                ]).unwrap(),
+            ],
+            ..Ifinfomsg::dump(Af::Unspec)
+        };
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ifinfomsg.serialize(&mut mem).unwrap();
+        // Protocol is in network byte order
+        assert!(mem.as_ref().windows(6).any(|w| w == [0x06, 0x00, 0x05, 0x00, 0x81, 0x00]));
+        let parsed = Ifinfomsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed, ifinfomsg);
+        assert_eq!(parsed.get_link_kind().unwrap(), Some("vlan".to_string()));
+
+        let linkinfo = parsed.rtattrs[2].get_nested::<IflaInfo>().unwrap();
+        let data = linkinfo[1].get_nested::<IflaVlan>().unwrap();
+        assert_eq!(data[0].get_payload_with::<u16>(None).unwrap(), 100);
+        assert_eq!(data[1].get_vlan_protocol().unwrap(), 0x8100);
+        assert_eq!(data[2].get_payload_with::<IflaVlanFlags>(None).unwrap(), flags);
+        let mappings = data[3].get_nested::<IflaVlanQos>().unwrap().iter()
+            .map(|rta| rta.get_payload_with::<IflaVlanQosMapping>(None).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(mappings, vec![IflaVlanQosMapping { from: 1, to: 3 },
+                                  IflaVlanQosMapping { from: 2, to: 5 }]);
+    }
+
+    #[test]
+    fn test_link_from_ifinfomsg() {
+        let ifinfomsg = Ifinfomsg::new(Af::Unspec, Arphrd::Ether, 3,
+                                       NlFlags::from(vec![Iff::Up, Iff::Broadcast]), vec![
+            RtAttr::new(None, Ifla::Ifname, "eth0").unwrap(),
+            RtAttr::new(None, Ifla::Address, vec![0x02u8, 0, 0, 0, 0, 1]).unwrap(),
+            RtAttr::new(None, Ifla::Mtu, 1500u32).unwrap(),
+            RtAttr::new(None, Ifla::Operstate, IfOper::Up).unwrap(),
+        ]);
+        let link = Link::from_ifinfomsg(ifinfomsg).unwrap();
+        assert_eq!(link.index, 3);
+        assert_eq!(link.name, "eth0");
+        assert_eq!(link.mac, Some(vec![0x02, 0, 0, 0, 0, 1]));
+        assert_eq!(link.mtu, Some(1500));
+        assert!(link.flags.contains(&Iff::Up));
+        assert_eq!(link.oper_state, Some(IfOper::Up));
+        assert_eq!(link.rtattrs.len(), 4);
+
+        let ifinfomsg = Ifinfomsg::dump(Af::Unspec);
+        assert!(Link::from_ifinfomsg(ifinfomsg).is_err());
+    }
+
+    #[test]
+    fn test_get_links() {
+        let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let links = get_links(&mut sock).unwrap();
+        let lo = links.iter().find(|link| link.name == "lo").unwrap();
+        assert!(lo.flags.contains(&Iff::Loopback));
+        assert_eq!(lo.mac, Some(vec![0; 6]));
+        assert!(lo.mtu.is_some());
+    }
+
+    #[test]
+    fn test_addr_from_ifaddrmsg() {
+        let ifaddrmsg = Ifaddrmsg {
+            ifa_prefixlen: 32,
+            ifa_flags: NlFlags::from(IfaF::Permanent),
+            ifa_index: 5,
+            rtattrs: vec![
+                RtAttr::new(None, Ifa::Address, vec![10u8, 0, 0, 2]).unwrap(),
+                RtAttr::new(None, Ifa::Local, vec![10u8, 0, 0, 1]).unwrap(),
+            ],
+            ..Ifaddrmsg::dump(Af::Inet)
+        };
+        let addr = Addr::from_ifaddrmsg(ifaddrmsg).unwrap();
+        assert_eq!(addr.index, 5);
+        assert_eq!(addr.prefix_len, 32);
+        assert_eq!(addr.flags, NlFlags::from(IfaF::Permanent));
+        assert_eq!(addr.addr, IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(addr.peer(), Some(IpAddr::from(Ipv4Addr::new(10, 0, 0, 2))));
+
+        let ifaddrmsg = Ifaddrmsg {
+            rtattrs: vec![RtAttr::new(None, Ifa::Address, vec![0xfeu8, 0x80, 0, 0, 0, 0, 0, 0,
+                                                                 0, 0, 0, 0, 0, 0, 0, 1]).unwrap()],
+            ..Ifaddrmsg::dump(Af::Inet6)
+        };
+        let addr = Addr::from_ifaddrmsg(ifaddrmsg).unwrap();
+        assert_eq!(addr.addr, "fe80::1".parse::<IpAddr>().unwrap());
+        assert_eq!(addr.local, None);
+        assert_eq!(addr.peer(), None);
+
+        assert!(Addr::from_ifaddrmsg(Ifaddrmsg::dump(Af::Inet)).is_err());
+    }
+
+    #[test]
+    fn test_get_addrs() {
+        let mut sock = NlSocket::<Rtm, Ifaddrmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let addrs = get_addrs(&mut sock, Some(Af::Inet)).unwrap();
+        assert!(addrs.iter().all(|addr| addr.family == Af::Inet));
+        let lo = addrs.iter().find(|addr| addr.addr == IpAddr::from(Ipv4Addr::LOCALHOST)).unwrap();
+        assert_eq!(lo.prefix_len, 8);
+        assert_eq!(lo.scope, RtScope::Host);
+
+        let addrs = get_addrs(&mut sock, None).unwrap();
+        assert!(addrs.iter().any(|addr| addr.addr == IpAddr::from(Ipv4Addr::LOCALHOST)));
+    }
+
+    #[test]
+    fn test_get_routes() {
+        let mut sock = NlSocket::<Rtm, Rtmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let routes = get_routes(&mut sock, Af::Inet).unwrap();
+        assert!(routes.iter().all(|route| route.family == Af::Inet));
+        let loopback = routes.iter().find(|route| {
+            route.dst == Some(IpAddr::from(Ipv4Addr::new(127, 0, 0, 0))) && route.dst_len == 8
+        }).unwrap();
+        assert_eq!(loopback.table, u32::from(libc::RT_TABLE_LOCAL));
+        assert_eq!(loopback.scope, RtScope::Host);
+        assert_eq!(loopback.route_type, Rtn::Local);
+        assert!(loopback.oif.is_some());
+    }
+
+    #[test]
+    fn test_route_get() {
+        let mut sock = NlSocket::<Rtm, Rtmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let route = route_get(&mut sock, IpAddr::from(Ipv4Addr::LOCALHOST)).unwrap();
+        assert_eq!(route.dst, Some(IpAddr::from(Ipv4Addr::LOCALHOST)));
+        assert_eq!(route.dst_len, 32);
+        // The local table is merged into the main table while no custom rules exist
+        assert!(route.table == u32::from(libc::RT_TABLE_LOCAL)
+                || route.table == u32::from(libc::RT_TABLE_MAIN));
+        assert_eq!(route.route_type, Rtn::Local);
+
+        let mut link_sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None,
+                                                                Vec::new()).unwrap();
+        let lo = find_link(&mut link_sock, "lo").unwrap();
+        assert_eq!(route.oif, Some(lo.ifi_index as u32));
+    }
+
+    #[test]
+    fn test_ipnet() {
+        let net = IpNet::new(IpAddr::from(Ipv4Addr::new(192, 168, 1, 10)), 24).unwrap();
+        assert_eq!(net.broadcast(), Some(IpAddr::from(Ipv4Addr::new(192, 168, 1, 255))));
+        let net = IpNet::new(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)), 31).unwrap();
+        assert_eq!(net.broadcast(), None);
+        let net = IpNet::new(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)), 0).unwrap();
+        assert_eq!(net.broadcast(), Some(IpAddr::from(Ipv4Addr::new(255, 255, 255, 255))));
+        assert!(IpNet::new(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)), 33).is_err());
+        assert!(IpNet::new("fe80::1".parse().unwrap(), 128).is_ok());
+    }
+
+    // Requires CAP_NET_ADMIN - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_addr_add_del() {
+        let mut sock = NlSocket::<Rtm, Ifaddrmsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let mut link_sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None,
+                                                                Vec::new()).unwrap();
+        let lo = find_link(&mut link_sock, "lo").unwrap().ifi_index;
+        let net = IpNet::new(IpAddr::from(Ipv4Addr::new(198, 51, 100, 7)), 32).unwrap();
+        addr_add(&mut sock, lo, net, Vec::new()).unwrap();
+        let added = get_addrs(&mut sock, Some(Af::Inet)).unwrap().into_iter()
+            .any(|addr| addr.addr == net.addr && addr.index == lo);
+        let again = addr_add(&mut sock, lo, net, Vec::new());
+        addr_del(&mut sock, lo, net).unwrap();
+        assert!(added);
+        assert_eq!(again.err().and_then(|e| e.errno()), Some(libc::EEXIST));
+        assert_eq!(addr_del(&mut sock, lo, net).err().and_then(|e| e.errno()),
+                   Some(libc::EADDRNOTAVAIL));
+    }
+
+    #[test]
+    fn test_link_changes() {
+        let ifinfomsg = LinkChanges::new().down().mtu(1400).into_ifinfomsg(4).unwrap();
+        assert_eq!(ifinfomsg.ifi_index, 4);
+        assert!(!ifinfomsg.ifi_flags.contains(&Iff::Up));
+        assert_eq!(ifinfomsg.ifi_change, libc::IFF_UP as libc::c_uint);
+        assert_eq!(ifinfomsg.rtattrs, vec![RtAttr::new(None, Ifla::Mtu, 1400u32).unwrap()]);
+
+        let ifinfomsg = LinkChanges::new().up().into_ifinfomsg(4).unwrap();
+        assert!(ifinfomsg.ifi_flags.contains(&Iff::Up));
+        assert_eq!(ifinfomsg.ifi_change, libc::IFF_UP as libc::c_uint);
+
+        let ifinfomsg = LinkChanges::new().name("eth1").mac(vec![2, 0, 0, 0, 0, 1])
+            .into_ifinfomsg(4).unwrap();
+        assert_eq!(ifinfomsg.ifi_change, 0);
+        assert_eq!(ifinfomsg.rtattrs.len(), 2);
+
+        assert!(LinkChanges::new().name("a-very-long-name").into_ifinfomsg(4).is_err());
+    }
+
+    // Requires CAP_NET_ADMIN - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_link_set() {
+        let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let lo = Link::from_ifinfomsg(find_link(&mut sock, "lo").unwrap()).unwrap();
+        link_set(&mut sock, lo.index, LinkChanges::new().up().mtu(lo.mtu.unwrap())).unwrap();
+        assert_eq!(link_set(&mut sock, i32::max_value(), LinkChanges::new().up()).err()
+                       .and_then(|e| e.errno()), Some(libc::ENODEV));
+    }
+
+    #[test]
+    fn test_link_spec_veth() {
+        let ifinfomsg = LinkSpec::veth("veth0", "veth1").into_ifinfomsg().unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        ifinfomsg.serialize(&mut mem).unwrap();
+        let parsed = Ifinfomsg::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap();
+        assert_eq!(parsed.get_link_kind().unwrap(), Some("veth".to_string()));
+
+        let linkinfo = parsed.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Linkinfo).unwrap()
+            .get_nested::<IflaInfo>().unwrap();
+        let data = linkinfo.iter().find(|rta| rta.rta_type == IflaInfo::Data).unwrap()
+            .get_nested::<VethInfo>().unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].rta_type, VethInfo::Peer);
+        let peer = data[0].get_payload_with::<Ifinfomsg>(None).unwrap();
+        assert_eq!(Link::from_ifinfomsg(peer).unwrap().name, "veth1");
+    }
+
+    #[test]
+    fn test_link_spec_vlan() {
+        let ifinfomsg = LinkSpec::vlan("eth0.5", 2, 5).into_ifinfomsg().unwrap();
+        let link = ifinfomsg.rtattrs.iter().find(|rta| rta.rta_type == Ifla::Link).unwrap();
+        assert_eq!(link.get_payload_with::<u32>(None).unwrap(), 2);
+        assert_eq!(ifinfomsg.get_link_kind().unwrap(), Some("vlan".to_string()));
+    }
+
+    // Requires CAP_NET_ADMIN and the veth and bridge modules - run with
+    // `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_link_add_del() {
+        let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        link_add(&mut sock, LinkSpec::veth("neli-veth0", "neli-veth1")).unwrap();
+        let names = get_links(&mut sock).unwrap().into_iter().map(|link| link.name)
+            .collect::<Vec<_>>();
+        link_del(&mut sock, "neli-veth0").unwrap();
+        assert!(names.contains(&"neli-veth0".to_string()));
+        assert!(names.contains(&"neli-veth1".to_string()));
+        assert!(find_link(&mut sock, "neli-veth1").is_none());
+
+        link_add(&mut sock, LinkSpec::bridge("neli-br0")).unwrap();
+        let bridge = find_link(&mut sock, "neli-br0").unwrap();
+        link_del(&mut sock, bridge.ifi_index).unwrap();
+        assert_eq!(bridge.get_link_kind().unwrap(), Some("bridge".to_string()));
+    }
+
+    // Requires CAP_NET_ADMIN - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_ext_ack() {
+        let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        sock.set_ext_ack(true).unwrap();
+        let ifinfomsg = Ifinfomsg {
+            rtattrs: vec![
+                RtAttr::new(None, Ifla::Ifname, "neli-bogus0").unwrap(),
+                RtAttr::new_linkinfo("neli-bogus").unwrap(),
+            ],
+            ..Ifinfomsg::dump(Af::Unspec)
+        };
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Newlink,
+                                   vec![NlmF::Request, NlmF::Create, NlmF::Excl, NlmF::Ack],
+                                   None, None, ifinfomsg)).unwrap();
+        match sock.recv_ack(None) {
+            Err(NlError::ExtAck { errno: libc::EOPNOTSUPP, msg, .. }) => assert!(msg.is_some()),
+            Err(e) => panic!("Unexpected error {}", e),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    fn find_link(sock: &mut NlSocket<Rtm, Ifinfomsg>, name: &str) -> Option<Ifinfomsg> {
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Getlink, vec![NlmF::Request, NlmF::Dump], None,
+                                   None, Ifinfomsg::dump(Af::Unspec))).unwrap();
+        let mut found = None;
+        for msg in sock.iter() {
+            let ifinfomsg = msg.unwrap().nl_payload;
+            let matches = ifinfomsg.rtattrs.iter().any(|rta| {
+                rta.rta_type == Ifla::Ifname
+                    && rta.get_payload_with::<String>(Some(rta.rta_payload.len())).unwrap() == name
+            });
+            if matches {
+                found = Some(ifinfomsg);
+            }
+        }
+        found
+    }
+
+    // Requires CAP_NET_ADMIN - run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_dummy_link() {
+        let mut sock = NlSocket::<Rtm, Ifinfomsg>::connect(NlFamily::Route, None, Vec::new())
+            .unwrap();
+        let ifinfomsg = Ifinfomsg {
+            rtattrs: vec![
+                RtAttr::new(None, Ifla::Ifname, "neli-dummy0").unwrap(),
+                RtAttr::new_linkinfo("dummy").unwrap(),
+            ],
+            ..Ifinfomsg::dump(Af::Unspec)
+        };
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Newlink,
+                                   vec![NlmF::Request, NlmF::Create, NlmF::Excl, NlmF::Ack],
+                                   None, None, ifinfomsg)).unwrap();
+        sock.recv_ack(None).unwrap();
+
+        let link = find_link(&mut sock, "neli-dummy0").unwrap();
+        assert_eq!(link.get_link_kind().unwrap(), Some("dummy".to_string()));
+
+        let ifinfomsg = Ifinfomsg { ifi_index: link.ifi_index, ..Ifinfomsg::dump(Af::Unspec) };
+        sock.send_nl(Nlmsghdr::new(None, Rtm::Dellink, vec![NlmF::Request, NlmF::Ack], None,
+                                   None, ifinfomsg)).unwrap();
+        sock.recv_ack(None).unwrap();
+        assert!(find_link(&mut sock, "neli-dummy0").is_none());
     }
 }
@@ -0,0 +1,352 @@
+use std::io::{Read,Write};
+use std::mem;
+use std::net::IpAddr;
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+use libc;
+
+use Nl;
+use consts::{Af,InetDiag,NlmF,SockDiag,TcpState};
+use err::{SerError,DeError,NlError};
+use nl::Nlmsghdr;
+use rtnl::RtAttr;
+use socket::{recv_dump,send_nl,NlStream};
+
+/// Socket identity used to select a socket in requests and to describe it in responses
+/// (`struct inet_diag_sockid`)
+///
+/// Ports are stored in host byte order and converted to network byte order on the wire.
+/// Addresses are stored as the raw bytes of `__be32[4]` - an IPv4 address only occupies the
+/// first four bytes.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct InetDiagSockid {
+    /// Source port
+    pub idiag_sport: u16,
+    /// Destination port
+    pub idiag_dport: u16,
+    /// Source address
+    pub idiag_src: [u8; 16],
+    /// Destination address
+    pub idiag_dst: [u8; 16],
+    /// Index of the interface the socket is bound to
+    pub idiag_if: u32,
+    /// Kernel cookie identifying the socket - `[!0, !0]` (`INET_DIAG_NOCOOKIE`) when unknown
+    pub idiag_cookie: [u32; 2],
+}
+
+impl InetDiagSockid {
+    /// Length of the struct on the wire
+    pub const LEN: usize = mem::size_of::<u16>() * 2 + 16 * 2 + mem::size_of::<u32>() * 3;
+
+    /// Create a socket identity from addresses and ports
+    pub fn new(src: IpAddr, sport: u16, dst: IpAddr, dport: u16) -> Self {
+        InetDiagSockid {
+            idiag_sport: sport,
+            idiag_dport: dport,
+            idiag_src: addr_bytes(src),
+            idiag_dst: addr_bytes(dst),
+            idiag_if: 0,
+            idiag_cookie: [!0, !0],
+        }
+    }
+
+    /// Parse the source address according to `family`
+    pub fn get_src(&self, family: Af) -> Result<IpAddr, DeError> {
+        IpAddr::deserialize_with(&mut StreamReadBuffer::new(&self.idiag_src[..]), family)
+    }
+
+    /// Parse the destination address according to `family`
+    pub fn get_dst(&self, family: Af) -> Result<IpAddr, DeError> {
+        IpAddr::deserialize_with(&mut StreamReadBuffer::new(&self.idiag_dst[..]), family)
+    }
+}
+
+fn addr_bytes(addr: IpAddr) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    match addr {
+        IpAddr::V4(a) => bytes[..4].copy_from_slice(&a.octets()),
+        IpAddr::V6(a) => bytes.copy_from_slice(&a.octets()),
+    };
+    bytes
+}
+
+impl Nl for InetDiagSockid {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.idiag_sport.to_be().serialize(buf)?;
+        self.idiag_dport.to_be().serialize(buf)?;
+        buf.write_all(&self.idiag_src)?;
+        buf.write_all(&self.idiag_dst)?;
+        self.idiag_if.serialize(buf)?;
+        self.idiag_cookie[0].serialize(buf)?;
+        self.idiag_cookie[1].serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let idiag_sport = u16::from_be(u16::deserialize(buf)?);
+        let idiag_dport = u16::from_be(u16::deserialize(buf)?);
+        let mut idiag_src = [0u8; 16];
+        buf.read_exact(&mut idiag_src)?;
+        let mut idiag_dst = [0u8; 16];
+        buf.read_exact(&mut idiag_dst)?;
+        Ok(InetDiagSockid {
+            idiag_sport,
+            idiag_dport,
+            idiag_src,
+            idiag_dst,
+            idiag_if: u32::deserialize(buf)?,
+            idiag_cookie: [u32::deserialize(buf)?, u32::deserialize(buf)?],
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::LEN
+    }
+}
+
+/// Request for `SockDiag::ByFamily` messages selecting inet sockets (`struct inet_diag_req_v2`)
+#[derive(Debug,PartialEq)]
+pub struct InetDiagReqV2 {
+    /// Address family of the sockets
+    pub sdiag_family: Af,
+    /// IP protocol of the sockets such as `libc::IPPROTO_TCP`
+    pub sdiag_protocol: u8,
+    /// Bitmask of extensions to report where bit `n - 1` requests the `InetDiag` attribute
+    /// with value `n`
+    pub idiag_ext: u8,
+    /// Bitmask of the socket states to report where bit `n` selects the `TcpState` with
+    /// value `n`
+    pub idiag_states: u32,
+    /// Identity of the socket to look up - ignored for dump requests
+    pub id: InetDiagSockid,
+}
+
+impl InetDiagReqV2 {
+    /// Length of the struct on the wire
+    pub const LEN: usize = mem::size_of::<u8>() * 4 + mem::size_of::<u32>() + InetDiagSockid::LEN;
+
+    /// Create a request for a dump (`NlmF::Dump`) of all sockets of the given family and
+    /// protocol in one of `states`
+    pub fn dump(sdiag_family: Af, sdiag_protocol: u8, states: &[TcpState]) -> Self {
+        InetDiagReqV2 {
+            sdiag_family,
+            sdiag_protocol,
+            idiag_ext: 0,
+            idiag_states: states.iter().fold(0, |acc, state| {
+                acc | (1 << u8::from(state))
+            }),
+            id: InetDiagSockid::default(),
+        }
+    }
+
+    /// Request the given attribute in the responses
+    pub fn request_ext(&mut self, ext: InetDiag) {
+        let ext = libc::c_ushort::from(ext);
+        if (1..=8).contains(&ext) {
+            self.idiag_ext |= 1 << (ext - 1);
+        }
+    }
+}
+
+impl Nl for InetDiagReqV2 {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.sdiag_family.serialize(buf)?;
+        self.sdiag_protocol.serialize(buf)?;
+        self.idiag_ext.serialize(buf)?;
+        // Padding after idiag_ext
+        0u8.serialize(buf)?;
+        self.idiag_states.serialize(buf)?;
+        self.id.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(InetDiagReqV2 {
+            sdiag_family: Af::deserialize(buf)?,
+            sdiag_protocol: u8::deserialize(buf)?,
+            idiag_ext: {
+                let idiag_ext = u8::deserialize(buf)?;
+                u8::deserialize(buf)?;
+                idiag_ext
+            },
+            idiag_states: u32::deserialize(buf)?,
+            id: InetDiagSockid::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::LEN
+    }
+}
+
+/// Response describing a single inet socket (`struct inet_diag_msg`)
+#[derive(Debug,PartialEq)]
+pub struct InetDiagMsg {
+    /// Address family of the socket
+    pub idiag_family: Af,
+    /// State of the socket
+    pub idiag_state: TcpState,
+    /// Type of the pending timer
+    pub idiag_timer: u8,
+    /// Number of retransmits of the pending timer
+    pub idiag_retrans: u8,
+    /// Identity of the socket
+    pub id: InetDiagSockid,
+    /// Time until the pending timer expires in milliseconds
+    pub idiag_expires: u32,
+    /// Bytes in the receive queue or pending connections for listening sockets
+    pub idiag_rqueue: u32,
+    /// Bytes in the send queue or the backlog for listening sockets
+    pub idiag_wqueue: u32,
+    /// User ID of the owner of the socket
+    pub idiag_uid: u32,
+    /// Inode of the socket
+    pub idiag_inode: u32,
+    /// Attributes requested with `InetDiagReqV2.idiag_ext`
+    pub rtattrs: Vec<RtAttr<InetDiag>>,
+}
+
+impl InetDiagMsg {
+    /// Length of the fixed fields preceding the attributes
+    pub const LEN: usize = mem::size_of::<u8>() * 4 + InetDiagSockid::LEN
+        + mem::size_of::<u32>() * 5;
+}
+
+impl Nl for InetDiagMsg {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, buf: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.idiag_family.serialize(buf)?;
+        self.idiag_state.serialize(buf)?;
+        self.idiag_timer.serialize(buf)?;
+        self.idiag_retrans.serialize(buf)?;
+        self.id.serialize(buf)?;
+        self.idiag_expires.serialize(buf)?;
+        self.idiag_rqueue.serialize(buf)?;
+        self.idiag_wqueue.serialize(buf)?;
+        self.idiag_uid.serialize(buf)?;
+        self.idiag_inode.serialize(buf)?;
+        self.rtattrs.serialize(buf)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(buf: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(InetDiagMsg {
+            idiag_family: Af::deserialize(buf)?,
+            idiag_state: TcpState::deserialize(buf)?,
+            idiag_timer: u8::deserialize(buf)?,
+            idiag_retrans: u8::deserialize(buf)?,
+            id: InetDiagSockid::deserialize(buf)?,
+            idiag_expires: u32::deserialize(buf)?,
+            idiag_rqueue: u32::deserialize(buf)?,
+            idiag_wqueue: u32::deserialize(buf)?,
+            idiag_uid: u32::deserialize(buf)?,
+            idiag_inode: u32::deserialize(buf)?,
+            rtattrs: Vec::<RtAttr<InetDiag>>::deserialize(buf)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        Self::LEN + self.rtattrs.size()
+    }
+}
+
+/// Dump all sockets of the given family and protocol in one of `states` over any `NlStream`
+/// connected to `NlFamily::SockOrInetDiag` like `ss` does
+pub fn dump_inet_sockets<S>(stream: &mut S, family: Af, protocol: u8, states: &[TcpState])
+        -> Result<Vec<InetDiagMsg>, NlError> where S: NlStream {
+    let req = Nlmsghdr::new(None, SockDiag::ByFamily, vec![NlmF::Request, NlmF::Dump], None,
+                            None, InetDiagReqV2::dump(family, protocol, states));
+    send_nl(stream, req)?;
+    Ok(recv_dump::<_, SockDiag, InetDiagMsg>(stream)?.into_iter()
+        .map(|msg| msg.nl_payload)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+    use std::net::{Ipv4Addr,TcpListener};
+
+    use byteorder::{NativeEndian,WriteBytesExt};
+
+    use consts::NlFamily;
+    use socket::NlSocket;
+
+    #[test]
+    fn test_inet_diag_req_serialize() {
+        let mut req = InetDiagReqV2::dump(Af::Inet, libc::IPPROTO_TCP as u8,
+                                          &[TcpState::Established, TcpState::Listen]);
+        req.request_ext(InetDiag::Info);
+        let nlhdr = Nlmsghdr::new(None, SockDiag::ByFamily, vec![NlmF::Request, NlmF::Dump], None,
+                                  None, req);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        nlhdr.serialize(&mut mem).unwrap();
+
+        let mut expected = Cursor::new(Vec::new());
+        expected.write_u32::<NativeEndian>(72).unwrap();
+        expected.write_u16::<NativeEndian>(20).unwrap();
+        expected.write_u16::<NativeEndian>(0x301).unwrap();
+        expected.write_u32::<NativeEndian>(0).unwrap();
+        expected.write_u32::<NativeEndian>(0).unwrap();
+        expected.write_u8(libc::AF_INET as u8).unwrap();
+        expected.write_u8(libc::IPPROTO_TCP as u8).unwrap();
+        expected.write_u8(0x2).unwrap();
+        expected.write_u8(0).unwrap();
+        expected.write_u32::<NativeEndian>((1 << 1) | (1 << 10)).unwrap();
+        expected.write_all(&[0u8; 48]).unwrap();
+        assert_eq!(mem.as_ref(), expected.get_ref().as_slice());
+        assert_eq!(nlhdr.nl_len as usize, mem.as_ref().len());
+    }
+
+    #[test]
+    fn test_inet_diag_sockid() {
+        let id = InetDiagSockid::new(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)), 22,
+                                     IpAddr::from(Ipv4Addr::new(10, 0, 0, 2)), 40000);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        id.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref().len(), InetDiagSockid::LEN);
+
+        let mut expected = Cursor::new(Vec::new());
+        // Ports in network byte order
+        expected.write_all(&[0, 22, 0x9c, 0x40]).unwrap();
+        expected.write_all(&[10, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        expected.write_all(&[10, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        expected.write_u32::<NativeEndian>(0).unwrap();
+        expected.write_u32::<NativeEndian>(!0).unwrap();
+        expected.write_u32::<NativeEndian>(!0).unwrap();
+        assert_eq!(mem.as_ref(), expected.get_ref().as_slice());
+
+        let parsed = InetDiagSockid::deserialize(&mut StreamReadBuffer::new(mem.as_ref()))
+            .unwrap();
+        assert_eq!(parsed, id);
+        assert_eq!(parsed.get_src(Af::Inet).unwrap(), IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(parsed.get_dst(Af::Inet).unwrap(), IpAddr::from(Ipv4Addr::new(10, 0, 0, 2)));
+    }
+
+    #[test]
+    fn test_inet_diag_dump_listen() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut sock = NlSocket::<SockDiag, InetDiagMsg>::connect(NlFamily::SockOrInetDiag, None,
+                                                                  Vec::new()).unwrap();
+        let found = dump_inet_sockets(&mut sock, Af::Inet, libc::IPPROTO_TCP as u8,
+                                      &[TcpState::Listen]).unwrap()
+            .into_iter()
+            .find(|diag| diag.id.idiag_sport == port);
+        let diag = found.unwrap();
+        assert_eq!(diag.idiag_state, TcpState::Listen);
+        assert_eq!(diag.id.get_src(Af::Inet).unwrap(), IpAddr::from(Ipv4Addr::LOCALHOST));
+        assert!(diag.idiag_inode != 0);
+    }
+}
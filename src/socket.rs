@@ -7,37 +7,219 @@
 //! of netlink code.
 
 use std::io;
-use std::os::unix::io::{AsRawFd,IntoRawFd,RawFd};
+use std::os::unix::io::{AsRawFd,FromRawFd,IntoRawFd,RawFd};
 use std::marker::PhantomData;
 use std::mem::{zeroed,size_of};
+use std::ptr;
+use std::time::Duration;
 
 use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+use byteorder::{ByteOrder,NativeEndian};
 use libc::{self,c_int,c_void};
 
 use {Nl,MAX_NL_LENGTH};
-use err::{NlError,Nlmsgerr};
-use consts::{self,AddrFamily,CtrlCmd,CtrlAttr,CtrlAttrMcastGrp,GenlId,NlmF,NlFamily,NlType};
-use genl::Genlmsghdr;
-use nlattr::Nlattr;
-use nl::Nlmsghdr;
+use err::{DeError,NlAck,NlError,Nlmsgerr,SerError};
+use consts::{self,checked_alignto,AddrFamily,CtrlCmd,CtrlAttr,GenlFamilyId,GenlId,McastGroup,
+             NlFlags,NlmF,NlFamily,NlmsgerrAttr,NlType,Rtmgrp};
+use genl::{get_mcast_groups,GenlFamilyInfo,Genlmsghdr};
+use nlattr::{AttrHandle,Nlattr};
+use nl::{Nlmsghdr,RawPayload};
+
+/// Sending and receiving of single netlink datagrams - implemented by `NlSocket` and accepted
+/// by the stream helpers in this module such as `resolve_genl_family` so that code using them
+/// can be tested against an in-memory mock instead of a kernel socket
+pub trait NlStream {
+    /// Send a single datagram and return the number of bytes sent
+    fn send(&mut self, buf: &[u8]) -> Result<usize, io::Error>;
+
+    /// Receive a single datagram into `buf` and return the number of bytes received - a
+    /// datagram larger than `buf` may be reported by returning its full length, which the
+    /// stream helpers return as `NlError::Truncated`
+    fn recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error>;
+
+    /// Receive a single datagram like `recv` without waiting for one to arrive - used to
+    /// drain the remainder of a failed dump. Defaults to `recv`.
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.recv(buf)
+    }
+
+    /// Size of the buffer the stream helpers receive datagrams into when no size is given
+    fn recv_buffer_size(&self) -> usize {
+        MAX_NL_LENGTH
+    }
+
+    /// Whether error messages received from this stream omit the payload of the failed
+    /// request even if `NlmF::Capped` is not set - see `NlSocket::set_cap_ack`
+    fn cap_ack(&self) -> bool {
+        false
+    }
+}
+
+/// Credentials of the sender of a message (`struct ucred`) received with `SO_PASSCRED` set
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Ucred {
+    /// Process ID of the sender - 0 for messages from the kernel
+    pub pid: libc::pid_t,
+    /// User ID of the sender
+    pub uid: libc::uid_t,
+    /// Group ID of the sender
+    pub gid: libc::gid_t,
+}
+
+/// Multicast group bitmask as used in `sockaddr_nl.nl_groups` where bit `n - 1` is set when
+/// subscribed to group `n`
+#[derive(Clone,Copy,Debug,Default,PartialEq)]
+pub struct GroupMask(pub u32);
+
+impl GroupMask {
+    /// Build a mask from 1-based group numbers - groups outside of `1..=32` cannot be
+    /// represented in the mask and are ignored
+    pub fn from_groups(groups: &[u32]) -> Self {
+        GroupMask(groups.iter().filter(|g| (1..=32).contains(*g)).fold(0, |acc, g| {
+            acc | (1 << (g - 1))
+        }))
+    }
+
+    /// Check whether the given 1-based group is set in the mask
+    pub fn contains(&self, group: u32) -> bool {
+        (1..=32).contains(&group) && self.0 & (1 << (group - 1)) != 0
+    }
+
+    /// Iterate over the 1-based group numbers set in the mask in ascending order
+    pub fn iter(&self) -> GroupMaskIter {
+        GroupMaskIter { mask: self.0 }
+    }
+}
+
+impl From<u32> for GroupMask {
+    fn from(v: u32) -> Self {
+        GroupMask(v)
+    }
+}
+
+impl From<GroupMask> for u32 {
+    fn from(v: GroupMask) -> Self {
+        v.0
+    }
+}
+
+/// Netlink socket address (`struct sockaddr_nl`) as used to bind and address sockets
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct NlSockAddr {
+    /// Address family - always `AF_NETLINK` for valid addresses
+    pub nl_family: libc::sa_family_t,
+    /// Padding that should be zero - always zero when converted from or to
+    /// `libc::sockaddr_nl`, which keeps its padding private
+    pub nl_pad: u16,
+    /// Port ID of the socket - 0 addresses the kernel
+    pub nl_pid: u32,
+    /// Multicast groups the socket is subscribed to or sends to
+    pub nl_groups: GroupMask,
+}
+
+impl NlSockAddr {
+    /// Create an `AF_NETLINK` address for the given port ID and multicast groups
+    pub fn new(nl_pid: u32, nl_groups: GroupMask) -> Self {
+        NlSockAddr {
+            nl_family: libc::c_int::from(AddrFamily::Netlink) as libc::sa_family_t,
+            nl_pad: 0,
+            nl_pid,
+            nl_groups,
+        }
+    }
+}
+
+impl From<libc::sockaddr_nl> for NlSockAddr {
+    fn from(addr: libc::sockaddr_nl) -> Self {
+        NlSockAddr {
+            nl_family: addr.nl_family,
+            nl_pad: 0,
+            nl_pid: addr.nl_pid,
+            nl_groups: GroupMask(addr.nl_groups),
+        }
+    }
+}
+
+impl From<NlSockAddr> for libc::sockaddr_nl {
+    fn from(addr: NlSockAddr) -> Self {
+        let mut nladdr = unsafe { zeroed::<libc::sockaddr_nl>() };
+        nladdr.nl_family = addr.nl_family;
+        nladdr.nl_pid = addr.nl_pid;
+        nladdr.nl_groups = addr.nl_groups.into();
+        nladdr
+    }
+}
+
+impl Nl for NlSockAddr {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.nl_family.serialize(mem)?;
+        self.nl_pad.serialize(mem)?;
+        self.nl_pid.serialize(mem)?;
+        self.nl_groups.0.serialize(mem)?;
+        Ok(())
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        Ok(NlSockAddr {
+            nl_family: libc::sa_family_t::deserialize(mem)?,
+            nl_pad: u16::deserialize(mem)?,
+            nl_pid: u32::deserialize(mem)?,
+            nl_groups: GroupMask(u32::deserialize(mem)?),
+        })
+    }
+
+    fn size(&self) -> usize {
+        size_of::<libc::sockaddr_nl>()
+    }
+}
+
+/// Iterator over the groups in a `GroupMask`
+pub struct GroupMaskIter {
+    mask: u32,
+}
+
+impl Iterator for GroupMaskIter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.mask == 0 {
+            return None;
+        }
+        let bit = self.mask.trailing_zeros();
+        self.mask &= !(1 << bit);
+        Some(bit + 1)
+    }
+}
 
 /// Handle for the socket file descriptor
 pub struct NlSocket<T, P> {
     fd: c_int,
+    cap_ack: bool,
+    recv_buf_sz: usize,
     data_type: PhantomData<T>,
     data_payload: PhantomData<P>,
 }
 
 impl<T, P> NlSocket<T, P> where T: NlType {
-    /// Wrapper around `socket()` syscall filling in the netlink-specific information
-    pub fn new(proto: NlFamily) -> Result<Self, io::Error> {
+    /// Wrapper around `socket()` syscall filling in the netlink-specific information - `proto`
+    /// is an `NlFamily` or the raw protocol number of a family it does not define
+    pub fn new<F>(proto: F) -> Result<Self, io::Error> where F: Into<NlFamily> {
         let fd = match unsafe {
-            libc::socket(AddrFamily::Netlink.into(), libc::SOCK_RAW, proto.into())
+            libc::socket(AddrFamily::Netlink.into(), libc::SOCK_RAW, proto.into().into())
         } {
             i if i >= 0 => Ok(i),
             _ => Err(io::Error::last_os_error()),
         }?;
-        Ok(NlSocket { fd, data_type: PhantomData, data_payload: PhantomData })
+        Ok(NlSocket {
+            fd,
+            cap_ack: false,
+            recv_buf_sz: MAX_NL_LENGTH,
+            data_type: PhantomData,
+            data_payload: PhantomData,
+        })
     }
 
     /// Set underlying socket file descriptor to be blocking
@@ -68,15 +250,120 @@ impl<T, P> NlSocket<T, P> where T: NlType {
         Ok(is_blocking)
     }
 
+    /// Wait until the socket is readable using `poll()` - returns `false` if `timeout` elapsed
+    /// first and never times out if `timeout` is `None`
+    pub fn poll_readable(&self, timeout: Option<Duration>) -> Result<bool, io::Error> {
+        let timeout_ms = match timeout {
+            Some(t) => {
+                let ms = t.as_secs().saturating_mul(1000)
+                    .saturating_add(u64::from(t.subsec_nanos() / 1_000_000));
+                if ms > c_int::max_value() as u64 { c_int::max_value() } else { ms as c_int }
+            },
+            None => -1,
+        };
+        let mut pfd = libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 };
+        match unsafe { libc::poll(&mut pfd, 1, timeout_ms) } {
+            i if i < 0 => Err(io::Error::last_os_error()),
+            0 => Ok(false),
+            _ => Ok(pfd.revents & libc::POLLIN == libc::POLLIN),
+        }
+    }
+
+    fn set_sockopt_int(&mut self, level: c_int, opt: c_int, val: c_int) -> Result<(), io::Error> {
+        match unsafe {
+            libc::setsockopt(self.fd, level, opt, &val as *const _ as *const c_void,
+                             size_of::<c_int>() as libc::socklen_t)
+        } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    fn set_sockopt_bool(&mut self, opt: c_int, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_int(libc::SOL_NETLINK, opt, if enable { 1 } else { 0 })
+    }
+
+    /// Join a multicast group with `NETLINK_ADD_MEMBERSHIP` - accepts group numbers such as
+    /// `Rtnlgrp` variants or IDs from `resolve_nl_mcast_group`, not `Rtmgrp` bitmasks
+    pub fn add_mcast_membership<G>(&mut self, group: G) -> Result<(), io::Error>
+            where G: McastGroup {
+        self.set_sockopt_int(libc::SOL_NETLINK, libc::NETLINK_ADD_MEMBERSHIP, group.into() as c_int)
+    }
+
+    /// Leave a multicast group with `NETLINK_DROP_MEMBERSHIP`
+    pub fn drop_mcast_membership<G>(&mut self, group: G) -> Result<(), io::Error>
+            where G: McastGroup {
+        self.set_sockopt_int(libc::SOL_NETLINK, libc::NETLINK_DROP_MEMBERSHIP,
+                             group.into() as c_int)
+    }
+
+    /// Set `NETLINK_BROADCAST_ERROR` to report delivery failures of broadcast messages sent
+    /// from this socket
+    pub fn set_broadcast_error(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_bool(libc::NETLINK_BROADCAST_ERROR, enable)
+    }
+
+    /// Set `NETLINK_NO_ENOBUFS` to stop the kernel from reporting `ENOBUFS` when multicast
+    /// messages are dropped because the receive buffer is full
+    pub fn set_no_enobufs(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_bool(libc::NETLINK_NO_ENOBUFS, enable)
+    }
+
+    /// Set `NETLINK_LISTEN_ALL_NSID` to receive multicast messages from every network namespace
+    /// that has an ID assigned in the namespace of this socket - use `recv_into_nsid` or
+    /// `recv_nl_nsid` to find out which namespace a message originated from
+    pub fn set_listen_all_nsid(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_bool(NETLINK_LISTEN_ALL_NSID, enable)
+    }
+
+    /// Set `NETLINK_EXT_ACK` to have the kernel attach a human readable reason to error
+    /// responses - these are reported as `NlError::ExtAck`
+    pub fn set_ext_ack(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_bool(NETLINK_EXT_ACK, enable)
+    }
+
+    /// Set `NETLINK_CAP_ACK` to have the kernel omit the payload of the failed request from
+    /// error messages - kernels before 4.12 do this without setting `NlmF::Capped` so the
+    /// setting is also remembered for parsing received errors
+    pub fn set_cap_ack(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_bool(NETLINK_CAP_ACK, enable)?;
+        self.cap_ack = enable;
+        Ok(())
+    }
+
+    /// Set the size of the buffer datagrams are received into when no size is given - defaults
+    /// to `MAX_NL_LENGTH`. Datagrams that do not fit are dropped and reported as
+    /// `NlError::Truncated` with the size that would have been needed.
+    pub fn set_recv_buffer_size(&mut self, size: usize) {
+        self.recv_buf_sz = size;
+    }
+
+    /// Set `SO_PASSCRED` to receive the credentials of the sender with each message - use
+    /// `recv_into_creds` or `recv_with_creds` to read them
+    pub fn set_passcred(&mut self, enable: bool) -> Result<(), io::Error> {
+        self.set_sockopt_int(libc::SOL_SOCKET, libc::SO_PASSCRED, if enable { 1 } else { 0 })
+    }
+
     /// Use this function to bind to a netlink ID and subscribe to groups. See netlink(7)
-    /// man pages for more information on netlink IDs and groups.
+    /// man pages for more information on netlink IDs and groups. Groups above 32 do not fit in
+    /// the bind mask and are joined with `add_mcast_membership` after binding.
     pub fn bind(&mut self, pid: Option<u32>, groups: Vec<u32>) -> Result<(), io::Error> {
-        let mut nladdr = unsafe { zeroed::<libc::sockaddr_nl>() };
-        nladdr.nl_family = libc::c_int::from(AddrFamily::Netlink) as u16;
-        nladdr.nl_pid = pid.unwrap_or(0);
-        nladdr.nl_groups = groups.into_iter().fold(0, |acc, next| {
-            acc | (1 << (next - 1))
-        });
+        self.bind_mask(pid, GroupMask::from_groups(&groups))?;
+        for group in groups.into_iter().filter(|g| *g > 32) {
+            self.add_mcast_membership(group)?;
+        }
+        Ok(())
+    }
+
+    /// Bind to a netlink ID and subscribe to route netlink groups given as a legacy `Rtmgrp`
+    /// bitmask - use `add_mcast_membership` with `Rtnlgrp` for groups without a bitmask value
+    pub fn bind_rtmgrp(&mut self, pid: Option<u32>, groups: NlFlags<Rtmgrp>)
+            -> Result<(), io::Error> {
+        self.bind_mask(pid, GroupMask(groups.bits()))
+    }
+
+    fn bind_mask(&mut self, pid: Option<u32>, groups: GroupMask) -> Result<(), io::Error> {
+        let nladdr = libc::sockaddr_nl::from(NlSockAddr::new(pid.unwrap_or(0), groups));
         match unsafe {
             libc::bind(self.fd, &nladdr as *const _ as *const libc::sockaddr,
                        size_of::<libc::sockaddr_nl>() as u32)
@@ -97,6 +384,33 @@ impl<T, P> NlSocket<T, P> where T: NlType {
         }
     }
 
+    /// Send message encoded as byte slice to the socket with the given address - a port ID of 0
+    /// addresses the kernel
+    pub fn send_to<B>(&mut self, buf: B, addr: NlSockAddr, flags: i32)
+            -> Result<libc::ssize_t, io::Error> where B: AsRef<[u8]> {
+        let nladdr = libc::sockaddr_nl::from(addr);
+        match unsafe {
+            libc::sendto(self.fd, buf.as_ref() as *const _ as *const c_void, buf.as_ref().len(),
+                         flags, &nladdr as *const _ as *const libc::sockaddr,
+                         size_of::<libc::sockaddr_nl>() as libc::socklen_t)
+        } {
+            i if i >= 0 => Ok(i),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    /// Get the address the socket is bound to, including the port ID assigned by the kernel
+    pub fn local_addr(&self) -> Result<NlSockAddr, io::Error> {
+        let mut nladdr = unsafe { zeroed::<libc::sockaddr_nl>() };
+        let mut len = size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+        match unsafe {
+            libc::getsockname(self.fd, &mut nladdr as *mut _ as *mut libc::sockaddr, &mut len)
+        } {
+            0 => Ok(NlSockAddr::from(nladdr)),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
     /// Receive message encoded as byte slice from the netlink socket
     pub fn recv<'a, B>(&mut self, mut buf: B, flags: i32) -> Result<libc::ssize_t, io::Error> where B: AsMut<[u8]> {
         match unsafe {
@@ -107,99 +421,588 @@ impl<T, P> NlSocket<T, P> where T: NlType {
         }
     }
 
+    /// Receive a single datagram into a caller-supplied buffer and return the filled part of
+    /// the buffer - this allows reusing one buffer across receives instead of allocating.
+    /// `ENOBUFS` is returned as `NlError::Overrun` and a datagram larger than `buf` as
+    /// `NlError::Truncated`.
+    pub fn recv_into<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], NlError> {
+        let len = match unsafe {
+            libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), libc::MSG_TRUNC)
+        } {
+            i if i >= 0 => i as usize,
+            _ => return Err(recv_error(io::Error::last_os_error())),
+        };
+        if len > buf.len() {
+            return Err(NlError::Truncated { needed: len });
+        }
+        Ok(&buf[..len])
+    }
+
+    /// Receive a single datagram into a caller-supplied buffer like `recv_into` and also
+    /// return the ID of the network namespace it originated from - the ID is only reported
+    /// when `NETLINK_LISTEN_ALL_NSID` is set
+    pub fn recv_into_nsid<'a>(&self, buf: &'a mut [u8])
+            -> Result<(&'a [u8], Option<i32>), NlError> {
+        let mut cmsgs = [0u8; CMSG_BUF_LEN];
+        let (len, cmsgs_len) = self.recvmsg(buf, &mut cmsgs)?;
+        Ok((&buf[..len], parse_nsid(&cmsgs[..cmsgs_len])))
+    }
+
+    /// Receive a single datagram into a caller-supplied buffer like `recv_into` and also
+    /// return the credentials of the sender - the credentials are only reported when
+    /// `SO_PASSCRED` is set
+    pub fn recv_into_creds<'a>(&self, buf: &'a mut [u8])
+            -> Result<(&'a [u8], Option<Ucred>), NlError> {
+        let mut cmsgs = [0u8; CMSG_BUF_LEN];
+        let (len, cmsgs_len) = self.recvmsg(buf, &mut cmsgs)?;
+        Ok((&buf[..len], parse_creds(&cmsgs[..cmsgs_len])))
+    }
+
+    // Receive a datagram with `recvmsg()` returning the length of the data and of the
+    // ancillary data written to `cmsgs` - datagrams larger than `buf` are reported as
+    // `NlError::Truncated`
+    fn recvmsg(&self, buf: &mut [u8], cmsgs: &mut [u8]) -> Result<(usize, usize), NlError> {
+        let mut iov = libc::iovec { iov_base: buf.as_mut_ptr() as *mut c_void, iov_len: buf.len() };
+        let mut msg = unsafe { zeroed::<libc::msghdr>() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = cmsgs.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = cmsgs.len() as _;
+        let len = match unsafe { libc::recvmsg(self.fd, &mut msg, libc::MSG_TRUNC) } {
+            i if i >= 0 => i as usize,
+            _ => return Err(recv_error(io::Error::last_os_error())),
+        };
+        if msg.msg_flags & libc::MSG_TRUNC != 0 || len > buf.len() {
+            return Err(NlError::Truncated { needed: len });
+        }
+        Ok((len, (msg.msg_controllen as usize).min(cmsgs.len())))
+    }
+
     /// Equivalent of `socket` and `bind` calls.
-    pub fn connect(proto: NlFamily, pid: Option<u32>, groups: Vec<u32>)
-                   -> Result<Self, io::Error> {
+    pub fn connect<F>(proto: F, pid: Option<u32>, groups: Vec<u32>)
+                      -> Result<Self, io::Error> where F: Into<NlFamily> {
         let mut s = try!(NlSocket::new(proto));
         try!(s.bind(pid, groups));
         Ok(s)
     }
 }
 
+impl<T, P> NlStream for NlSocket<T, P> where T: NlType {
+    fn send(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        NlSocket::send(self, buf, 0).map(|i| i as usize)
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // MSG_TRUNC makes the kernel return the full length of a datagram that did not fit
+        NlSocket::recv(self, buf, libc::MSG_TRUNC).map(|i| i as usize)
+    }
+
+    fn try_recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        NlSocket::recv(self, buf, libc::MSG_TRUNC | libc::MSG_DONTWAIT).map(|i| i as usize)
+    }
+
+    fn recv_buffer_size(&self) -> usize {
+        self.recv_buf_sz
+    }
+
+    fn cap_ack(&self) -> bool {
+        self.cap_ack
+    }
+}
+
 impl<T, P> NlSocket<T, P> where T: Nl + NlType, P: Nl {
     /// Convenience function to send an `Nlmsghdr` struct
     pub fn send_nl(&mut self, msg: Nlmsghdr<T, P>) -> Result<(), NlError> {
-        let mut mem = StreamWriteBuffer::new_growable(Some(msg.asize()));
-        msg.serialize(&mut mem)?;
-        self.send(mem, 0)?;
-        Ok(())
+        send_nl(self, msg)
     }
 
-    /// Convenience function to receive an `Nlmsghdr` struct
+    /// Convenience function to receive an `Nlmsghdr` struct - `ENOBUFS` is returned as
+    /// `NlError::Overrun` and a datagram larger than `buf_sz` as `NlError::Truncated`
     pub fn recv_nl(&mut self, buf_sz: Option<usize>) -> Result<Nlmsghdr<T, P>, NlError> {
-        let mut mem = vec![0; buf_sz.unwrap_or(MAX_NL_LENGTH)];
-        let mem_read = self.recv(&mut mem, 0)?;
-        mem.truncate(mem_read as usize);
-        Ok(Nlmsghdr::<T, P>::deserialize(&mut StreamReadBuffer::new(mem))?)
+        recv_nl(self, buf_sz)
     }
 
     /// Convenience function to receive an `Nlmsghdr` struct with function type parameters
     /// that determine deserialization type
     pub fn recv_nl_typed<TT, PP>(&mut self, buf_sz: Option<usize>)
             -> Result<Nlmsghdr<TT, PP>, NlError> where TT: NlType, PP: Nl {
-        let mut mem = vec![0; buf_sz.unwrap_or(MAX_NL_LENGTH)];
-        let mem_read = self.recv(&mut mem, 0)?;
-        mem.truncate(mem_read as usize);
-        Ok(Nlmsghdr::<TT, PP>::deserialize(&mut StreamReadBuffer::new(mem))?)
+        recv_nl(self, buf_sz)
+    }
+
+    /// Convenience function to receive an `Nlmsghdr` struct along with the ID of the network
+    /// namespace it originated from - see `recv_into_nsid`
+    pub fn recv_nl_nsid(&mut self, buf_sz: Option<usize>)
+            -> Result<(Nlmsghdr<T, P>, Option<i32>), NlError> {
+        let mut mem = vec![0; buf_sz.unwrap_or(self.recv_buf_sz)];
+        let (msg, nsid) = {
+            let (bytes, nsid) = self.recv_into_nsid(&mut mem)?;
+            (Nlmsghdr::<T, P>::deserialize(&mut StreamReadBuffer::new(bytes))?, nsid)
+        };
+        Ok((msg, nsid))
+    }
+
+    /// Convenience function to receive an `Nlmsghdr` struct along with the credentials of the
+    /// sender - see `recv_into_creds`
+    pub fn recv_with_creds(&mut self, buf_sz: Option<usize>)
+            -> Result<(Nlmsghdr<T, P>, Option<Ucred>), NlError> {
+        let mut mem = vec![0; buf_sz.unwrap_or(self.recv_buf_sz)];
+        let (msg, creds) = {
+            let (bytes, creds) = self.recv_into_creds(&mut mem)?;
+            (Nlmsghdr::<T, P>::deserialize(&mut StreamReadBuffer::new(bytes))?, creds)
+        };
+        Ok((msg, creds))
+    }
+
+    /// Return an iterator over the messages of a multipart response, receiving from the
+    /// socket as needed until `Nlmsg::Done` is found
+    pub fn iter<'a>(&'a mut self) -> NlMessageIter<'a, T, P> {
+        NlMessageIter { sock: self, parts: MultipartBuf::new() }
+    }
+
+    /// Consume an ACK and return it - if an error is received instead, the error code is
+    /// returned as `NlError::Errno` or `NlError::ExtAck` and by `NlError::errno` for both
+    pub fn recv_ack(&mut self, buf_sz: Option<usize>) -> Result<NlAck<T>, NlError> {
+        recv_ack(self, buf_sz)
+    }
+
+    /// Send a request and consume its acknowledgement - see `send_ack`
+    pub fn send_ack(&mut self, req: Nlmsghdr<T, P>) -> Result<NlAck<T>, NlError> {
+        send_ack(self, req)
     }
 
-    /// Consume an ACK and return an error if an ACK is not found
-    pub fn recv_ack(&mut self, buf_sz: Option<usize>) -> Result<(), NlError> {
-        let ack = self.recv_nl_typed::<consts::Nlmsg, Nlmsgerr<consts::Nlmsg>>(buf_sz)?;
-        if ack.nl_type == consts::Nlmsg::Error && ack.nl_payload.error == 0 {
-            Ok(())
-        } else {
-            Err(NlError::NoAck)
+    /// Send a dump request and collect every message of the response - see `dump`
+    pub fn dump(&mut self, req: Nlmsghdr<T, P>) -> Result<Vec<Nlmsghdr<T, P>>, NlError>
+            where P: Nl {
+        dump(self, req)
+    }
+}
+
+/// Send a dump request over any `NlStream` and collect the messages of the multipart
+/// response until `Nlmsg::Done`
+///
+/// `NlmF::Request` and `NlmF::Dump` are added to the flags of `req` if missing. An
+/// interrupted dump fails with `NlError::DumpInterrupted` and should be retried. The rest of
+/// a response that fails partway is received and discarded before the error is returned.
+pub fn dump<S, T, P>(stream: &mut S, mut req: Nlmsghdr<T, P>)
+        -> Result<Vec<Nlmsghdr<T, P>>, NlError> where S: NlStream, T: NlType, P: Nl {
+    add_missing_flags(&mut req, vec![NlmF::Request, NlmF::Dump]);
+    send_nl(stream, req)?;
+    recv_dump(stream)
+}
+
+/// Collect the messages of a multipart response until `Nlmsg::Done` from any `NlStream` -
+/// for dumps whose responses have a different payload type than the request
+pub fn recv_dump<S, T, P>(stream: &mut S) -> Result<Vec<Nlmsghdr<T, P>>, NlError>
+        where S: NlStream, T: NlType, P: Nl {
+    let mut parts = MultipartBuf::new();
+    let mut msgs = Vec::new();
+    while let Some(msg) = parts.next(stream) {
+        msgs.push(msg?);
+    }
+    Ok(msgs)
+}
+
+/// Send a request over any `NlStream` and consume its acknowledgement
+///
+/// `NlmF::Request` and `NlmF::Ack` are added to the flags of `req` if missing. A kernel error
+/// is returned as `NlError::Errno`, or as `NlError::ExtAck` carrying the reason if
+/// `NlSocket::set_ext_ack` was enabled.
+pub fn send_ack<S, T, P>(stream: &mut S, mut req: Nlmsghdr<T, P>) -> Result<NlAck<T>, NlError>
+        where S: NlStream, T: NlType, P: Nl {
+    add_missing_flags(&mut req, vec![NlmF::Request, NlmF::Ack]);
+    send_nl(stream, req)?;
+    recv_ack(stream, None)
+}
+
+// Set each of `flags` on `req` in addition to the flags it already has
+fn add_missing_flags<T, P>(req: &mut Nlmsghdr<T, P>, flags: Vec<NlmF>) {
+    for flag in flags {
+        req.nl_flags.insert(flag);
+    }
+}
+
+/// Send an `Nlmsghdr` struct over any `NlStream`
+pub fn send_nl<S, T, P>(stream: &mut S, msg: Nlmsghdr<T, P>) -> Result<(), NlError>
+        where S: NlStream, T: NlType, P: Nl {
+    stream.send(&msg.serialize_to_vec()?)?;
+    Ok(())
+}
+
+/// Receive an `Nlmsghdr` struct from any `NlStream` - `ENOBUFS` is returned as
+/// `NlError::Overrun` and a datagram larger than `buf_sz` as `NlError::Truncated`
+pub fn recv_nl<S, T, P>(stream: &mut S, buf_sz: Option<usize>) -> Result<Nlmsghdr<T, P>, NlError>
+        where S: NlStream, T: NlType, P: Nl {
+    let mem = recv_datagram(stream, buf_sz)?;
+    Ok(Nlmsghdr::<T, P>::deserialize(&mut StreamReadBuffer::new(mem))?)
+}
+
+/// Receive a single datagram from any `NlStream` without parsing it as netlink messages -
+/// for families such as `NlFamily::KobjectUevent` whose datagrams have no `Nlmsghdr`.
+/// Without `buf_sz` the datagram is received into a buffer of `NlStream::recv_buffer_size`.
+/// `ENOBUFS` is returned as `NlError::Overrun` and a datagram larger than the buffer as
+/// `NlError::Truncated`.
+pub fn recv_datagram<S>(stream: &mut S, buf_sz: Option<usize>) -> Result<Vec<u8>, NlError>
+        where S: NlStream {
+    let mut mem = vec![0; buf_sz.unwrap_or_else(|| stream.recv_buffer_size())];
+    let mem_read = stream.recv(&mut mem).map_err(recv_error)?;
+    if mem_read > mem.len() {
+        return Err(NlError::Truncated { needed: mem_read });
+    }
+    mem.truncate(mem_read);
+    Ok(mem)
+}
+
+/// Consume an ACK from any `NlStream` and return it - if an error is received instead, the
+/// error code is returned as `NlError::Errno` or `NlError::ExtAck` and by `NlError::errno`
+/// for both
+pub fn recv_ack<S, T>(stream: &mut S, buf_sz: Option<usize>) -> Result<NlAck<T>, NlError>
+        where S: NlStream, T: NlType {
+    let msg = recv_nl::<S, consts::Nlmsg, RawPayload>(stream, buf_sz)?;
+    if msg.nl_type != consts::Nlmsg::Error {
+        return Err(NlError::NoAck);
+    }
+    let err = parse_nlmsgerr::<T>(&msg.nl_flags, &msg.nl_payload.0, stream.cap_ack())?;
+    err.into_ack()
+}
+
+fn get_genl_family<S>(stream: &mut S, family_name: &str)
+        -> Result<Nlmsghdr<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>, NlError> where S: NlStream {
+    let attrs = vec![Nlattr::new_str_payload(None, CtrlAttr::FamilyName, family_name)?];
+    let genlhdr = Genlmsghdr::new(CtrlCmd::Getfamily, 2, attrs)?;
+    let nlhdr = Nlmsghdr::new(None, GenlId::Ctrl,
+                           vec![NlmF::Request], None, None, genlhdr);
+    send_nl(stream, nlhdr)?;
+
+    Ok(recv_nl(stream, Some(4096))?)
+}
+
+/// Resolve a generic netlink family name to its numeric netlink ID over any `NlStream`
+/// connected to `NlFamily::Generic` - use `get_genl_family_info` to also get the version,
+/// header size and multicast groups of the family from the same reply
+pub fn resolve_genl_family<S>(stream: &mut S, family_name: &str)
+        -> Result<GenlFamilyId, NlError> where S: NlStream {
+    let nlhdr = get_genl_family(stream, family_name)?;
+    let mut handle = nlhdr.nl_payload.get_attr_handle();
+    Ok(handle.get_payload_with::<GenlFamilyId>(CtrlAttr::FamilyId, None)?)
+}
+
+/// Look up the ID, operations and multicast groups of a generic netlink family over any
+/// `NlStream` connected to `NlFamily::Generic`
+pub fn get_genl_family_info<S>(stream: &mut S, family_name: &str)
+        -> Result<GenlFamilyInfo, NlError> where S: NlStream {
+    let nlhdr = get_genl_family(stream, family_name)?;
+    Ok(GenlFamilyInfo::from_attr_handle(nlhdr.nl_payload.get_attr_handle())?)
+}
+
+/// List the ID, operations and multicast groups of every generic netlink family registered
+/// with the kernel over any `NlStream` connected to `NlFamily::Generic`
+pub fn list_genl_families<S>(stream: &mut S) -> Result<Vec<GenlFamilyInfo>, NlError>
+        where S: NlStream {
+    let genlhdr = Genlmsghdr::<CtrlCmd, CtrlAttr>::new(CtrlCmd::Getfamily, 2, Vec::new())?;
+    let nlhdr = Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Request, NlmF::Dump], None, None,
+                              genlhdr);
+    let mut families = Vec::new();
+    for msg in dump(stream, nlhdr)? {
+        families.push(GenlFamilyInfo::from_attr_handle(msg.nl_payload.get_attr_handle())?);
+    }
+    Ok(families)
+}
+
+/// Resolve a multicast group name of a generic netlink family to its numeric netlink ID over
+/// any `NlStream` connected to `NlFamily::Generic`
+pub fn resolve_nl_mcast_group<S>(stream: &mut S, family_name: &str, mcast_name: &str)
+        -> Result<u32, NlError> where S: NlStream {
+    let nlhdr = get_genl_family(stream, family_name)?;
+    let mcast_groups = get_mcast_groups(&nlhdr.nl_payload.get_attr_handle())?;
+    mcast_groups.into_iter()
+        .find(|(name, _)| name == mcast_name)
+        .map(|(_, id)| id)
+        .ok_or_else(|| NlError::new("Failed to resolve multicast group ID"))
+}
+
+// Value of `NETLINK_LISTEN_ALL_NSID` from `linux/netlink.h`
+const NETLINK_LISTEN_ALL_NSID: c_int = 8;
+
+// Value of `NETLINK_CAP_ACK` from `linux/netlink.h`
+const NETLINK_CAP_ACK: c_int = 10;
+
+// Value of `NETLINK_EXT_ACK` from `linux/netlink.h`
+const NETLINK_EXT_ACK: c_int = 11;
+
+// Large enough for the nsid or credentials control message with room to spare
+const CMSG_BUF_LEN: usize = 64;
+
+// Reimplementation of `CMSG_ALIGN` in C
+fn cmsg_align(len: usize) -> usize {
+    (len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+}
+
+// Find the data of the first control message with the given level and type that is at least
+// `min_len` bytes long in the ancillary data of a received message
+fn find_cmsg(cmsgs: &[u8], level: c_int, ty: c_int, min_len: usize) -> Option<&[u8]> {
+    let hdr_len = cmsg_align(size_of::<libc::cmsghdr>());
+    let mut pos = 0;
+    while pos + hdr_len <= cmsgs.len() {
+        let hdr = unsafe {
+            ptr::read_unaligned(cmsgs[pos..].as_ptr() as *const libc::cmsghdr)
+        };
+        let cmsg_len = hdr.cmsg_len as usize;
+        if cmsg_len < hdr_len || pos + cmsg_len > cmsgs.len() {
+            return None;
         }
+        if hdr.cmsg_level == level && hdr.cmsg_type == ty && cmsg_len >= hdr_len + min_len {
+            return Some(&cmsgs[pos + hdr_len..pos + cmsg_len]);
+        }
+        pos += cmsg_align(cmsg_len);
     }
+    None
 }
 
-impl NlSocket<GenlId, Genlmsghdr<CtrlCmd>> {
-    /// Create generic netlink resolution socket
-    pub fn new_genl() -> Result<NlSocket<GenlId, Genlmsghdr<CtrlCmd>>, io::Error> {
-        Self::connect(NlFamily::Generic, None, Vec::new())
+// Find the network namespace ID in the ancillary data of a message received from a socket
+// with `NETLINK_LISTEN_ALL_NSID` set
+fn parse_nsid(cmsgs: &[u8]) -> Option<i32> {
+    find_cmsg(cmsgs, libc::SOL_NETLINK, NETLINK_LISTEN_ALL_NSID, size_of::<c_int>())
+        .map(NativeEndian::read_i32)
+}
+
+// Find the credentials of the sender in the ancillary data of a message received from a
+// socket with `SO_PASSCRED` set
+fn parse_creds(cmsgs: &[u8]) -> Option<Ucred> {
+    find_cmsg(cmsgs, libc::SOL_SOCKET, libc::SCM_CREDENTIALS, size_of::<libc::ucred>())
+        .map(|data| unsafe {
+            let ucred = ptr::read_unaligned(data.as_ptr() as *const libc::ucred);
+            Ucred { pid: ucred.pid, uid: ucred.uid, gid: ucred.gid }
+        })
+}
+
+// Surface dropped multicast messages as `NlError::Overrun` so that callers can resync
+fn recv_error(e: io::Error) -> NlError {
+    if e.raw_os_error() == Some(libc::ENOBUFS) {
+        NlError::Overrun
+    } else {
+        NlError::from(e)
+    }
+}
+
+// Convert the error code that a `Nlmsg::Done` message carries after a dump failed partway,
+// followed by extended acknowledgement attributes if `NlmF::AckTlvs` is set - older kernels
+// send no payload
+fn parse_done_errno(nl_flags: u16, payload: &[u8]) -> Result<(), NlError> {
+    if payload.len() < size_of::<i32>() {
+        return Ok(());
+    }
+    let error = NativeEndian::read_i32(payload);
+    if error == 0 {
+        return Ok(());
+    }
+    let ack_tlvs: u16 = NlmF::AckTlvs.into();
+    if nl_flags & ack_tlvs == ack_tlvs {
+        let mut handle = AttrHandle::<NlmsgerrAttr>::Bin(&payload[size_of::<i32>()..]);
+        if handle.parse_nested_attributes().is_ok() {
+            let msg = handle.get_attribute(NlmsgerrAttr::Msg)
+                .map(|attr| attr.get_payload_as_string_lossy());
+            let offset = match handle.get_attr_payload(NlmsgerrAttr::Offs) {
+                Ok(Some(payload)) => u32::deserialize(&mut StreamReadBuffer::new(payload)).ok(),
+                _ => None,
+            };
+            if msg.is_some() || offset.is_some() {
+                return Err(NlError::ExtAck { errno: -error, msg, offset });
+            }
+        }
+    }
+    Err(NlError::Errno(-error))
+}
+
+// Deserialize an error payload according to the flags of its header - `cap_ack` treats the
+// payload as capped even if the kernel did not set `NlmF::Capped`
+fn parse_nlmsgerr<T>(nl_flags: &NlFlags<NlmF>, payload: &[u8], cap_ack: bool)
+        -> Result<Nlmsgerr<T>, NlError> where T: NlType {
+    let mut flags = nl_flags.clone();
+    if cap_ack {
+        flags.insert(NlmF::Capped);
+    }
+    Ok(Nlmsgerr::<T>::deserialize_with_flags(&mut StreamReadBuffer::new(payload), &flags)?)
+}
+
+/// Iterator over netlink messages received from a socket
+///
+/// Each datagram may contain several messages - they are split by `nl_len` before being
+/// deserialized. Iteration stops after `Nlmsg::Done` or after a message that does not
+/// have `NlmF::Multi` set.
+pub struct NlMessageIter<'a, T: 'a, P: 'a> {
+    sock: &'a mut NlSocket<T, P>,
+    parts: MultipartBuf,
+}
+
+// Buffer holding the remainder of the last datagram of a multipart response
+struct MultipartBuf {
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl MultipartBuf {
+    fn new() -> Self {
+        MultipartBuf { buf: Vec::new(), pos: 0, done: false }
+    }
+
+    fn next_msg<T, P>(&mut self, cap_ack: bool) -> Result<Option<Nlmsghdr<T, P>>, NlError>
+            where T: NlType, P: Nl {
+        let (nl_len, nl_type, nl_flags) = {
+            let mut mem = StreamReadBuffer::new(&self.buf[self.pos..]);
+            (u32::deserialize(&mut mem)? as usize, u16::deserialize(&mut mem)?,
+             u16::deserialize(&mut mem)?)
+        };
+        let end = self.pos.checked_add(nl_len).filter(|end| *end <= self.buf.len());
+        if nl_len < Nlmsghdr::<T, P>::HEADER_LEN || end.is_none() {
+            return Err(NlError::new("Message length does not match the received data"));
+        }
+        let start = self.pos;
+        self.pos = checked_alignto(start + nl_len)?;
+
+        let dump_intr: u16 = NlmF::DumpIntr.into();
+        if nl_flags & dump_intr == dump_intr {
+            self.done = is_last_msg(nl_type, nl_flags);
+            return Err(NlError::DumpInterrupted);
+        }
+        let multi: u16 = NlmF::Multi.into();
+        let done: u16 = consts::Nlmsg::Done.into();
+        let error: u16 = consts::Nlmsg::Error.into();
+        if nl_type == done {
+            self.done = true;
+            let payload = &self.buf[start + Nlmsghdr::<T, P>::HEADER_LEN..start + nl_len];
+            return parse_done_errno(nl_flags, payload).map(|_| None);
+        }
+        if nl_type == error {
+            self.done = true;
+            let msg = Nlmsghdr::<consts::Nlmsg, RawPayload>::deserialize(
+                &mut StreamReadBuffer::new(&self.buf[start..start + nl_len])
+            )?;
+            return parse_nlmsgerr::<T>(&msg.nl_flags, &msg.nl_payload.0, cap_ack)?.into_ack()
+                .map(|_| None);
+        }
+        if nl_flags & multi != multi {
+            self.done = true;
+        }
+        let mut mem = StreamReadBuffer::new(&self.buf[start..start + nl_len]);
+        Ok(Some(Nlmsghdr::<T, P>::deserialize(&mut mem)?))
+    }
+
+    // Receive the next message from `stream`, reading another datagram when the buffered one
+    // is exhausted
+    fn next<S, T, P>(&mut self, stream: &mut S) -> Option<Result<Nlmsghdr<T, P>, NlError>>
+            where S: NlStream, T: NlType, P: Nl {
+        if self.done {
+            return None;
+        }
+        if self.pos >= self.buf.len() {
+            let mut mem = vec![0; stream.recv_buffer_size()];
+            let mem_read = match stream.recv(&mut mem).map_err(recv_error) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                },
+                Ok(mem_read) => mem_read,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                },
+            };
+            let truncated = mem_read > mem.len();
+            mem.truncate(mem_read);
+            self.buf = mem;
+            self.pos = 0;
+            if truncated {
+                self.drain(stream, mem_read);
+                return Some(Err(NlError::Truncated { needed: mem_read }));
+            }
+        }
+        match self.next_msg(stream.cap_ack()) {
+            Ok(Some(m)) => Some(Ok(m)),
+            Ok(None) => None,
+            Err(e) => {
+                self.drain(stream, 0);
+                Some(Err(e))
+            },
+        }
     }
 
-    fn get_genl_family(&mut self, family_name: &str)
-            -> Result<Nlmsghdr<GenlId, Genlmsghdr<CtrlCmd>>, NlError> {
-        let attrs = vec![Nlattr::new_str_payload(None, CtrlAttr::FamilyName, family_name)?];
-        let genlhdr = Genlmsghdr::new(CtrlCmd::Getfamily, 2, attrs)?;
-        let nlhdr = Nlmsghdr::new(None, GenlId::Ctrl,
-                               vec![NlmF::Request], None, None, genlhdr);
-        self.send_nl(nlhdr)?;
+    // Discard the rest of a failed multipart response up to the message that ends it so that
+    // the next request does not read stale messages - datagrams are received into a buffer
+    // of at least `buf_sz` bytes and draining stops when no datagram is queued
+    fn drain<S>(&mut self, stream: &mut S, buf_sz: usize) where S: NlStream {
+        while !self.done {
+            while !self.done && self.pos + Nlmsghdr::<u16, ()>::HEADER_LEN <= self.buf.len() {
+                let nl_len = NativeEndian::read_u32(&self.buf[self.pos..]) as usize;
+                let nl_type = NativeEndian::read_u16(&self.buf[self.pos + 4..]);
+                let nl_flags = NativeEndian::read_u16(&self.buf[self.pos + 6..]);
+                match self.pos.checked_add(nl_len).and_then(|end| checked_alignto(end).ok()) {
+                    Some(pos) if nl_len >= Nlmsghdr::<u16, ()>::HEADER_LEN => self.pos = pos,
+                    _ => self.done = true,
+                };
+                self.done = self.done || is_last_msg(nl_type, nl_flags);
+            }
+            if self.done {
+                break;
+            }
+            let mut mem = vec![0; buf_sz.max(stream.recv_buffer_size())];
+            match stream.try_recv(&mut mem) {
+                Ok(mem_read) if mem_read > 0 => {
+                    mem.truncate(mem_read);
+                    self.buf = mem;
+                    self.pos = 0;
+                },
+                _ => self.done = true,
+            };
+        }
+    }
+}
+
+// Whether a message of a multipart response is the last one - `Nlmsg::Done`, an error or a
+// message without `NlmF::Multi`
+fn is_last_msg(nl_type: u16, nl_flags: u16) -> bool {
+    let multi: u16 = NlmF::Multi.into();
+    nl_type == consts::Nlmsg::Done.into() || nl_type == consts::Nlmsg::Error.into()
+        || nl_flags & multi != multi
+}
+
+impl<'a, T, P> Iterator for NlMessageIter<'a, T, P> where T: Nl + NlType, P: Nl {
+    type Item = Result<Nlmsghdr<T, P>, NlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parts.next(self.sock)
+    }
+}
 
-        Ok(self.recv_nl(Some(4096))?)
+impl NlSocket<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>> {
+    /// Create generic netlink resolution socket
+    pub fn new_genl() -> Result<NlSocket<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>, io::Error> {
+        Self::connect(NlFamily::Generic, None, Vec::new())
     }
 
     /// Convenience function for resolving a `&str` containing the multicast group name to a
     /// numeric netlink ID
-    pub fn resolve_genl_family(&mut self, family_name: &str) -> Result<u16, NlError> {
-        let nlhdr = self.get_genl_family(family_name)?;
-        let mut handle = nlhdr.nl_payload.get_attr_handle::<CtrlAttr>();
-        Ok(handle.get_payload_with::<u16>(CtrlAttr::FamilyId, None)?)
+    pub fn resolve_genl_family(&mut self, family_name: &str)
+            -> Result<GenlFamilyId, NlError> {
+        resolve_genl_family(self, family_name)
     }
 
     /// Convenience function for resolving a `&str` containing the multicast group name to a
     /// numeric netlink ID
     pub fn resolve_nl_mcast_group(&mut self, family_name: &str, mcast_name: &str)
             -> Result<u32, NlError> {
-        let nlhdr = self.get_genl_family(family_name)?;
-        let mut handle = nlhdr.nl_payload.get_attr_handle::<CtrlAttr>();
-        let mut mcast_groups = handle.get_nested_attributes::<u16>(CtrlAttr::McastGroups)?;
-        mcast_groups.parse_nested_attributes()?;
-        let mut id = None;
-        if let Some(iter) = mcast_groups.iter() {
-            for attribute in iter {
-                let attribute_len = attribute.nla_len;
-                let mut handle = attribute.get_attr_handle();
-                let string = handle.get_payload_with::<String>(CtrlAttrMcastGrp::Name,
-                    Some(attribute_len as usize))?;
-                if string.as_str() == mcast_name {
-                    id = handle.get_payload_with::<u32>(CtrlAttrMcastGrp::Id, None).ok();
-                }
-            }
-        }
-        id.ok_or(NlError::new("Failed to resolve multicast group ID"))
+        resolve_nl_mcast_group(self, family_name, mcast_name)
+    }
+
+    /// Look up the ID, operations and multicast groups of a generic netlink family
+    pub fn get_genl_family_info(&mut self, family_name: &str)
+            -> Result<GenlFamilyInfo, NlError> {
+        get_genl_family_info(self, family_name)
+    }
+
+    /// List the ID, operations and multicast groups of every generic netlink family
+    pub fn list_genl_families(&mut self) -> Result<Vec<GenlFamilyInfo>, NlError> {
+        list_genl_families(self)
     }
 }
 
@@ -209,6 +1012,18 @@ impl<T, P> AsRawFd for NlSocket<T, P> {
     }
 }
 
+impl<T, P> FromRawFd for NlSocket<T, P> {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        NlSocket {
+            fd,
+            cap_ack: false,
+            recv_buf_sz: MAX_NL_LENGTH,
+            data_type: PhantomData,
+            data_payload: PhantomData,
+        }
+    }
+}
+
 impl<T, P> IntoRawFd for NlSocket<T, P> {
     fn into_raw_fd(self) -> RawFd {
         self.fd
@@ -319,11 +1134,595 @@ impl<T, P> Drop for NlSocket<T, P> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use consts::{CtrlCmd,Nlmsg};
-    use genl::Genlmsghdr;
+    use std::collections::VecDeque;
+    use byteorder::WriteBytesExt;
+    use consts::{CtrlAttrMcastGrp,CtrlAttrOp,CtrlCmd,Nlmsg,NlmsgerrAttr,Rtnlgrp};
+    use genl::{GenlMcastGroup,GenlOp,Genlmsghdr};
+
+    struct MockStream {
+        sent: Vec<Vec<u8>>,
+        responses: VecDeque<Vec<u8>>,
+    }
+
+    impl NlStream for MockStream {
+        fn send(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+            self.sent.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn recv(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+            match self.responses.pop_front() {
+                Some(resp) => {
+                    // Report the full length of truncated datagrams like MSG_TRUNC
+                    let len = resp.len().min(buf.len());
+                    buf[..len].copy_from_slice(&resp[..len]);
+                    Ok(resp.len())
+                },
+                None => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_genl_family_mock() {
+        let genlhdr = Genlmsghdr::new(CtrlCmd::Newfamily, 2, vec![
+            Nlattr::new_str_payload(None, CtrlAttr::FamilyName, "nl80211").unwrap(),
+            Nlattr::new_nl_payload(None, CtrlAttr::FamilyId, 28u16).unwrap(),
+        ]).unwrap();
+        let resp = Nlmsghdr::new(None, GenlId::Ctrl, Vec::new(), Some(1), None, genlhdr);
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        resp.serialize(&mut mem).unwrap();
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(mem.as_ref().to_vec());
+
+        assert_eq!(resolve_genl_family(&mut stream, "nl80211").unwrap(), GenlFamilyId(28));
+        assert_eq!(stream.sent.len(), 1);
+        let req = Nlmsghdr::<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>::deserialize(
+            &mut StreamReadBuffer::new(stream.sent[0].as_slice())
+        ).unwrap();
+        assert_eq!(req.nl_type, GenlId::Ctrl);
+        assert_eq!(req.nl_payload.cmd, CtrlCmd::Getfamily);
+        let mut handle = req.nl_payload.get_attr_handle();
+        assert_eq!(handle.get_payload_with::<String>(CtrlAttr::FamilyName, Some(8)).unwrap(),
+                   "nl80211");
+
+        // No response left so the receive fails instead of blocking
+        assert!(resolve_genl_family(&mut stream, "nl80211").is_err());
+    }
+
+    #[test]
+    fn test_get_genl_family_info_mock() {
+        let genlhdr = Genlmsghdr::builder(CtrlCmd::Newfamily, 2)
+            .attr(CtrlAttr::FamilyName, "acme")
+            .attr(CtrlAttr::FamilyId, 31u16)
+            .attr(CtrlAttr::Version, 3u32)
+            .attr(CtrlAttr::Hdrsize, 4u32)
+            .attr(CtrlAttr::Maxattr, 9u32)
+            .nlattr(Nlattr::new_nested(None, CtrlAttr::Ops, vec![
+                Nlattr::new_nested(None, 1u16, vec![
+                    Nlattr::new_nl_payload(None, CtrlAttrOp::Id, 1u32).unwrap(),
+                    Nlattr::new_nl_payload(None, CtrlAttrOp::Flags, 0x0au32).unwrap(),
+                ]).unwrap(),
+            ]).unwrap())
+            .nlattr(Nlattr::new_nested(None, CtrlAttr::McastGroups, vec![
+                Nlattr::new_nested(None, 1u16, vec![
+                    Nlattr::new_str_payload(None, CtrlAttrMcastGrp::Name, "events").unwrap(),
+                    Nlattr::new_nl_payload(None, CtrlAttrMcastGrp::Id, 12u32).unwrap(),
+                ]).unwrap(),
+            ]).unwrap())
+            .build()
+            .unwrap();
+        let resp = Nlmsghdr::new(None, GenlId::Ctrl, Vec::new(), Some(1), None, genlhdr);
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(resp.serialize_to_vec().unwrap());
+
+        let info = get_genl_family_info(&mut stream, "acme").unwrap();
+        assert_eq!(info, GenlFamilyInfo {
+            id: 31,
+            name: "acme".to_string(),
+            version: 3,
+            hdrsize: 4,
+            maxattr: 9,
+            ops: vec![GenlOp { id: 1, flags: 0x0a }],
+            mcast_groups: vec![GenlMcastGroup { name: "events".to_string(), id: 12 }],
+        });
+    }
+
+    #[test]
+    fn test_list_genl_families_mock() {
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        for (id, name) in vec![(16u16, "nlctrl"), (21, "taskstats"), (31, "acme")] {
+            let genlhdr = Genlmsghdr::builder(CtrlCmd::Newfamily, 2)
+                .attr(CtrlAttr::FamilyName, name)
+                .attr(CtrlAttr::FamilyId, id)
+                .build()
+                .unwrap();
+            Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Multi], Some(1), None, genlhdr)
+                .serialize(&mut mem).unwrap();
+        }
+        Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], Some(1), None, 0u32)
+            .serialize(&mut mem).unwrap();
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(mem.as_ref().to_vec());
+
+        let families = list_genl_families(&mut stream).unwrap();
+        assert_eq!(families.iter().map(|f| (f.id, f.name.as_str())).collect::<Vec<_>>(),
+                   vec![(16, "nlctrl"), (21, "taskstats"), (31, "acme")]);
+        assert!(families.iter().all(|f| f.ops.is_empty() && f.mcast_groups.is_empty()));
+        let req = Nlmsghdr::<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>::from_bytes(&stream.sent[0])
+            .unwrap();
+        assert_eq!(req.nl_payload.cmd, CtrlCmd::Getfamily);
+        let flags = NativeEndian::read_u16(&stream.sent[0][6..8]);
+        assert_eq!(flags, u16::from(NlmF::Request) | u16::from(NlmF::Dump));
+    }
+
+    #[test]
+    fn test_get_genl_family_info() {
+        let mut sock = NlSocket::new_genl().unwrap();
+        let info = sock.get_genl_family_info("nlctrl").unwrap();
+        assert_eq!(info.id, u16::from(GenlId::Ctrl));
+        assert_eq!(info.name, "nlctrl");
+        assert!(info.ops.iter().any(|op| op.id == u32::from(u8::from(CtrlCmd::Getfamily))));
+        let notify = info.mcast_groups.iter().find(|group| group.name == "notify").unwrap();
+        assert_eq!(sock.resolve_nl_mcast_group("nlctrl", "notify").unwrap(), notify.id);
+        assert!(sock.list_genl_families().unwrap().contains(&info));
+    }
+
+    #[test]
+    fn test_resolved_family_as_nl_type() {
+        let mut sock = NlSocket::new_genl().unwrap();
+        let id = sock.resolve_genl_family("nlctrl").unwrap();
+        assert_eq!(id, GenlId::Ctrl);
+
+        let mut sock = NlSocket::<GenlFamilyId, Genlmsghdr<CtrlCmd, CtrlAttr>>::connect(
+            NlFamily::Generic, None, Vec::new()
+        ).unwrap();
+        let genlhdr = Genlmsghdr::builder(CtrlCmd::Getfamily, 2)
+            .attr(CtrlAttr::FamilyName, "nlctrl")
+            .build()
+            .unwrap();
+        sock.send_nl(Nlmsghdr::new(None, id, vec![NlmF::Request], None, None, genlhdr))
+            .unwrap();
+        let resp = sock.recv_nl(None).unwrap();
+        assert_eq!(resp.nl_type, id);
+        assert_eq!(resp.nl_payload.cmd, CtrlCmd::Newfamily);
+    }
 
     #[test]
     fn test_socket_creation() {
-       NlSocket::<Nlmsg, Genlmsghdr<CtrlCmd>>::connect(NlFamily::Generic, None, Vec::new()).unwrap();
+       NlSocket::<Nlmsg, Genlmsghdr<CtrlCmd, CtrlAttr>>::connect(NlFamily::Generic, None,
+                                                                 Vec::new()).unwrap();
+    }
+
+    #[test]
+    fn test_socket_options() {
+        let mut sock = NlSocket::<Nlmsg, ()>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        sock.set_broadcast_error(true).unwrap();
+        sock.set_broadcast_error(false).unwrap();
+        sock.set_no_enobufs(true).unwrap();
+        sock.set_no_enobufs(false).unwrap();
+        sock.set_listen_all_nsid(true).unwrap();
+        sock.set_listen_all_nsid(false).unwrap();
+        sock.set_passcred(true).unwrap();
+        sock.set_passcred(false).unwrap();
+        sock.set_ext_ack(true).unwrap();
+        sock.set_ext_ack(false).unwrap();
+        sock.set_cap_ack(true).unwrap();
+        assert!(NlStream::cap_ack(&sock));
+        sock.set_cap_ack(false).unwrap();
+    }
+
+    #[test]
+    fn test_mcast_membership() {
+        let mut sock = NlSocket::<Nlmsg, ()>::new(NlFamily::Route).unwrap();
+        sock.bind_rtmgrp(None, Rtmgrp::Link | Rtmgrp::Ipv4Ifaddr).unwrap();
+        sock.add_mcast_membership(Rtnlgrp::Nsid).unwrap();
+        sock.add_mcast_membership(Rtnlgrp::Nexthop).unwrap();
+        sock.drop_mcast_membership(Rtnlgrp::Nexthop).unwrap();
+        sock.drop_mcast_membership(u32::from(Rtnlgrp::Link)).unwrap();
+    }
+
+    #[test]
+    fn test_group_mask() {
+        let mask = GroupMask::from_groups(&[1, 3, 32]);
+        assert_eq!(u32::from(mask), 0x80000005);
+        assert!(mask.contains(3));
+        assert!(!mask.contains(2));
+        assert_eq!(mask.iter().collect::<Vec<_>>(), vec![1, 3, 32]);
+        // Groups that do not fit in the mask are skipped
+        assert_eq!(GroupMask::from_groups(&[0, 2, 33]), GroupMask(0x2));
+        assert_eq!(GroupMask::default().iter().next(), None);
+    }
+
+    #[test]
+    fn test_bind_group_above_mask() {
+        // Group 33 is `RTNLGRP_BRVLAN` which can only be joined with `NETLINK_ADD_MEMBERSHIP`
+        let sock = NlSocket::<Nlmsg, ()>::connect(NlFamily::Route, None,
+                                                   vec![u32::from(Rtnlgrp::Link), 33]);
+        assert!(sock.is_ok());
+        assert_eq!(GroupMask::from_groups(&[33]), GroupMask(0));
+    }
+
+    #[test]
+    fn test_parse_nsid() {
+        let hdr_len = cmsg_align(size_of::<libc::cmsghdr>());
+        let mut cmsgs = vec![0u8; 2 * cmsg_align(hdr_len + size_of::<c_int>())];
+        let mut hdr = unsafe { zeroed::<libc::cmsghdr>() };
+        hdr.cmsg_len = (hdr_len + size_of::<c_int>()) as _;
+        hdr.cmsg_level = libc::SOL_SOCKET;
+        hdr.cmsg_type = NETLINK_LISTEN_ALL_NSID;
+        unsafe { ptr::write_unaligned(cmsgs.as_mut_ptr() as *mut libc::cmsghdr, hdr); }
+        assert_eq!(parse_nsid(&cmsgs), None);
+
+        let second = cmsg_align(hdr_len + size_of::<c_int>());
+        hdr.cmsg_level = libc::SOL_NETLINK;
+        unsafe { ptr::write_unaligned(cmsgs[second..].as_mut_ptr() as *mut libc::cmsghdr, hdr); }
+        NativeEndian::write_i32(&mut cmsgs[second + hdr_len..], 7);
+        assert_eq!(parse_nsid(&cmsgs), Some(7));
+    }
+
+    #[test]
+    fn test_parse_creds() {
+        let hdr_len = cmsg_align(size_of::<libc::cmsghdr>());
+        let nsid_len = cmsg_align(hdr_len + size_of::<c_int>());
+        let mut cmsgs = vec![0u8; nsid_len + cmsg_align(hdr_len + size_of::<libc::ucred>())];
+        let mut hdr = unsafe { zeroed::<libc::cmsghdr>() };
+        hdr.cmsg_len = (hdr_len + size_of::<c_int>()) as _;
+        hdr.cmsg_level = libc::SOL_NETLINK;
+        hdr.cmsg_type = NETLINK_LISTEN_ALL_NSID;
+        unsafe { ptr::write_unaligned(cmsgs.as_mut_ptr() as *mut libc::cmsghdr, hdr); }
+        assert_eq!(parse_creds(&cmsgs), None);
+
+        hdr.cmsg_len = (hdr_len + size_of::<libc::ucred>()) as _;
+        hdr.cmsg_level = libc::SOL_SOCKET;
+        hdr.cmsg_type = libc::SCM_CREDENTIALS;
+        unsafe { ptr::write_unaligned(cmsgs[nsid_len..].as_mut_ptr() as *mut libc::cmsghdr, hdr); }
+        let ucred = libc::ucred { pid: 1234, uid: 1000, gid: 100 };
+        unsafe {
+            ptr::write_unaligned(cmsgs[nsid_len + hdr_len..].as_mut_ptr() as *mut libc::ucred,
+                                 ucred);
+        }
+        assert_eq!(parse_creds(&cmsgs), Some(Ucred { pid: 1234, uid: 1000, gid: 100 }));
+        // Truncated credentials are ignored
+        assert_eq!(parse_creds(&cmsgs[..cmsgs.len() - 8]), None);
+    }
+
+    #[test]
+    fn test_parse_nlmsgerr_cap_ack() {
+        // Error code and request header whose `nl_len` counts a payload that was omitted,
+        // followed by an extended acknowledgement message
+        let mut payload = Vec::new();
+        payload.write_i32::<NativeEndian>(-libc::ENOENT).unwrap();
+        payload.write_u32::<NativeEndian>(24).unwrap();
+        payload.write_u16::<NativeEndian>(GenlId::Ctrl.into()).unwrap();
+        payload.write_u16::<NativeEndian>(NlmF::Request.into()).unwrap();
+        payload.extend_from_slice(&[0; 8]);
+        payload.write_u16::<NativeEndian>(8).unwrap();
+        payload.write_u16::<NativeEndian>(consts::NlmsgerrAttr::Msg.into()).unwrap();
+        payload.extend_from_slice(b"bad\0");
+        let flags = NlFlags::from(NlmF::AckTlvs);
+
+        let err = parse_nlmsgerr::<GenlId>(&flags, &payload, true).unwrap();
+        assert_eq!(err.error, -libc::ENOENT);
+        assert_eq!(err.nlmsg.nl_type, GenlId::Ctrl);
+        assert_eq!(err.ext_msg, Some("bad".to_string()));
+        // Without the socket setting the attributes are taken for the omitted payload
+        let err = parse_nlmsgerr::<GenlId>(&flags, &payload, false).unwrap();
+        assert_eq!(err.ext_msg, None);
+    }
+
+    #[test]
+    fn test_sockaddr_nl() {
+        let addr = NlSockAddr::new(1234, GroupMask::from_groups(&[1, 3]));
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        addr.serialize(&mut mem).unwrap();
+        let nladdr = libc::sockaddr_nl::from(addr);
+        let c_bytes = unsafe {
+            ::std::slice::from_raw_parts(&nladdr as *const _ as *const u8,
+                                         size_of::<libc::sockaddr_nl>())
+        };
+        assert_eq!(mem.as_ref(), c_bytes);
+        assert_eq!(addr.size(), mem.as_ref().len());
+        assert_eq!(NlSockAddr::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(),
+                   addr);
+        assert_eq!(NlSockAddr::from(nladdr), addr);
+    }
+
+    #[test]
+    fn test_send_to() {
+        let mut sock = NlSocket::<GenlId, Genlmsghdr<CtrlCmd, CtrlAttr>>::connect(
+            NlFamily::Generic, None, Vec::new()
+        ).unwrap();
+        let local = sock.local_addr().unwrap();
+        assert_eq!(local.nl_family, libc::AF_NETLINK as libc::sa_family_t);
+        assert_ne!(local.nl_pid, 0);
+
+        let genlhdr = Genlmsghdr::builder(CtrlCmd::Getfamily, 2)
+            .attr(CtrlAttr::FamilyName, "nlctrl")
+            .build()
+            .unwrap();
+        let req = Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Request], None, None, genlhdr);
+        sock.send_to(req.serialize_to_vec().unwrap(), NlSockAddr::new(0, GroupMask(0)), 0)
+            .unwrap();
+        let resp = sock.recv_nl(None).unwrap();
+        assert_eq!(resp.nl_pid, local.nl_pid);
+        assert_eq!(resp.nl_payload.cmd, CtrlCmd::Newfamily);
+    }
+
+    #[test]
+    fn test_poll_readable() {
+        let sock = NlSocket::<Nlmsg, ()>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        assert!(!sock.poll_readable(Some(Duration::from_millis(10))).unwrap());
+    }
+
+    #[test]
+    fn test_recv_error_overrun() {
+        match recv_error(io::Error::from_raw_os_error(libc::ENOBUFS)) {
+            NlError::Overrun => (),
+            _ => panic!("Expected NlError::Overrun"),
+        }
+    }
+
+    #[test]
+    fn test_recv_truncated_mock() {
+        let msg = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None, vec![0u8; 24]);
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(msg.serialize_to_vec().unwrap());
+        match recv_nl::<_, Nlmsg, Vec<u8>>(&mut stream, Some(16)) {
+            Err(NlError::Truncated { needed: 40 }) => (),
+            r => panic!("Expected NlError::Truncated, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_connect_raw_protocol() {
+        let sock = NlSocket::<Nlmsg, ()>::connect(libc::NETLINK_ROUTE, None, Vec::new())
+            .unwrap();
+        assert!(sock.as_raw_fd() >= 0);
+        // Protocol numbers without a registered family are rejected by the kernel
+        assert!(NlSocket::<Nlmsg, ()>::new(31).is_err());
+    }
+
+    #[test]
+    fn test_set_recv_buffer_size() {
+        let mut sock = NlSocket::new_genl().unwrap();
+        sock.set_recv_buffer_size(64);
+        let genlhdr = Genlmsghdr::<CtrlCmd, CtrlAttr>::new(CtrlCmd::Getfamily, 2, Vec::new())
+            .unwrap();
+        let req = Nlmsghdr::new(None, GenlId::Ctrl, vec![NlmF::Dump], None, None, genlhdr);
+        match sock.dump(req) {
+            Err(NlError::Truncated { needed }) => assert!(needed > 64),
+            r => panic!("Expected NlError::Truncated, got {:?}", r.map(|msgs| msgs.len())),
+        }
+    }
+
+    #[test]
+    fn test_recv_into() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe {
+            libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr())
+        }, 0);
+        let sock = unsafe { NlSocket::<Nlmsg, ()>::from_raw_fd(fds[0]) };
+        let sent = [1u8, 2, 3, 4, 5];
+        assert_eq!(unsafe {
+            libc::send(fds[1], sent.as_ptr() as *const c_void, sent.len(), 0)
+        }, sent.len() as libc::ssize_t);
+
+        let mut buf = [0u8; 64];
+        assert_eq!(sock.recv_into(&mut buf).unwrap(), &sent[..]);
+
+        // Datagrams that do not fit are reported with their full length
+        let mut short = [0u8; 3];
+        for _ in 0..3 {
+            assert_eq!(unsafe {
+                libc::send(fds[1], sent.as_ptr() as *const c_void, sent.len(), 0)
+            }, sent.len() as libc::ssize_t);
+        }
+        match sock.recv_into(&mut short) {
+            Err(NlError::Truncated { needed: 5 }) => (),
+            r => panic!("Expected NlError::Truncated, got {:?}", r),
+        }
+        match sock.recv_into_nsid(&mut short) {
+            Err(NlError::Truncated { needed: 5 }) => (),
+            r => panic!("Expected NlError::Truncated, got {:?}", r),
+        }
+        match sock.recv_into_creds(&mut short) {
+            Err(NlError::Truncated { needed: 5 }) => (),
+            r => panic!("Expected NlError::Truncated, got {:?}", r),
+        }
+        unsafe { libc::close(fds[1]); }
+    }
+
+    fn serialize_msgs(msgs: Vec<Nlmsghdr<Nlmsg, u32>>) -> Vec<u8> {
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        for msg in msgs {
+            msg.serialize(&mut mem).unwrap();
+        }
+        mem.as_ref().to_vec()
+    }
+
+    #[test]
+    fn test_iter_multipart() {
+        let mut sock = NlSocket::<Nlmsg, u32>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        let buf = serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 2),
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], None, None, 0),
+        ]);
+        let iter = NlMessageIter {
+            sock: &mut sock,
+            parts: MultipartBuf { buf, pos: 0, done: false },
+        };
+        let payloads = iter.map(|m| m.unwrap().nl_payload).collect::<Vec<_>>();
+        assert_eq!(payloads, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_iter_dump_interrupted() {
+        let mut sock = NlSocket::<Nlmsg, u32>::connect(NlFamily::Route, None, Vec::new()).unwrap();
+        let buf = serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], None, None, 0),
+        ]);
+        let mut iter = NlMessageIter {
+            sock: &mut sock,
+            parts: MultipartBuf { buf, pos: 0, done: false },
+        };
+        match iter.next() {
+            Some(Err(NlError::DumpInterrupted)) => (),
+            _ => panic!("Expected NlError::DumpInterrupted"),
+        }
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_dump_mock() {
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 2),
+        ]));
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], None, None, 0),
+        ]));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), Some(1), None, 0u32);
+        let msgs = dump(&mut stream, req).unwrap();
+        assert_eq!(msgs.iter().map(|m| m.nl_payload).collect::<Vec<_>>(), vec![1, 2]);
+
+        // Flags of the request sent with `NlmF::Request` and `NlmF::Dump` added
+        let flags = NativeEndian::read_u16(&stream.sent[0][6..8]);
+        assert_eq!(flags, u16::from(NlmF::Request) | u16::from(NlmF::Dump));
+
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 1),
+        ]));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Request, NlmF::Dump], None, None,
+                                0u32);
+        match dump(&mut stream, req) {
+            Err(NlError::DumpInterrupted) => (),
+            _ => panic!("Expected NlError::DumpInterrupted"),
+        }
+    }
+
+    #[test]
+    fn test_dump_drain_mock() {
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 2),
+        ]));
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 3),
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi, NlmF::DumpIntr], None, None, 0),
+        ]));
+        let next = serialize_msgs(vec![Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None,
+                                                     4)]);
+        stream.responses.push_back(next.clone());
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None, 0u32);
+        match dump(&mut stream, req) {
+            Err(NlError::DumpInterrupted) => (),
+            _ => panic!("Expected NlError::DumpInterrupted"),
+        }
+        assert_eq!(stream.responses, vec![next.clone()]);
+
+        // A datagram larger than the receive buffer hides the rest of its messages
+        stream.responses.clear();
+        let big = Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None,
+                                vec![0u8; MAX_NL_LENGTH]);
+        stream.responses.push_back(big.serialize_to_vec().unwrap());
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], None, None, 0),
+        ]));
+        stream.responses.push_back(next.clone());
+        match recv_dump::<_, Nlmsg, Vec<u8>>(&mut stream) {
+            Err(NlError::Truncated { needed }) => assert_eq!(needed, MAX_NL_LENGTH + 16),
+            r => panic!("Expected NlError::Truncated, got {:?}", r.map(|msgs| msgs.len())),
+        }
+        assert_eq!(stream.responses, vec![next]);
+    }
+
+    #[test]
+    fn test_dump_done_errno_mock() {
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(serialize_msgs(vec![
+            Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Multi], None, None, 1),
+            Nlmsghdr::new(None, Nlmsg::Done, vec![NlmF::Multi], None, None, -libc::EMSGSIZE as u32),
+        ]));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None, 0u32);
+        match dump(&mut stream, req) {
+            Err(NlError::Errno(errno)) => assert_eq!(errno, libc::EMSGSIZE),
+            r => panic!("Expected NlError::Errno, got {:?}", r),
+        }
+
+        let mut payload = Vec::new();
+        payload.write_i32::<NativeEndian>(-libc::EINVAL).unwrap();
+        payload.extend(vec![
+            Nlattr::new_str_payload(None, NlmsgerrAttr::Msg, "Invalid dump filter").unwrap(),
+        ].serialize_to_vec().unwrap());
+        stream.responses.push_back(Nlmsghdr::new(None, Nlmsg::Done,
+                                                 vec![NlmF::Multi, NlmF::AckTlvs], None, None,
+                                                 RawPayload(payload))
+            .serialize_to_vec().unwrap());
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), None, None, 0u32);
+        match dump(&mut stream, req) {
+            Err(NlError::ExtAck { errno, msg: Some(msg), .. }) => {
+                assert_eq!(errno, libc::EINVAL);
+                assert_eq!(msg, "Invalid dump filter");
+            },
+            r => panic!("Expected NlError::ExtAck, got {:?}", r),
+        }
+    }
+
+    // Error reply to a request with sequence number 1 whose payload was capped
+    fn error_reply(error: i32, tlvs: Vec<Nlattr<NlmsgerrAttr>>) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.write_i32::<NativeEndian>(error).unwrap();
+        payload.write_u32::<NativeEndian>(20).unwrap();
+        payload.write_u16::<NativeEndian>(Nlmsg::Noop.into()).unwrap();
+        payload.write_u16::<NativeEndian>(NlmF::Request.into()).unwrap();
+        payload.write_u32::<NativeEndian>(1).unwrap();
+        payload.write_u32::<NativeEndian>(0).unwrap();
+        let mut flags = vec![NlmF::Capped];
+        if !tlvs.is_empty() {
+            flags.push(NlmF::AckTlvs);
+            payload.extend(tlvs.serialize_to_vec().unwrap());
+        }
+        Nlmsghdr::new(None, Nlmsg::Error, flags, Some(1), None, RawPayload(payload))
+            .serialize_to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_send_ack_mock() {
+        let mut stream = MockStream { sent: Vec::new(), responses: VecDeque::new() };
+        stream.responses.push_back(error_reply(0, Vec::new()));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), Some(1), None, 0u32);
+        let ack = send_ack(&mut stream, req).unwrap();
+        assert_eq!(ack.nlmsg.nl_seq, 1);
+
+        // Flags of the request sent with `NlmF::Request` and `NlmF::Ack` added
+        let flags = NativeEndian::read_u16(&stream.sent[0][6..8]);
+        assert_eq!(flags, u16::from(NlmF::Request) | u16::from(NlmF::Ack));
+
+        stream.responses.push_back(error_reply(-libc::EINVAL, vec![
+            Nlattr::new_str_payload(None, NlmsgerrAttr::Msg, "Invalid request").unwrap(),
+            Nlattr::new_nl_payload(None, NlmsgerrAttr::Offs, 16u32).unwrap(),
+        ]));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, vec![NlmF::Request], Some(1), None, 0u32);
+        match send_ack(&mut stream, req) {
+            Err(NlError::ExtAck { errno, msg, offset }) => {
+                assert_eq!(errno, libc::EINVAL);
+                assert_eq!(msg, Some("Invalid request".to_string()));
+                assert_eq!(offset, Some(16));
+            },
+            _ => panic!("Expected NlError::ExtAck"),
+        }
+
+        stream.responses.push_back(error_reply(-libc::EPERM, Vec::new()));
+        let req = Nlmsghdr::new(None, Nlmsg::Noop, Vec::new(), Some(1), None, 0u32);
+        match send_ack(&mut stream, req) {
+            Err(NlError::Errno(libc::EPERM)) => (),
+            _ => panic!("Expected NlError::Errno"),
+        }
     }
 }
@@ -0,0 +1,323 @@
+//! Decoding of `struct taskstats` replies of the `TASKSTATS` generic netlink family
+//!
+//! The kernel appends fields to `struct taskstats` as its version grows. `Taskstats` models
+//! the layout up to `thrashing_delay_total` and ignores any fields the kernel appends after
+//! it, so only replies shorter than `TASKSTATS_LEN` fail to parse.
+
+use std::io::{Read,Write};
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+
+use Nl;
+use consts::{NlmF,Nlmsg};
+use consts::taskstats::*;
+use err::{DeError,NlError,Nlmsgerr,SerError};
+use genl::Genlmsghdr;
+use nl::{Nlmsghdr,RawPayload};
+use nlattr::Nlattr;
+use socket::{recv_nl,resolve_genl_family,send_nl,NlStream};
+
+/// Length of the part of `struct taskstats` modeled by `Taskstats`
+pub const TASKSTATS_LEN: usize = 344;
+
+/// Accounting of a task or thread group from `struct taskstats` in `linux/taskstats.h`
+///
+/// Delays are in nanoseconds, `ac_utime` and `ac_stime` in microseconds, and memory
+/// integrals in megabyte-microseconds.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Taskstats {
+    /// Version of the kernel's `struct taskstats`
+    pub version: u16,
+    /// Exit status of the task
+    pub ac_exitcode: u32,
+    /// Accounting record flags
+    pub ac_flag: u8,
+    /// Nice value of the task
+    pub ac_nice: u8,
+    /// Number of delays waiting for a CPU
+    pub cpu_count: u64,
+    /// Total delay waiting for a CPU
+    pub cpu_delay_total: u64,
+    /// Number of delays waiting for synchronous block I/O
+    pub blkio_count: u64,
+    /// Total delay waiting for synchronous block I/O
+    pub blkio_delay_total: u64,
+    /// Number of delays waiting for pages to be swapped in
+    pub swapin_count: u64,
+    /// Total delay waiting for pages to be swapped in
+    pub swapin_delay_total: u64,
+    /// Wall clock time spent running on a CPU
+    pub cpu_run_real_total: u64,
+    /// Virtual time spent running on a CPU
+    pub cpu_run_virtual_total: u64,
+    /// Command name, null padded
+    pub ac_comm: [u8; TS_COMM_LEN],
+    /// Scheduling discipline
+    pub ac_sched: u8,
+    /// User ID
+    pub ac_uid: u32,
+    /// Group ID
+    pub ac_gid: u32,
+    /// Process ID
+    pub ac_pid: u32,
+    /// Parent process ID
+    pub ac_ppid: u32,
+    /// Start time in seconds since the Unix epoch
+    pub ac_btime: u32,
+    /// Elapsed time in microseconds
+    pub ac_etime: u64,
+    /// User CPU time in microseconds
+    pub ac_utime: u64,
+    /// System CPU time in microseconds
+    pub ac_stime: u64,
+    /// Minor page faults
+    pub ac_minflt: u64,
+    /// Major page faults
+    pub ac_majflt: u64,
+    /// Accumulated resident memory usage
+    pub coremem: u64,
+    /// Accumulated virtual memory usage
+    pub virtmem: u64,
+    /// High watermark of resident memory in kilobytes
+    pub hiwater_rss: u64,
+    /// High watermark of virtual memory in kilobytes
+    pub hiwater_vm: u64,
+    /// Bytes read by `read` and similar system calls
+    pub read_char: u64,
+    /// Bytes written by `write` and similar system calls
+    pub write_char: u64,
+    /// Number of read system calls
+    pub read_syscalls: u64,
+    /// Number of write system calls
+    pub write_syscalls: u64,
+    /// Bytes read from storage
+    pub read_bytes: u64,
+    /// Bytes written to storage
+    pub write_bytes: u64,
+    /// Bytes of cancelled writeback
+    pub cancelled_write_bytes: u64,
+    /// Voluntary context switches
+    pub nvcsw: u64,
+    /// Involuntary context switches
+    pub nivcsw: u64,
+    /// User CPU time scaled by CPU frequency
+    pub ac_utimescaled: u64,
+    /// System CPU time scaled by CPU frequency
+    pub ac_stimescaled: u64,
+    /// Wall clock time spent running on a CPU scaled by CPU frequency
+    pub cpu_scaled_run_real_total: u64,
+    /// Number of delays waiting for memory reclaim
+    pub freepages_count: u64,
+    /// Total delay waiting for memory reclaim
+    pub freepages_delay_total: u64,
+    /// Number of delays waiting for thrashing pages
+    pub thrashing_count: u64,
+    /// Total delay waiting for thrashing pages
+    pub thrashing_delay_total: u64,
+}
+
+impl Taskstats {
+    /// Command name up to the first null byte
+    pub fn comm(&self) -> String {
+        let len = self.ac_comm.iter().position(|b| *b == 0).unwrap_or(TS_COMM_LEN);
+        String::from_utf8_lossy(&self.ac_comm[..len]).into_owned()
+    }
+}
+
+// Padding inserted by the `aligned(8)` attributes of `struct taskstats`
+fn serialize_pad(mem: &mut StreamWriteBuffer, len: usize) -> Result<(), SerError> {
+    mem.write_all(&[0u8; 8][..len])?;
+    Ok(())
+}
+
+fn deserialize_pad<B>(mem: &mut StreamReadBuffer<B>, len: usize) -> Result<(), DeError>
+        where B: AsRef<[u8]> {
+    mem.read_exact(&mut [0u8; 8][..len])?;
+    Ok(())
+}
+
+impl Nl for Taskstats {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.version.serialize(mem)?;
+        serialize_pad(mem, 2)?;
+        self.ac_exitcode.serialize(mem)?;
+        self.ac_flag.serialize(mem)?;
+        self.ac_nice.serialize(mem)?;
+        serialize_pad(mem, 6)?;
+        for v in &[self.cpu_count, self.cpu_delay_total, self.blkio_count,
+                   self.blkio_delay_total, self.swapin_count, self.swapin_delay_total,
+                   self.cpu_run_real_total, self.cpu_run_virtual_total] {
+            v.serialize(mem)?;
+        }
+        mem.write_all(&self.ac_comm)?;
+        self.ac_sched.serialize(mem)?;
+        serialize_pad(mem, 7)?;
+        for v in &[self.ac_uid, self.ac_gid, self.ac_pid, self.ac_ppid, self.ac_btime] {
+            v.serialize(mem)?;
+        }
+        serialize_pad(mem, 4)?;
+        for v in &[self.ac_etime, self.ac_utime, self.ac_stime, self.ac_minflt, self.ac_majflt,
+                   self.coremem, self.virtmem, self.hiwater_rss, self.hiwater_vm,
+                   self.read_char, self.write_char, self.read_syscalls, self.write_syscalls,
+                   self.read_bytes, self.write_bytes, self.cancelled_write_bytes, self.nvcsw,
+                   self.nivcsw, self.ac_utimescaled, self.ac_stimescaled,
+                   self.cpu_scaled_run_real_total, self.freepages_count,
+                   self.freepages_delay_total, self.thrashing_count,
+                   self.thrashing_delay_total] {
+            v.serialize(mem)?;
+        }
+        Ok(())
+    }
+
+    /// Consumes the rest of the buffer - bytes following the modeled fields are ignored
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut bytes = Vec::new();
+        mem.read_to_end(&mut bytes)?;
+        if bytes.len() < TASKSTATS_LEN {
+            return Err(DeError::new("Taskstats payload is shorter than the modeled struct"));
+        }
+        let mut mem = StreamReadBuffer::new(&bytes[..TASKSTATS_LEN]);
+        let version = u16::deserialize(&mut mem)?;
+        deserialize_pad(&mut mem, 2)?;
+        let ac_exitcode = u32::deserialize(&mut mem)?;
+        let ac_flag = u8::deserialize(&mut mem)?;
+        let ac_nice = u8::deserialize(&mut mem)?;
+        deserialize_pad(&mut mem, 6)?;
+        let cpu_count = u64::deserialize(&mut mem)?;
+        let cpu_delay_total = u64::deserialize(&mut mem)?;
+        let blkio_count = u64::deserialize(&mut mem)?;
+        let blkio_delay_total = u64::deserialize(&mut mem)?;
+        let swapin_count = u64::deserialize(&mut mem)?;
+        let swapin_delay_total = u64::deserialize(&mut mem)?;
+        let cpu_run_real_total = u64::deserialize(&mut mem)?;
+        let cpu_run_virtual_total = u64::deserialize(&mut mem)?;
+        let mut ac_comm = [0u8; TS_COMM_LEN];
+        mem.read_exact(&mut ac_comm)?;
+        let ac_sched = u8::deserialize(&mut mem)?;
+        deserialize_pad(&mut mem, 7)?;
+        let ac_uid = u32::deserialize(&mut mem)?;
+        let ac_gid = u32::deserialize(&mut mem)?;
+        let ac_pid = u32::deserialize(&mut mem)?;
+        let ac_ppid = u32::deserialize(&mut mem)?;
+        let ac_btime = u32::deserialize(&mut mem)?;
+        deserialize_pad(&mut mem, 4)?;
+        Ok(Taskstats {
+            version,
+            ac_exitcode,
+            ac_flag,
+            ac_nice,
+            cpu_count,
+            cpu_delay_total,
+            blkio_count,
+            blkio_delay_total,
+            swapin_count,
+            swapin_delay_total,
+            cpu_run_real_total,
+            cpu_run_virtual_total,
+            ac_comm,
+            ac_sched,
+            ac_uid,
+            ac_gid,
+            ac_pid,
+            ac_ppid,
+            ac_btime,
+            ac_etime: u64::deserialize(&mut mem)?,
+            ac_utime: u64::deserialize(&mut mem)?,
+            ac_stime: u64::deserialize(&mut mem)?,
+            ac_minflt: u64::deserialize(&mut mem)?,
+            ac_majflt: u64::deserialize(&mut mem)?,
+            coremem: u64::deserialize(&mut mem)?,
+            virtmem: u64::deserialize(&mut mem)?,
+            hiwater_rss: u64::deserialize(&mut mem)?,
+            hiwater_vm: u64::deserialize(&mut mem)?,
+            read_char: u64::deserialize(&mut mem)?,
+            write_char: u64::deserialize(&mut mem)?,
+            read_syscalls: u64::deserialize(&mut mem)?,
+            write_syscalls: u64::deserialize(&mut mem)?,
+            read_bytes: u64::deserialize(&mut mem)?,
+            write_bytes: u64::deserialize(&mut mem)?,
+            cancelled_write_bytes: u64::deserialize(&mut mem)?,
+            nvcsw: u64::deserialize(&mut mem)?,
+            nivcsw: u64::deserialize(&mut mem)?,
+            ac_utimescaled: u64::deserialize(&mut mem)?,
+            ac_stimescaled: u64::deserialize(&mut mem)?,
+            cpu_scaled_run_real_total: u64::deserialize(&mut mem)?,
+            freepages_count: u64::deserialize(&mut mem)?,
+            freepages_delay_total: u64::deserialize(&mut mem)?,
+            thrashing_count: u64::deserialize(&mut mem)?,
+            thrashing_delay_total: u64::deserialize(&mut mem)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        TASKSTATS_LEN
+    }
+}
+
+/// Query the accounting of the task with process ID `pid` over any `NlStream` connected to
+/// `NlFamily::Generic` - pass `std::process::id()` for the calling process
+pub fn get_pid_stats<S>(stream: &mut S, pid: u32) -> Result<Taskstats, NlError>
+        where S: NlStream {
+    let family_id = resolve_genl_family(stream, TASKSTATS_GENL_NAME)?;
+    let genlhdr = Genlmsghdr::new(TaskstatsCmd::Get, TASKSTATS_GENL_VERSION, vec![
+        Nlattr::new_nl_payload(None, TaskstatsCmdAttr::Pid, pid)?,
+    ])?;
+    send_nl(stream, Nlmsghdr::new(None, family_id, vec![NlmF::Request], None, None, genlhdr))?;
+
+    let msg = recv_nl::<S, u16, RawPayload>(stream, None)?;
+    if msg.nl_type == u16::from(Nlmsg::Error) {
+        let mut flags = msg.nl_flags.clone();
+        if stream.cap_ack() {
+            flags.insert(NlmF::Capped);
+        }
+        Nlmsgerr::<u16>::deserialize_with_flags(
+            &mut StreamReadBuffer::new(msg.nl_payload.0.as_slice()), &flags
+        )?.into_ack()?;
+        return Err(NlError::new("Received an acknowledgement instead of task statistics"));
+    }
+    let genl = Genlmsghdr::<TaskstatsCmd, TaskstatsType>::from_bytes(&msg.nl_payload.0)?;
+    let mut aggr = genl.get_attr_handle();
+    let mut stats = aggr.get_nested_attributes::<TaskstatsType>(TaskstatsType::AggrPid)?;
+    Ok(stats.get_payload_with::<Taskstats>(TaskstatsType::Stats, None)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::process;
+
+    use consts::{GenlFamilyId,NlFamily};
+    use genl::Genlmsghdr;
+    use socket::NlSocket;
+
+    #[test]
+    fn test_taskstats_layout() {
+        let mut bytes = vec![0u8; TASKSTATS_LEN + 8];
+        bytes[0] = 9;
+        bytes[80..84].copy_from_slice(b"neli");
+        bytes[128] = 42;
+        bytes[336] = 7;
+        let stats = Taskstats::from_bytes(&bytes).unwrap();
+        assert_eq!(stats.version, 9);
+        assert_eq!(stats.comm(), "neli");
+        assert_eq!(stats.ac_pid, 42);
+        assert_eq!(stats.thrashing_delay_total, 7);
+
+        let mem = stats.serialize_to_vec().unwrap();
+        assert_eq!(mem.as_slice(), &bytes[..TASKSTATS_LEN]);
+        assert!(Taskstats::from_bytes(&bytes[..TASKSTATS_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn test_get_pid_stats() {
+        let mut sock = NlSocket::<GenlFamilyId, Genlmsghdr<TaskstatsCmd, TaskstatsCmdAttr>>
+            ::connect(NlFamily::Generic, None, Vec::new()).unwrap();
+        let stats = get_pid_stats(&mut sock, process::id()).unwrap();
+        assert_eq!(stats.ac_pid, process::id());
+        assert!(stats.version > 0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Device events sent by the kernel over `NlFamily::KobjectUevent` are not netlink messages -
+//! each datagram is a header of the form `ACTION@DEVPATH` followed by `KEY=VALUE` pairs, all
+//! null terminated.
+//!
+//! ```no_run
+//! use neli::socket::recv_datagram;
+//! use neli::uevent::{self,Uevent};
+//!
+//! let mut sock = uevent::connect_uevent().unwrap();
+//! loop {
+//!     let event = Uevent::parse(&recv_datagram(&mut sock, None).unwrap()).unwrap();
+//!     println!("{} {} ({})", event.action, event.devpath, event.subsystem);
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::str;
+
+use consts::{NlFamily,Nlmsg};
+use err::{DeError,NlError};
+use socket::{recv_datagram,NlSocket,NlStream};
+
+/// Multicast group of the events broadcast by the kernel
+pub const UEVENT_GROUP_KERNEL: u32 = 1;
+
+/// Multicast group of the events rebroadcast by udev in the libudev format that `Uevent`
+/// rejects
+pub const UEVENT_GROUP_UDEV: u32 = 2;
+
+// Prefix of the messages rebroadcast by udev, followed by a binary header
+const LIBUDEV_PREFIX: &[u8] = b"libudev\0";
+
+/// Device event broadcast by the kernel
+#[derive(Clone,Debug,PartialEq)]
+pub struct Uevent {
+    /// Action such as `add`, `remove` or `change`
+    pub action: String,
+    /// Path of the device below `/sys`
+    pub devpath: String,
+    /// Subsystem of the device such as `net` or `block`
+    pub subsystem: String,
+    /// All `KEY=VALUE` pairs of the event, including `ACTION`, `DEVPATH` and `SUBSYSTEM`
+    pub env: HashMap<String, String>,
+    /// Sequence number of the event
+    pub seqnum: u64,
+}
+
+impl Uevent {
+    /// Parse a datagram received from the kernel group - messages rebroadcast by udev are
+    /// rejected
+    pub fn parse(bytes: &[u8]) -> Result<Self, DeError> {
+        if bytes.starts_with(LIBUDEV_PREFIX) {
+            return Err(DeError::new("Messages in the libudev format are not supported"));
+        }
+        let mut fields = bytes.split(|b| *b == 0).filter(|field| !field.is_empty());
+        let header = str::from_utf8(fields.next()
+            .ok_or_else(|| DeError::new("Uevent is empty"))?)?;
+        let at = header.find('@')
+            .ok_or_else(|| DeError::new("Uevent header is not of the form ACTION@DEVPATH"))?;
+        let mut env = HashMap::new();
+        for field in fields {
+            let field = str::from_utf8(field)?;
+            let eq = field.find('=')
+                .ok_or_else(|| DeError::new("Uevent field is not of the form KEY=VALUE"))?;
+            env.insert(field[..eq].to_string(), field[eq + 1..].to_string());
+        }
+        let subsystem = env.get("SUBSYSTEM").cloned()
+            .ok_or_else(|| DeError::new("Uevent is missing SUBSYSTEM"))?;
+        let seqnum = env.get("SEQNUM")
+            .ok_or_else(|| DeError::new("Uevent is missing SEQNUM"))?
+            .parse::<u64>()
+            .map_err(|_| DeError::new("Uevent SEQNUM is not a number"))?;
+        Ok(Uevent {
+            action: header[..at].to_string(),
+            devpath: header[at + 1..].to_string(),
+            subsystem,
+            env,
+            seqnum,
+        })
+    }
+}
+
+/// Create a socket subscribed to the events broadcast by the kernel - receive them with
+/// `recv_uevent` or `recv_datagram`
+pub fn connect_uevent() -> Result<NlSocket<Nlmsg, ()>, io::Error> {
+    NlSocket::connect(NlFamily::KobjectUevent, None, vec![UEVENT_GROUP_KERNEL])
+}
+
+/// Receive and parse an event from any `NlStream` subscribed to `UEVENT_GROUP_KERNEL`
+pub fn recv_uevent<S>(stream: &mut S) -> Result<Uevent, NlError> where S: NlStream {
+    Ok(Uevent::parse(&recv_datagram(stream, None)?)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+    use std::time::{Duration,Instant};
+
+    #[test]
+    fn test_parse_uevent() {
+        let bytes = b"add@/devices/virtual/net/veth0\0ACTION=add\0\
+                      DEVPATH=/devices/virtual/net/veth0\0SUBSYSTEM=net\0INTERFACE=veth0\0\
+                      IFINDEX=7\0SEQNUM=4242\0";
+        let event = Uevent::parse(bytes).unwrap();
+        assert_eq!(event.action, "add");
+        assert_eq!(event.devpath, "/devices/virtual/net/veth0");
+        assert_eq!(event.subsystem, "net");
+        assert_eq!(event.seqnum, 4242);
+        assert_eq!(event.env.get("IFINDEX").map(|s| s.as_str()), Some("7"));
+
+        assert!(Uevent::parse(b"libudev\0\xfe\xed\xca\xfe").is_err());
+        assert!(Uevent::parse(b"add@/devices/foo\0SUBSYSTEM=net\0").is_err());
+    }
+
+    #[test]
+    fn test_parse_captured_uevent() {
+        // Event of a USB storage device as received from `UEVENT_GROUP_KERNEL`
+        let bytes = b"add@/devices/pci0000:00/0000:00:14.0/usb1/1-2\0ACTION=add\0\
+                      DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2\0SUBSYSTEM=usb\0\
+                      MAJOR=189\0MINOR=3\0DEVNAME=bus/usb/001/004\0DEVTYPE=usb_device\0\
+                      PRODUCT=781/5583/100\0TYPE=0/0/0\0BUSNUM=001\0DEVNUM=004\0SEQNUM=5121\0";
+        let event = Uevent::parse(bytes).unwrap();
+        assert_eq!(event.action, "add");
+        assert_eq!(event.devpath, "/devices/pci0000:00/0000:00:14.0/usb1/1-2");
+        assert_eq!(event.subsystem, "usb");
+        assert_eq!(event.seqnum, 5121);
+        assert_eq!(event.env.len(), 12);
+        assert_eq!(event.env.get("DEVNAME").map(|s| s.as_str()), Some("bus/usb/001/004"));
+        assert_eq!(event.env.get("PRODUCT").map(|s| s.as_str()), Some("781/5583/100"));
+    }
+
+    // Requires root and a writable /sys and fires a uevent on the host - run with
+    // `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_recv_uevent() {
+        let mut sock = connect_uevent().unwrap();
+        fs::write("/sys/class/net/lo/uevent", "change").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let now = Instant::now();
+            assert!(now < deadline, "Timed out waiting for the uevent of lo");
+            assert!(sock.poll_readable(Some(deadline - now)).unwrap());
+            let event = recv_uevent(&mut sock).unwrap();
+            if event.env.get("INTERFACE").map(|s| s.as_str()) == Some("lo") {
+                assert_eq!(event.action, "change");
+                assert_eq!(event.subsystem, "net");
+                break;
+            }
+        }
+    }
+}
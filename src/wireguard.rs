@@ -0,0 +1,406 @@
+//! Typed payloads for the `wireguard` generic netlink family
+//!
+//! A `WgCmd::GetDevice` reply carries the device attributes at the top level with the peers
+//! as an array of nested attributes indexed from 0, each of which holds an array of allowed
+//! IPs in the same layout:
+//!
+//! ```text
+//! WgDeviceAttr::Peers
+//!     0 => WgPeerAttr::PublicKey, ..., WgPeerAttr::Allowedips
+//!         0 => WgAllowedIpAttr::Family, WgAllowedIpAttr::Ipaddr, WgAllowedIpAttr::CidrMask
+//!         1 => ...
+//!     1 => ...
+//! ```
+//!
+//! Devices with many peers are split across several messages of the same dump - the peers of
+//! every message after the first should be appended to the first `WgDevice`.
+
+use std::io::Read;
+use std::net::{IpAddr,SocketAddr};
+use std::time::Duration;
+
+use buffering::copy::{StreamReadBuffer,StreamWriteBuffer};
+
+use Nl;
+use consts::Af;
+use consts::wireguard::{WgAllowedIpAttr,WgDeviceAttr,WgPeerAttr,WG_KEY_LEN};
+use err::{DeError,SerError};
+use nlattr::{AttrHandle,Nlattr,NLA_TYPE_MASK};
+
+/// Public, private or preshared key of `WG_KEY_LEN` bytes
+#[derive(Clone,Debug,PartialEq)]
+pub struct WgKey(pub [u8; WG_KEY_LEN]);
+
+impl WgKey {
+    /// Parse a key from an attribute payload, failing if it is not exactly `WG_KEY_LEN` bytes
+    pub fn from_payload(payload: &[u8]) -> Result<Self, DeError> {
+        if payload.len() != WG_KEY_LEN {
+            return Err(DeError::new("WireGuard key payload must be 32 bytes"));
+        }
+        let mut key = [0u8; WG_KEY_LEN];
+        key.copy_from_slice(payload);
+        Ok(WgKey(key))
+    }
+
+    /// Whether every byte of the key is zero - the kernel reports unset keys this way
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|b| *b == 0)
+    }
+}
+
+impl Nl for WgKey {
+    type SerIn = ();
+    type DeIn = ();
+
+    fn serialize(&self, mem: &mut StreamWriteBuffer) -> Result<(), SerError> {
+        self.0.as_ref().serialize(mem)
+    }
+
+    fn deserialize<B>(mem: &mut StreamReadBuffer<B>) -> Result<Self, DeError> where B: AsRef<[u8]> {
+        let mut key = [0u8; WG_KEY_LEN];
+        mem.read_exact(&mut key)?;
+        Ok(WgKey(key))
+    }
+
+    fn size(&self) -> usize {
+        WG_KEY_LEN
+    }
+}
+
+/// Allowed IP range of a peer corresponding to the attributes in `WgPeerAttr::Allowedips`
+#[derive(Clone,Debug,PartialEq)]
+pub struct WgAllowedIp {
+    /// Network address of the range
+    pub addr: IpAddr,
+    /// Prefix length of the range
+    pub cidr_mask: u8,
+}
+
+impl WgAllowedIp {
+    /// Create a new allowed IP range
+    pub fn new(addr: IpAddr, cidr_mask: u8) -> Self {
+        WgAllowedIp { addr, cidr_mask }
+    }
+
+    /// Parse the attributes of one entry of `WgPeerAttr::Allowedips`
+    pub fn from_attr_handle(handle: AttrHandle<WgAllowedIpAttr>) -> Result<Self, DeError> {
+        let mut family = None;
+        let mut addr = None;
+        let mut cidr_mask = None;
+        handle.parse_with(|attr| {
+            match WgAllowedIpAttr::from(u16::from(&attr.nla_type) & NLA_TYPE_MASK) {
+                WgAllowedIpAttr::Family => {
+                    family = Some(Af::from(payload_as::<u16, _>(attr)? as u8));
+                },
+                WgAllowedIpAttr::Ipaddr => { addr = Some(attr.payload_bytes().to_vec()); },
+                WgAllowedIpAttr::CidrMask => { cidr_mask = Some(payload_as::<u8, _>(attr)?); },
+                _ => (),
+            };
+            Ok(())
+        })?;
+        let family = family.ok_or_else(|| DeError::new("Allowed IP is missing its family"))?;
+        let bytes = addr.ok_or_else(|| DeError::new("Allowed IP is missing its address"))?;
+        let addr = IpAddr::deserialize_with(&mut StreamReadBuffer::new(bytes.as_slice()), family)?;
+        if addr.size() != bytes.len() {
+            return Err(DeError::new("Allowed IP address does not match its family"));
+        }
+        let cidr_mask = cidr_mask
+            .ok_or_else(|| DeError::new("Allowed IP is missing its CIDR mask"))?;
+        Ok(WgAllowedIp { addr, cidr_mask })
+    }
+
+    /// Build the attributes of this range for an entry of `WgPeerAttr::Allowedips`
+    pub fn to_attrs(&self) -> Result<Vec<Nlattr<WgAllowedIpAttr>>, SerError> {
+        let family = match self.addr {
+            IpAddr::V4(_) => Af::Inet,
+            IpAddr::V6(_) => Af::Inet6,
+        };
+        Ok(vec![
+            Nlattr::new_nl_payload(None, WgAllowedIpAttr::Family, u16::from(u8::from(family)))?,
+            Nlattr::new_ip_payload(None, WgAllowedIpAttr::Ipaddr, self.addr),
+            Nlattr::new_nl_payload(None, WgAllowedIpAttr::CidrMask, self.cidr_mask)?,
+        ])
+    }
+}
+
+/// Peer of a WireGuard device corresponding to one entry of `WgDeviceAttr::Peers`
+#[derive(Clone,Debug,PartialEq)]
+pub struct WgPeer {
+    /// Public key identifying the peer
+    pub public_key: WgKey,
+    /// Preshared key - `None` if the kernel did not report one or it is unset
+    pub preshared_key: Option<WgKey>,
+    /// Last known address of the peer
+    pub endpoint: Option<SocketAddr>,
+    /// Keepalive interval in seconds - 0 if disabled
+    pub persistent_keepalive_interval: u16,
+    /// Time of the last handshake since the Unix epoch - zero if there has been none
+    pub last_handshake_time: Duration,
+    /// Bytes received from the peer
+    pub rx_bytes: u64,
+    /// Bytes sent to the peer
+    pub tx_bytes: u64,
+    /// Address ranges routed to the peer
+    pub allowed_ips: Vec<WgAllowedIp>,
+    /// Version of the WireGuard protocol used with the peer
+    pub protocol_version: u32,
+}
+
+impl WgPeer {
+    /// Parse the attributes of one entry of `WgDeviceAttr::Peers`
+    pub fn from_attr_handle(handle: AttrHandle<WgPeerAttr>) -> Result<Self, DeError> {
+        let mut public_key = None;
+        let mut peer = WgPeer {
+            public_key: WgKey([0; WG_KEY_LEN]),
+            preshared_key: None,
+            endpoint: None,
+            persistent_keepalive_interval: 0,
+            last_handshake_time: Duration::from_secs(0),
+            rx_bytes: 0,
+            tx_bytes: 0,
+            allowed_ips: Vec::new(),
+            protocol_version: 0,
+        };
+        handle.parse_with(|attr| {
+            match WgPeerAttr::from(u16::from(&attr.nla_type) & NLA_TYPE_MASK) {
+                WgPeerAttr::PublicKey => {
+                    public_key = Some(WgKey::from_payload(attr.payload_bytes())?);
+                },
+                WgPeerAttr::PresharedKey => {
+                    let key = WgKey::from_payload(attr.payload_bytes())?;
+                    peer.preshared_key = if key.is_zero() { None } else { Some(key) };
+                },
+                WgPeerAttr::Endpoint => {
+                    peer.endpoint = Some(parse_sockaddr(attr.payload_bytes())?);
+                },
+                WgPeerAttr::PersistentKeepaliveInterval => {
+                    peer.persistent_keepalive_interval = payload_as(attr)?;
+                },
+                WgPeerAttr::LastHandshakeTime => {
+                    peer.last_handshake_time = parse_kernel_timespec(attr.payload_bytes())?;
+                },
+                WgPeerAttr::RxBytes => { peer.rx_bytes = payload_as(attr)?; },
+                WgPeerAttr::TxBytes => { peer.tx_bytes = payload_as(attr)?; },
+                WgPeerAttr::Allowedips => {
+                    attr.get_attr_handle::<u16>().parse_with(|entry| {
+                        peer.allowed_ips.push(WgAllowedIp::from_attr_handle(
+                            entry.get_attr_handle()
+                        )?);
+                        Ok(())
+                    })?;
+                },
+                WgPeerAttr::ProtocolVersion => { peer.protocol_version = payload_as(attr)?; },
+                _ => (),
+            };
+            Ok(())
+        })?;
+        peer.public_key = public_key
+            .ok_or_else(|| DeError::new("Peer is missing its public key"))?;
+        Ok(peer)
+    }
+}
+
+/// WireGuard device as reported by `WgCmd::GetDevice`
+#[derive(Clone,Debug,PartialEq)]
+pub struct WgDevice {
+    /// Interface index of the device
+    pub ifindex: u32,
+    /// Interface name of the device
+    pub ifname: String,
+    /// Private key of the device - `None` if unset
+    pub private_key: Option<WgKey>,
+    /// Public key derived from the private key - `None` if unset
+    pub public_key: Option<WgKey>,
+    /// UDP port the device listens on
+    pub listen_port: u16,
+    /// Firewall mark of outgoing packets - 0 if unset
+    pub fwmark: u32,
+    /// Peers of the device
+    pub peers: Vec<WgPeer>,
+}
+
+impl WgDevice {
+    /// Parse the top level attributes of a `WgCmd::GetDevice` reply
+    pub fn from_attr_handle(handle: AttrHandle<WgDeviceAttr>) -> Result<Self, DeError> {
+        let mut dev = WgDevice {
+            ifindex: 0,
+            ifname: String::new(),
+            private_key: None,
+            public_key: None,
+            listen_port: 0,
+            fwmark: 0,
+            peers: Vec::new(),
+        };
+        handle.parse_with(|attr| {
+            match WgDeviceAttr::from(u16::from(&attr.nla_type) & NLA_TYPE_MASK) {
+                WgDeviceAttr::Ifindex => { dev.ifindex = payload_as(attr)?; },
+                WgDeviceAttr::Ifname => { dev.ifname = attr.get_payload_as_str()?.to_string(); },
+                WgDeviceAttr::PrivateKey => {
+                    dev.private_key = Some(WgKey::from_payload(attr.payload_bytes())?);
+                },
+                WgDeviceAttr::PublicKey => {
+                    dev.public_key = Some(WgKey::from_payload(attr.payload_bytes())?);
+                },
+                WgDeviceAttr::ListenPort => { dev.listen_port = payload_as(attr)?; },
+                WgDeviceAttr::Fwmark => { dev.fwmark = payload_as(attr)?; },
+                WgDeviceAttr::Peers => {
+                    attr.get_attr_handle::<u16>().parse_with(|entry| {
+                        dev.peers.push(WgPeer::from_attr_handle(entry.get_attr_handle())?);
+                        Ok(())
+                    })?;
+                },
+                _ => (),
+            };
+            Ok(())
+        })?;
+        Ok(dev)
+    }
+}
+
+fn payload_as<R, T>(attr: &Nlattr<T>) -> Result<R, DeError>
+        where R: Nl, T: Nl + Into<u16> + From<u16> {
+    R::deserialize(&mut StreamReadBuffer::new(attr.payload_bytes()))
+}
+
+// The last handshake time is a struct __kernel_timespec of two 64 bit fields on every
+// architecture, unlike libc::timespec
+fn parse_kernel_timespec(bytes: &[u8]) -> Result<Duration, DeError> {
+    let mut mem = StreamReadBuffer::new(bytes);
+    let secs = i64::deserialize(&mut mem)?;
+    let nsecs = i64::deserialize(&mut mem)?;
+    if secs < 0 || nsecs < 0 || nsecs >= 1_000_000_000 {
+        return Err(DeError::new("Timespec does not represent a valid duration"));
+    }
+    Ok(Duration::new(secs as u64, nsecs as u32))
+}
+
+// Endpoints are a struct sockaddr_in or struct sockaddr_in6 with the family in host byte
+// order and the port in network byte order
+fn parse_sockaddr(bytes: &[u8]) -> Result<SocketAddr, DeError> {
+    let mut mem = StreamReadBuffer::new(bytes);
+    let family = Af::from(u16::deserialize(&mut mem)? as u8);
+    let mut port = [0u8; 2];
+    mem.read_exact(&mut port)?;
+    let port = u16::from(port[0]) << 8 | u16::from(port[1]);
+    match family {
+        Af::Inet if bytes.len() >= 16 => {
+            Ok(SocketAddr::new(IpAddr::deserialize_with(&mut mem, family)?, port))
+        },
+        Af::Inet6 if bytes.len() >= 28 => {
+            // sin6_flowinfo precedes the address
+            u32::deserialize(&mut mem)?;
+            Ok(SocketAddr::new(IpAddr::deserialize_with(&mut mem, family)?, port))
+        },
+        _ => Err(DeError::new("Unsupported or truncated endpoint address")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{Ipv4Addr,Ipv6Addr};
+
+    use consts::wireguard::{WgCmd,WGDEVICE_F_REPLACE_PEERS,WG_GENL_VERSION};
+    use genl::Genlmsghdr;
+
+    fn peer_attrs() -> Vec<Nlattr<WgPeerAttr>> {
+        let endpoint = vec![2, 0, 0xca, 0x6c, 192, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+        let allowed_ips = vec![
+            Nlattr::new_nested(None, 0u16,
+                WgAllowedIp::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 24).to_attrs().unwrap()
+            ).unwrap(),
+            Nlattr::new_nested(None, 1u16,
+                WgAllowedIp::new(IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0)), 64)
+                    .to_attrs().unwrap()
+            ).unwrap(),
+        ];
+        let mut handshake = 1_600_000_000i64.serialize_to_vec().unwrap();
+        handshake.extend(500i64.serialize_to_vec().unwrap());
+        let mut allowed = Nlattr::new_nested(None, WgPeerAttr::Allowedips, allowed_ips).unwrap();
+        allowed.nla_type = WgPeerAttr::from(u16::from(WgPeerAttr::Allowedips) | 1 << 15);
+        vec![
+            Nlattr::new_binary_payload(None, WgPeerAttr::PublicKey, vec![7; WG_KEY_LEN]),
+            Nlattr::new_binary_payload(None, WgPeerAttr::PresharedKey, vec![0; WG_KEY_LEN]),
+            Nlattr::new_binary_payload(None, WgPeerAttr::Endpoint, endpoint),
+            Nlattr::new_nl_payload(None, WgPeerAttr::PersistentKeepaliveInterval, 25u16).unwrap(),
+            Nlattr::new_nl_payload(None, WgPeerAttr::RxBytes, 1024u64).unwrap(),
+            Nlattr::new_binary_payload(None, WgPeerAttr::LastHandshakeTime, handshake),
+            allowed,
+        ]
+    }
+
+    #[test]
+    fn test_parse_device() {
+        let peers = vec![Nlattr::new_nested(None, 0u16, peer_attrs()).unwrap()];
+        let genl = Genlmsghdr::new(WgCmd::GetDevice, 1, vec![
+            Nlattr::new_nl_payload(None, WgDeviceAttr::Ifindex, 4u32).unwrap(),
+            Nlattr::new_str_payload(None, WgDeviceAttr::Ifname, "wg0").unwrap(),
+            Nlattr::new_binary_payload(None, WgDeviceAttr::PublicKey, vec![1; WG_KEY_LEN]),
+            Nlattr::new_nl_payload(None, WgDeviceAttr::ListenPort, 51820u16).unwrap(),
+            Nlattr::new_nested(None, WgDeviceAttr::Peers, peers).unwrap(),
+        ]).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        genl.serialize(&mut mem).unwrap();
+
+        let genl = Genlmsghdr::<WgCmd, WgDeviceAttr>::deserialize(
+            &mut StreamReadBuffer::new(mem.as_ref())
+        ).unwrap();
+        let dev = WgDevice::from_attr_handle(genl.get_attr_handle()).unwrap();
+        assert_eq!(dev.ifindex, 4);
+        assert_eq!(dev.ifname, "wg0");
+        assert_eq!(dev.private_key, None);
+        assert_eq!(dev.public_key, Some(WgKey([1; WG_KEY_LEN])));
+        assert_eq!(dev.listen_port, 51820);
+        assert_eq!(dev.peers.len(), 1);
+
+        let peer = &dev.peers[0];
+        assert_eq!(peer.public_key, WgKey([7; WG_KEY_LEN]));
+        assert_eq!(peer.preshared_key, None);
+        assert_eq!(peer.endpoint, Some("192.0.2.1:51820".parse().unwrap()));
+        assert_eq!(peer.persistent_keepalive_interval, 25);
+        assert_eq!(peer.rx_bytes, 1024);
+        assert_eq!(peer.last_handshake_time, Duration::new(1_600_000_000, 500));
+        assert_eq!(peer.allowed_ips, vec![
+            WgAllowedIp::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 24),
+            WgAllowedIp::new(IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0)), 64),
+        ]);
+    }
+
+    #[test]
+    fn test_device_attrs_round_trip() {
+        for &(ref attr, value) in &[(WgDeviceAttr::Ifindex, 1), (WgDeviceAttr::Ifname, 2),
+                                    (WgDeviceAttr::PrivateKey, 3), (WgDeviceAttr::PublicKey, 4),
+                                    (WgDeviceAttr::Flags, 5), (WgDeviceAttr::ListenPort, 6),
+                                    (WgDeviceAttr::Fwmark, 7), (WgDeviceAttr::Peers, 8)] {
+            assert_eq!(u16::from(attr), value);
+            assert_eq!(&WgDeviceAttr::from(value), attr);
+        }
+        assert_eq!(u8::from(WgCmd::SetDevice), 1);
+
+        let genl = Genlmsghdr::new(WgCmd::SetDevice, WG_GENL_VERSION, vec![
+            Nlattr::new_str_payload(None, WgDeviceAttr::Ifname, "wg0").unwrap(),
+            Nlattr::new_binary_payload(None, WgDeviceAttr::PrivateKey, vec![2; WG_KEY_LEN]),
+            Nlattr::new_nl_payload(None, WgDeviceAttr::Flags, WGDEVICE_F_REPLACE_PEERS).unwrap(),
+            Nlattr::new_nl_payload(None, WgDeviceAttr::ListenPort, 51820u16).unwrap(),
+            Nlattr::new_nl_payload(None, WgDeviceAttr::Fwmark, 0x42u32).unwrap(),
+        ]).unwrap();
+        let bytes = genl.serialize_to_vec().unwrap();
+        let parsed = Genlmsghdr::<WgCmd, WgDeviceAttr>::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, genl);
+        let mut handle = parsed.get_attr_handle();
+        assert_eq!(handle.get_payload_with::<u32>(WgDeviceAttr::Fwmark, None).unwrap(), 0x42);
+        assert_eq!(handle.get_payload_with::<u32>(WgDeviceAttr::Flags, None).unwrap(),
+                   WGDEVICE_F_REPLACE_PEERS);
+    }
+
+    #[test]
+    fn test_key_len() {
+        assert!(WgKey::from_payload(&[0; 31]).is_err());
+        let key = WgKey::from_payload(&[3; WG_KEY_LEN]).unwrap();
+        let mut mem = StreamWriteBuffer::new_growable(None);
+        key.serialize(&mut mem).unwrap();
+        assert_eq!(mem.as_ref(), &[3; WG_KEY_LEN][..]);
+        assert_eq!(WgKey::deserialize(&mut StreamReadBuffer::new(mem.as_ref())).unwrap(), key);
+    }
+}